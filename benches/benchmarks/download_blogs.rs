@@ -1,8 +1,15 @@
 use criterion::{criterion_group, Criterion};
-use rss2email_lib::download_blogs;
+use rss2email_lib::{fetch::ReplayFetcher, pipeline::Pipeline};
+
+use super::RECORDINGS;
 
 pub fn criterion_benchmark(c: &mut Criterion) {
-  c.bench_function("download blogs", |b| b.iter(|| download_blogs(7)));
+  let fetcher = ReplayFetcher::new(RECORDINGS);
+  let pipeline = Pipeline::new(fetcher.urls().unwrap()).with_fetcher(fetcher);
+
+  c.bench_function("download blogs", |b| {
+    b.iter(|| pipeline.fetch_blocking().unwrap());
+  });
 }
 
 criterion_group! {
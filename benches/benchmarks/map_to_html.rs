@@ -15,12 +15,14 @@ fn mock_blog() -> Vec<Blog> {
     link: dummy_str.to_string(),
     description: Some(dummy_str.to_string()),
     pub_date: date_time,
+    ..Post::default()
   };
 
   vec![Blog {
     title: dummy_str.to_string(),
     most_recent_pub_date: date_time,
     posts: vec![p],
+    ..Blog::default()
   }]
 }
 
@@ -3,3 +3,6 @@
 pub mod download_blogs;
 pub mod get_page;
 pub mod map_to_html;
+
+/// The recorded responses the benchmarks are run against, see `tests/common`.
+const RECORDINGS: &str = "test-data/recordings";
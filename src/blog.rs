@@ -1,21 +1,70 @@
 use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
 
 /// Internal representation of a web feed.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
 pub struct Blog {
   pub title: String,
   pub most_recent_pub_date: DateTime<Utc>,
   pub posts: Vec<Post>,
+  /// The homepage of the site, from the RSS `link` or the Atom `alternate` link.
+  pub link: Option<String>,
+  /// The RSS `description` or Atom `subtitle` of the feed, as plain text.
+  pub description: Option<String>,
+  /// The URL of the icon of the site, from the RSS `image` or `itunes:image` or the Atom
+  /// `icon` or `logo`.
+  pub icon: Option<String>,
+  /// The RSS `language` or the `xml:lang` of the feed.
+  pub language: Option<String>,
+  /// The URL the feed was downloaded from.
+  pub feed_url: Option<String>,
 }
 
 /// Internal representation of a web feed post.
 ///
 /// The `pub_date` field will prefer the publication date
 /// and fallback to the last update date.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
 pub struct Post {
   pub title: String,
   pub link: String,
   pub description: Option<String>,
+  /// The full content of the post as cleaned HTML, from RSS `content:encoded` or Atom
+  /// `content`, or else from the RSS `description` or Atom `summary`. Its relative links
+  /// are resolved.
+  pub content: Option<String>,
   pub pub_date: DateTime<Utc>,
+  /// The RSS `category` or Atom `category@term` of the post.
+  pub categories: Vec<String>,
+  /// The names of the RSS `author` and `dc:creator` or the Atom `author`s of the post.
+  pub authors: Vec<String>,
+  /// The link to the comments of the post, from RSS `comments` or an Atom `replies` link.
+  pub comments: Option<String>,
+  /// The RSS `guid` or Atom `id` of the post.
+  pub id: Option<String>,
+  /// The URLs of the feeds the post was found in, more than one if it was syndicated.
+  pub feeds: Vec<String>,
+  /// The files attached to the post, such as the audio of a podcast episode.
+  pub enclosures: Vec<Enclosure>,
+  /// The URL of an image representing the post.
+  pub thumbnail: Option<String>,
+}
+
+/// A file attached to a post, from an RSS `enclosure`, an Atom `enclosure` link or a
+/// Media RSS `media:content`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Enclosure {
+  pub url: String,
+  pub mime_type: Option<String>,
+  /// The size of the file, in bytes.
+  pub length: Option<u64>,
+  /// The duration of audio and video files, in seconds.
+  pub duration: Option<u64>,
+}
+
+/// A web feed that could not be downloaded or parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FailedFeed {
+  pub url: String,
+  pub error: String,
 }
@@ -0,0 +1,325 @@
+//! The command line interface of the binary.
+
+use std::{
+  fs,
+  io::{ErrorKind, Write as _},
+  path::{Path, PathBuf},
+};
+
+use clap::{Args, Parser, Subcommand};
+use rss2email_lib::{
+  config::{split_addresses, Config},
+  parse_feeds, time_func,
+};
+
+/// An RSS feed aggregator that notifies you of new posts via email.
+///
+/// Settings are read from environment variables (or a `.env` file) and can be
+/// overridden with the options below.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+#[allow(clippy::doc_markdown)]
+pub struct Cli {
+  /// The TOML config file [env: CONFIG_FILE] [default: rss2email.toml]
+  #[arg(long, global = true, value_name = "FILE")]
+  pub config: Option<PathBuf>,
+
+  /// Runs only this profile of the config file, instead of all of them
+  #[arg(short, long, global = true)]
+  pub profile: Option<String>,
+
+  #[command(flatten)]
+  pub overrides: Overrides,
+
+  #[command(subcommand)]
+  pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// Fetches the feeds and sends the digest email. This is the default.
+  Run {
+    /// Does everything except sending the email, which is logged instead.
+    #[arg(long)]
+    dry_run: bool,
+  },
+  /// Fetches the feeds and renders the digest email without sending it.
+  Preview {
+    /// Writes the email to a file instead of stdout.
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+    /// Renders the plain-text body instead of the HTML one.
+    #[arg(long)]
+    text: bool,
+  },
+  /// Fetches the feeds and prints the parsed blogs.
+  Fetch {
+    /// Prints the blogs as JSON.
+    #[arg(long)]
+    json: bool,
+  },
+  /// Stays resident and sends the digest email on a schedule.
+  Daemon {
+    /// A cron expression such as "0 7 * * MON-FRI" [env: SCHEDULE]
+    #[arg(long)]
+    schedule: Option<String>,
+    /// The IANA timezone the schedule is evaluated in [env: TIMEZONE] [default: UTC]
+    #[arg(long)]
+    timezone: Option<String>,
+    /// Logs the email instead of sending it.
+    #[arg(long)]
+    dry_run: bool,
+  },
+  /// Checks the configuration without fetching anything.
+  ValidateConfig,
+  /// Manages the feeds file.
+  #[command(subcommand)]
+  Feeds(FeedsCommand),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum FeedsCommand {
+  /// Lists the feeds.
+  List,
+  /// Adds a feed to the feeds file.
+  Add { url: String },
+  /// Removes a feed from the feeds file.
+  Remove { url: String },
+}
+
+/// Options that take precedence over their environment variables.
+// Doc comments are the `--help` text, so environment variables are not in backticks.
+#[allow(clippy::doc_markdown)]
+#[derive(Debug, Args)]
+pub struct Overrides {
+  /// How many days in the past to look for posts [env: DAYS]
+  #[arg(long, global = true, conflicts_with = "window")]
+  days: Option<i64>,
+  /// A duration such as 36h, a calendar period such as yesterday, or since-last-run,
+  /// taking precedence over DAYS [env: WINDOW]
+  #[arg(long, global = true)]
+  window: Option<String>,
+  /// Reads the feeds from this file, ignoring FEEDS [default: feeds.txt]
+  #[arg(long, global = true, value_name = "FILE")]
+  feeds_file: Option<PathBuf>,
+  /// The address the email is sent from [env: EMAIL_ADDRESS]
+  #[arg(long, global = true, value_name = "ADDRESS")]
+  from: Option<String>,
+  /// Comma delimited list of recipient addresses [env: RECIPIENT_ADDRESSES]
+  #[arg(long, global = true, value_name = "ADDRESSES")]
+  to: Option<String>,
+  /// The email subject [env: SUBJECT]
+  #[arg(long, global = true)]
+  subject: Option<String>,
+  /// The email provider [env: EMAIL]
+  #[arg(long, global = true)]
+  provider: Option<String>,
+  /// The maximum length of post descriptions [env: DESCRIPTION_LENGTH]
+  #[arg(long, global = true, value_name = "CHARS")]
+  description_length: Option<usize>,
+  /// Skips sending the email if there are no new posts, --skip-if-no-new-posts=false
+  /// sends it anyway [env: SKIP_IF_NO_NEW_POSTS]
+  #[arg(
+    long,
+    global = true,
+    value_name = "BOOL",
+    num_args = 0..=1,
+    require_equals = true,
+    default_missing_value = "true"
+  )]
+  skip_if_no_new_posts: Option<bool>,
+  /// Shows the full content of posts instead of their description [env: FULL_TEXT]
+  #[arg(long, global = true)]
+  full_text: bool,
+  /// One of light, dark or compact [env: THEME]
+  #[arg(long, global = true)]
+  theme: Option<String>,
+  /// A stylesheet applied on top of the theme [env: CUSTOM_CSS]
+  #[arg(long, global = true, value_name = "FILE")]
+  css: Option<PathBuf>,
+  /// The template of the HTML body [env: HTML_TEMPLATE]
+  #[arg(long, global = true, value_name = "FILE")]
+  html_template: Option<PathBuf>,
+  /// The template of the plain-text body [env: TEXT_TEMPLATE]
+  #[arg(long, global = true, value_name = "FILE")]
+  text_template: Option<PathBuf>,
+}
+
+impl Overrides {
+  /// Replaces the values of `config` with the ones passed on the command line.
+  pub fn apply(&self, config: &mut Config) {
+    if let Some(days) = self.days {
+      config.days = days;
+      config.window = None;
+      config.clear_invalid_var("DAYS");
+    }
+    if self.window.is_some() {
+      config.window.clone_from(&self.window);
+    }
+    if let Some(feeds_file) = &self.feeds_file {
+      config.feeds = None;
+      config.feeds_file.clone_from(feeds_file);
+    }
+    if self.from.is_some() {
+      config.sender_address.clone_from(&self.from);
+    }
+    if let Some(to) = &self.to {
+      config.recipient_addresses = split_addresses(to);
+    }
+    if self.subject.is_some() {
+      config.subject.clone_from(&self.subject);
+    }
+    if let Some(provider) = &self.provider {
+      config.email_provider.clone_from(provider);
+    }
+    if let Some(description_length) = self.description_length {
+      config.description_length = description_length;
+      config.clear_invalid_var("DESCRIPTION_LENGTH");
+    }
+    if let Some(skip_if_no_new_posts) = self.skip_if_no_new_posts {
+      config.skip_if_no_new_posts = skip_if_no_new_posts;
+    }
+    if self.full_text {
+      config.full_text = true;
+    }
+    if self.theme.is_some() {
+      config.theme.clone_from(&self.theme);
+    }
+    if self.css.is_some() {
+      config.custom_css.clone_from(&self.css);
+    }
+    if self.html_template.is_some() {
+      config.html_template.clone_from(&self.html_template);
+    }
+    if self.text_template.is_some() {
+      config.text_template.clone_from(&self.text_template);
+    }
+  }
+}
+
+/// Fetches the feeds and prints the resulting blogs to stdout.
+pub fn fetch(config: &Config, json: bool) -> Result<(), String> {
+  let pipeline = config.fetch_pipeline().map_err(|e| e.to_string())?;
+  let digest = time_func(
+    || pipeline.fetch_blocking().map_err(|e| e.to_string()),
+    "download_blogs",
+  )?;
+
+  let mut out = std::io::stdout().lock();
+
+  if json {
+    let json = serde_json::to_string_pretty(&digest).map_err(|e| e.to_string())?;
+    return writeln!(out, "{json}").map_err(|e| e.to_string());
+  }
+
+  for blog in &digest.blogs {
+    let _ = writeln!(out, "{} ({} posts)", blog.title, blog.posts.len());
+    for post in &blog.posts {
+      let _ = writeln!(out, "  {}  {}", post.pub_date.date_naive(), post.title);
+      let _ = writeln!(out, "              {}", post.link);
+    }
+  }
+
+  for feed in &digest.failed_feeds {
+    let _ = writeln!(out, "Failed: {}\n  {}", feed.url, feed.error);
+  }
+
+  Ok(())
+}
+
+/// Checks that everything needed to send the email is set for every profile,
+/// printing every problem found.
+pub fn validate_config(configs: &[Config]) -> Result<(), String> {
+  let mut errors = 0;
+
+  for config in configs {
+    let validation = config.validate();
+    errors += validation.errors.len();
+    let _ = crate::check(config, &validation);
+  }
+
+  if errors > 0 {
+    return Err(format!("Found {errors} configuration error(s)"));
+  }
+
+  println!("Configuration is valid.");
+  Ok(())
+}
+
+/// Runs one of the `feeds` subcommands.
+pub fn feeds(config: &Config, command: FeedsCommand) -> Result<(), String> {
+  if !matches!(command, FeedsCommand::List) && config.feeds.is_some() {
+    return Err(format!(
+      "Feeds are set by FEEDS or the config file and take precedence over {}, edit them instead or pass --feeds-file.",
+      config.feeds_file.display()
+    ));
+  }
+
+  match command {
+    FeedsCommand::List => {
+      for feed in config.feeds().map_err(|e| e.to_string())? {
+        println!("{feed}");
+      }
+    }
+    FeedsCommand::Add { url } => {
+      if add_feed(&config.feeds_file, &url).map_err(|e| e.to_string())? {
+        println!("Added {url} to {}", config.feeds_file.display());
+      } else {
+        println!("{url} is already in {}", config.feeds_file.display());
+      }
+    }
+    FeedsCommand::Remove { url } => {
+      if !remove_feed(&config.feeds_file, &url).map_err(|e| e.to_string())? {
+        return Err(format!("{url} is not in {}", config.feeds_file.display()));
+      }
+      println!("Removed {url} from {}", config.feeds_file.display());
+    }
+  }
+
+  Ok(())
+}
+
+/// Appends `url` to the feeds file, creating it if needed.
+///
+/// Returns `false` if the feed was already present.
+fn add_feed(path: &Path, url: &str) -> std::io::Result<bool> {
+  let mut contents = match fs::read_to_string(path) {
+    Ok(contents) => contents,
+    Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+    Err(e) => return Err(e),
+  };
+
+  if parse_feeds(&contents).iter().any(|feed| feed == url) {
+    return Ok(false);
+  }
+
+  if !contents.is_empty() && !contents.ends_with('\n') {
+    contents.push('\n');
+  }
+  contents.push_str(url);
+  contents.push('\n');
+
+  fs::write(path, contents).map(|()| true)
+}
+
+/// Removes the lines of the feeds file whose feed is exactly `url`, keeping comments and
+/// the other feeds.
+///
+/// Returns `false` if the feed was not present.
+fn remove_feed(path: &Path, url: &str) -> std::io::Result<bool> {
+  let contents = fs::read_to_string(path)?;
+
+  let kept = contents
+    .lines()
+    .filter(|line| parse_feeds(line).first().map(String::as_str) != Some(url))
+    .collect::<Vec<_>>();
+
+  if kept.len() == contents.lines().count() {
+    return Ok(false);
+  }
+
+  let mut contents = kept.join("\n");
+  contents.push('\n');
+
+  fs::write(path, contents).map(|()| true)
+}
@@ -0,0 +1,295 @@
+//! Settings and profiles read from a TOML file.
+//!
+//! Every key of the file is optional and overrides the matching environment variable.
+//! A file can define several profiles, each sent as its own digest:
+//!
+//! ```toml
+//! # Shared by every profile.
+//! [defaults]
+//! sender_address = "rss2email@example.org"
+//! email_provider = "resend"
+//! timezone = "Europe/Athens"
+//!
+//! # Named lists of feeds that profiles pick from.
+//! [categories]
+//! news = ["https://example.org/news.xml", "https://example.com/rss"]
+//! blogs = ["https://blog.rust-lang.org/feed.xml"]
+//!
+//! [profiles.daily]
+//! categories = ["news"]
+//! days = 1
+//! recipient_addresses = ["me@example.org"]
+//! subject = "Today's news: $POST_COUNT posts"
+//! schedule = "0 7 * * *"
+//!
+//! [profiles.weekly]
+//! categories = ["blogs"]
+//! feeds = ["https://example.net/atom.xml"]
+//! recipient_addresses = ["me@example.org", "team@example.org"]
+//! subject = "This week's posts"
+//! theme = "compact"
+//! schedule = "0 9 * * MON"
+//!
+//! # Only applies to the weekly digest.
+//! [[profiles.weekly.filters]]
+//! exclude = [{ category = "release notes" }]
+//! ```
+//!
+//! A profile that selects neither `categories` nor `feeds` gets every feed of every
+//! category, or the feeds of the environment if there are no categories.
+//!
+//! The `filters` of a profile are applied on top of the ones of `[defaults]`, see
+//! [`filter`](crate::filter) for their format, and so are its link `rewrites`, see
+//! [`link`](crate::link).
+
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use itertools::Itertools;
+use serde_derive::Deserialize;
+
+use super::Config;
+use crate::{filter::FilterRule, link::RewriteRule, Error};
+
+/// The file settings are read from if `CONFIG_FILE` is not set, if it exists.
+pub const DEFAULT_CONFIG_FILE: &str = "rss2email.toml";
+
+/// The contents of a config file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+  /// Settings shared by every profile.
+  pub defaults: Settings,
+  /// Named lists of feeds that profiles can select.
+  pub categories: BTreeMap<String, Vec<String>>,
+  /// Named sets of settings, each sent as its own digest.
+  pub profiles: BTreeMap<String, Settings>,
+}
+
+/// The settings of the config file, each overriding the matching field of [`Config`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+  /// The categories whose feeds are included.
+  pub categories: Option<Vec<String>>,
+  /// Feeds included on top of `categories`.
+  pub feeds: Option<Vec<String>>,
+  pub feeds_file: Option<PathBuf>,
+  pub days: Option<i64>,
+  pub window: Option<String>,
+  pub future_tolerance: Option<String>,
+  pub future_posts: Option<String>,
+  pub sender_address: Option<String>,
+  pub recipient_addresses: Option<Vec<String>>,
+  pub subject: Option<String>,
+  pub email_provider: Option<String>,
+  pub skip_if_no_new_posts: Option<bool>,
+  pub description_length: Option<usize>,
+  pub full_text: Option<bool>,
+  pub theme: Option<String>,
+  pub custom_css: Option<PathBuf>,
+  pub html_template: Option<PathBuf>,
+  pub text_template: Option<PathBuf>,
+  pub schedule: Option<String>,
+  pub timezone: Option<String>,
+  pub state_backend: Option<String>,
+  pub state_dir: Option<PathBuf>,
+  pub s3_bucket: Option<String>,
+  pub s3_endpoint: Option<String>,
+  pub s3_region: Option<String>,
+  pub s3_prefix: Option<String>,
+  /// Appended to the filters of the defaults, see [`filter`](crate::filter).
+  pub filters: Option<Vec<FilterRule>>,
+  pub strip_params: Option<Vec<String>>,
+  pub unwrap_redirects: Option<bool>,
+  /// Appended to the rewrites of the defaults, see [`link`](crate::link).
+  pub rewrites: Option<Vec<RewriteRule>>,
+}
+
+impl ConfigFile {
+  /// Reads the file at `path`, or the one pointed to by `CONFIG_FILE` if `None`.
+  ///
+  /// Falls back to [`DEFAULT_CONFIG_FILE`] and returns `None` if that does not exist.
+  pub fn find(path: Option<&Path>) -> Result<Option<Self>, Error> {
+    let path = path
+      .map(Path::to_path_buf)
+      .or_else(|| std::env::var("CONFIG_FILE").ok().map(PathBuf::from));
+
+    match path {
+      Some(path) => Self::read(&path).map(Some),
+      None if Path::new(DEFAULT_CONFIG_FILE).is_file() => {
+        Self::read(Path::new(DEFAULT_CONFIG_FILE)).map(Some)
+      }
+      None => Ok(None),
+    }
+  }
+
+  /// Reads and parses the file at `path`.
+  pub fn read(path: &Path) -> Result<Self, Error> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+      Error::Generic(format!(
+        "Could not read config file {}: {e}",
+        path.display()
+      ))
+    })?;
+
+    Self::parse(&contents)
+      .map_err(|e| Error::Generic(format!("Invalid config file {}: {e}", path.display())))
+  }
+
+  /// Parses the contents of a config file.
+  pub fn parse(contents: &str) -> Result<Self, Error> {
+    toml::from_str(contents).map_err(|e| Error::Generic(e.to_string()))
+  }
+
+  /// The config of the profile called `name`, on top of `base`.
+  pub fn profile(&self, base: &Config, name: &str) -> Result<Config, Error> {
+    let profile = self.profiles.get(name).ok_or_else(|| {
+      Error::Generic(format!(
+        "Unknown profile '{name}', expected one of {}",
+        self.profiles.keys().join(", ")
+      ))
+    })?;
+
+    let mut config = self.apply(self.defaults(base)?, profile)?;
+    config.profile = Some(name.to_owned());
+    Ok(config)
+  }
+
+  /// The configs of every profile, or of the defaults if there are no profiles.
+  pub fn configs(&self, base: &Config) -> Result<Vec<Config>, Error> {
+    if self.profiles.is_empty() {
+      return Ok(vec![self.defaults(base)?]);
+    }
+
+    self
+      .profiles
+      .keys()
+      .map(|name| self.profile(base, name))
+      .collect()
+  }
+
+  /// `base` with the defaults of the file applied.
+  pub(super) fn defaults(&self, base: &Config) -> Result<Config, Error> {
+    let mut config = base.clone();
+
+    if !self.categories.is_empty() {
+      config.feeds = Some(
+        self
+          .categories
+          .values()
+          .flatten()
+          .unique()
+          .cloned()
+          .collect(),
+      );
+    }
+
+    self.apply(config, &self.defaults)
+  }
+
+  /// Overrides the fields of `config` that are set in `settings`.
+  fn apply(&self, mut config: Config, settings: &Settings) -> Result<Config, Error> {
+    if settings.categories.is_some() || settings.feeds.is_some() {
+      let mut feeds = Vec::new();
+
+      for category in settings.categories.iter().flatten() {
+        let category_feeds = self.categories.get(category).ok_or_else(|| {
+          Error::Generic(format!(
+            "Unknown category '{category}', expected one of {}",
+            self.categories.keys().join(", ")
+          ))
+        })?;
+        feeds.extend(category_feeds.iter().cloned());
+      }
+      feeds.extend(settings.feeds.iter().flatten().cloned());
+
+      config.feeds = Some(feeds.into_iter().unique().collect());
+    }
+
+    if let Some(feeds_file) = &settings.feeds_file {
+      config.feeds_file.clone_from(feeds_file);
+      if settings.categories.is_none() && settings.feeds.is_none() {
+        config.feeds = None;
+      }
+    }
+    if let Some(days) = settings.days {
+      config.days = days;
+      config.clear_invalid_var("DAYS");
+    }
+    if let Some(description_length) = settings.description_length {
+      config.description_length = description_length;
+      config.clear_invalid_var("DESCRIPTION_LENGTH");
+    }
+    if let Some(skip_if_no_new_posts) = settings.skip_if_no_new_posts {
+      config.skip_if_no_new_posts = skip_if_no_new_posts;
+    }
+    if let Some(full_text) = settings.full_text {
+      config.full_text = full_text;
+    }
+    if let Some(recipient_addresses) = &settings.recipient_addresses {
+      config.recipient_addresses.clone_from(recipient_addresses);
+    }
+    if let Some(email_provider) = &settings.email_provider {
+      config.email_provider.clone_from(email_provider);
+    }
+    if let Some(timezone) = &settings.timezone {
+      config.timezone.clone_from(timezone);
+    }
+    if let Some(state_backend) = &settings.state_backend {
+      config.state_backend.clone_from(state_backend);
+    }
+    if let Some(state_dir) = &settings.state_dir {
+      config.state_dir.clone_from(state_dir);
+    }
+    if let Some(s3_prefix) = &settings.s3_prefix {
+      config.s3_prefix.clone_from(s3_prefix);
+    }
+    if let Some(filters) = &settings.filters {
+      config.filters.extend(filters.iter().cloned());
+    }
+    if let Some(strip_params) = &settings.strip_params {
+      config.strip_params = Some(strip_params.clone());
+    }
+    if let Some(unwrap_redirects) = settings.unwrap_redirects {
+      config.unwrap_redirects = unwrap_redirects;
+    }
+    if let Some(rewrites) = &settings.rewrites {
+      config.rewrites.extend(rewrites.iter().cloned());
+    }
+
+    let options = [
+      (&mut config.sender_address, &settings.sender_address),
+      (&mut config.subject, &settings.subject),
+      (&mut config.window, &settings.window),
+      (&mut config.future_tolerance, &settings.future_tolerance),
+      (&mut config.future_posts, &settings.future_posts),
+      (&mut config.theme, &settings.theme),
+      (&mut config.schedule, &settings.schedule),
+      (&mut config.s3_bucket, &settings.s3_bucket),
+      (&mut config.s3_endpoint, &settings.s3_endpoint),
+      (&mut config.s3_region, &settings.s3_region),
+    ];
+    for (field, value) in options {
+      if value.is_some() {
+        field.clone_from(value);
+      }
+    }
+
+    let paths = [
+      (&mut config.custom_css, &settings.custom_css),
+      (&mut config.html_template, &settings.html_template),
+      (&mut config.text_template, &settings.text_template),
+    ];
+    for (field, value) in paths {
+      if value.is_some() {
+        field.clone_from(value);
+      }
+    }
+
+    Ok(config)
+  }
+}
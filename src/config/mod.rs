@@ -0,0 +1,788 @@
+//! Settings of a run.
+//!
+//! These are read from environment variables (or a `.env` file), then from an
+//! optional [config file](file) and can be overridden from the command line. [`Config::validate`] reports every problem
+//! with them at once, before anything is downloaded or sent.
+
+use chrono::Duration;
+use std::{
+  fmt::{self, Display},
+  path::{Path, PathBuf},
+  str::FromStr,
+  sync::Arc,
+};
+
+use crate::{
+  email::email_provider::{EmailProviders, PROVIDER_NAMES},
+  filter::{FilterRule, PostFilter},
+  link::{LinkResolver, LinkRewriter, RewriteRule, TRACKING_PARAMS},
+  parse_feeds,
+  pipeline::{
+    EmailDelivery, FeedSource, FeedsFile, HttpFetcher, Pipeline, RecentPosts, WebFeedParser,
+  },
+  schedule::{parse_cron, parse_timezone, Schedule},
+  state::{self, FsStore, MemoryStore, StateStore},
+  template::{Templates, Theme},
+  window::{parse_duration, FuturePosts, Window, DEFAULT_FUTURE_TOLERANCE, SINCE_LAST_RUN},
+  xml::{ParseOptions, DEFAULT_DESCRIPTION_LENGTH},
+  Error,
+};
+
+pub use self::file::ConfigFile;
+
+pub mod file;
+
+/// How many days in the past posts are fetched from by default.
+pub const DEFAULT_DAYS: i64 = 7;
+
+/// The file feeds are read from if `FEEDS` is not set.
+pub const DEFAULT_FEEDS_FILE: &str = "feeds.txt";
+
+/// The timezone schedules are evaluated in if `TIMEZONE` is not set.
+pub const DEFAULT_TIMEZONE: &str = "UTC";
+
+/// The directory of the `fs` state backend if `STATE_DIR` is not set.
+pub const DEFAULT_STATE_DIR: &str = ".rss2email";
+
+/// The names accepted by `STATE_BACKEND` (case insensitive).
+pub const STATE_BACKENDS: [&str; 4] = ["none", "fs", "s3", "memory"];
+
+/// All the settings of a run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+  /// The name of the profile of the config file these settings come from.
+  pub profile: Option<String>,
+  /// `DAYS`
+  pub days: i64,
+  /// `WINDOW`, takes precedence over `days` (see [`window`](crate::window)).
+  pub window: Option<String>,
+  /// `FUTURE_TOLERANCE`, how far in the future posts can be dated.
+  pub future_tolerance: Option<String>,
+  /// `FUTURE_POSTS`, whether posts dated beyond `future_tolerance` are dropped or clamped.
+  pub future_posts: Option<String>,
+  /// The feed URLs from `FEEDS` or the config file. Takes precedence over `feeds_file`.
+  pub feeds: Option<Vec<String>>,
+  /// The file to read feeds from, one per line.
+  pub feeds_file: PathBuf,
+  /// `EMAIL_ADDRESS`
+  pub sender_address: Option<String>,
+  /// `RECIPIENT_ADDRESSES`
+  pub recipient_addresses: Vec<String>,
+  /// `SUBJECT`
+  pub subject: Option<String>,
+  /// `EMAIL`
+  pub email_provider: String,
+  /// `SKIP_IF_NO_NEW_POSTS`
+  pub skip_if_no_new_posts: bool,
+  /// `DESCRIPTION_LENGTH`
+  pub description_length: usize,
+  /// `FULL_TEXT`, whether the email shows the full content of posts instead of their
+  /// description.
+  pub full_text: bool,
+  /// `THEME`
+  pub theme: Option<String>,
+  /// `CUSTOM_CSS`
+  pub custom_css: Option<PathBuf>,
+  /// `HTML_TEMPLATE`
+  pub html_template: Option<PathBuf>,
+  /// `TEXT_TEMPLATE`
+  pub text_template: Option<PathBuf>,
+  /// `SCHEDULE`, the cron expression of the daemon mode.
+  pub schedule: Option<String>,
+  /// `TIMEZONE`, the IANA timezone `schedule` is evaluated in.
+  pub timezone: String,
+  /// `STATE_BACKEND`, where state is kept between runs. One of [`STATE_BACKENDS`].
+  pub state_backend: String,
+  /// `STATE_DIR`, the directory of the `fs` state backend.
+  pub state_dir: PathBuf,
+  /// `S3_BUCKET`, the bucket of the `s3` state backend.
+  pub s3_bucket: Option<String>,
+  /// `S3_ENDPOINT`, defaults to the AWS endpoint of `s3_region`.
+  pub s3_endpoint: Option<String>,
+  /// `S3_REGION`, defaults to `AWS_REGION` or `us-east-1`.
+  pub s3_region: Option<String>,
+  /// `S3_PREFIX`, prepended to every key of the `s3` state backend.
+  pub s3_prefix: String,
+  /// The rules posts are filtered with, only read from the config file.
+  pub filters: Vec<FilterRule>,
+  /// `STRIP_PARAMS`, the query parameters removed from links, [`TRACKING_PARAMS`] if not
+  /// set. `default` stands for all of these.
+  pub strip_params: Option<Vec<String>>,
+  /// `UNWRAP_REDIRECTS`, whether known redirectors are removed from links.
+  pub unwrap_redirects: bool,
+  /// The rules links are rewritten with, only read from the config file.
+  pub rewrites: Vec<RewriteRule>,
+  /// Variables that could not be parsed and were replaced by their defaults.
+  pub invalid_vars: Vec<InvalidVar>,
+}
+
+/// An environment variable that is set to a value that could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidVar {
+  /// The name of the variable.
+  pub name: &'static str,
+  /// Its value.
+  pub value: String,
+  /// What the value should have been.
+  pub expected: &'static str,
+}
+
+impl Display for InvalidVar {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{} is set to '{}', expected {}",
+      self.name, self.value, self.expected
+    )
+  }
+}
+
+/// The errors and warnings found by [`Config::validate`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validation {
+  /// Problems that prevent a run.
+  pub errors: Vec<String>,
+  /// Problems that are likely mistakes but do not prevent a run.
+  pub warnings: Vec<String>,
+}
+
+impl Validation {
+  /// Whether no errors were found.
+  pub fn is_valid(&self) -> bool {
+    self.errors.is_empty()
+  }
+
+  /// Combines the problems of both validations.
+  #[must_use]
+  pub fn and(mut self, other: Self) -> Self {
+    self.errors.extend(other.errors);
+    self.warnings.extend(other.warnings);
+    self
+  }
+
+  fn error(&mut self, message: impl Into<String>) {
+    self.errors.push(message.into());
+  }
+
+  fn warning(&mut self, message: impl Into<String>) {
+    self.warnings.push(message.into());
+  }
+}
+
+impl Display for Validation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for error in &self.errors {
+      writeln!(f, "error: {error}")?;
+    }
+    for warning in &self.warnings {
+      writeln!(f, "warning: {warning}")?;
+    }
+    Ok(())
+  }
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      profile: None,
+      days: DEFAULT_DAYS,
+      window: None,
+      future_tolerance: None,
+      future_posts: None,
+      feeds: None,
+      feeds_file: PathBuf::from(DEFAULT_FEEDS_FILE),
+      sender_address: None,
+      recipient_addresses: Vec::new(),
+      subject: None,
+      email_provider: "SENDGRID".to_owned(),
+      skip_if_no_new_posts: false,
+      description_length: DEFAULT_DESCRIPTION_LENGTH,
+      full_text: false,
+      theme: None,
+      custom_css: None,
+      html_template: None,
+      text_template: None,
+      schedule: None,
+      timezone: DEFAULT_TIMEZONE.to_owned(),
+      state_backend: "none".to_owned(),
+      state_dir: PathBuf::from(DEFAULT_STATE_DIR),
+      s3_bucket: None,
+      s3_endpoint: None,
+      s3_region: None,
+      s3_prefix: String::new(),
+      filters: Vec::new(),
+      strip_params: None,
+      unwrap_redirects: true,
+      rewrites: Vec::new(),
+      invalid_vars: Vec::new(),
+    }
+  }
+}
+
+impl Config {
+  /// Reads the configuration from the environment, falling back to the defaults
+  /// for anything that is not set or cannot be parsed.
+  pub fn from_env() -> Self {
+    let defaults = Self::default();
+    let var = |key: &str| std::env::var(key).ok();
+    let mut invalid_vars = Vec::new();
+
+    Self {
+      profile: None,
+      days: parse_var("DAYS", "a whole number of days", &mut invalid_vars).unwrap_or(defaults.days),
+      window: var("WINDOW"),
+      future_tolerance: var("FUTURE_TOLERANCE"),
+      future_posts: var("FUTURE_POSTS"),
+      feeds: var("FEEDS").map(|feeds| parse_feeds(&feeds)),
+      feeds_file: defaults.feeds_file,
+      sender_address: var("EMAIL_ADDRESS"),
+      recipient_addresses: var("RECIPIENT_ADDRESSES")
+        .map(|v| split_addresses(&v))
+        .unwrap_or_default(),
+      subject: var("SUBJECT"),
+      email_provider: var("EMAIL").unwrap_or(defaults.email_provider),
+      skip_if_no_new_posts: var("SKIP_IF_NO_NEW_POSTS").is_some_and(|v| v.to_lowercase() == "true"),
+      description_length: parse_var(
+        "DESCRIPTION_LENGTH",
+        "a number of characters",
+        &mut invalid_vars,
+      )
+      .unwrap_or(defaults.description_length),
+      full_text: var("FULL_TEXT").is_some_and(|v| v.trim().to_lowercase() == "true"),
+      theme: var("THEME"),
+      custom_css: var("CUSTOM_CSS").map(PathBuf::from),
+      html_template: var("HTML_TEMPLATE").map(PathBuf::from),
+      text_template: var("TEXT_TEMPLATE").map(PathBuf::from),
+      schedule: var("SCHEDULE"),
+      timezone: var("TIMEZONE").unwrap_or(defaults.timezone),
+      state_backend: var("STATE_BACKEND").unwrap_or(defaults.state_backend),
+      state_dir: var("STATE_DIR").map_or(defaults.state_dir, PathBuf::from),
+      s3_bucket: var("S3_BUCKET"),
+      s3_endpoint: var("S3_ENDPOINT"),
+      s3_region: var("S3_REGION").or_else(|| var("AWS_REGION")),
+      s3_prefix: var("S3_PREFIX").unwrap_or_default(),
+      filters: defaults.filters,
+      strip_params: var("STRIP_PARAMS").map(|v| split_list(&v)),
+      unwrap_redirects: var("UNWRAP_REDIRECTS").map_or(defaults.unwrap_redirects, |v| {
+        v.trim().to_lowercase() != "false"
+      }),
+      rewrites: defaults.rewrites,
+      invalid_vars,
+    }
+  }
+
+  /// Reads the environment and the config file at `path` (see [`ConfigFile::find`]).
+  ///
+  /// Returns the config of `profile`, or of every profile of the file if `None`.
+  pub fn load(path: Option<&Path>, profile: Option<&str>) -> Result<Vec<Self>, Error> {
+    let base = Self::from_env();
+
+    match (ConfigFile::find(path)?, profile) {
+      (None, None) => Ok(vec![base]),
+      (None, Some(name)) => Err(Error::Generic(format!(
+        "Profile '{name}' was requested but there is no config file"
+      ))),
+      (Some(file), Some(name)) => Ok(vec![file.profile(&base, name)?]),
+      (Some(file), None) => file.configs(&base),
+    }
+  }
+
+  /// Reads the environment and the `[defaults]` of the config file at `path`, ignoring
+  /// its profiles.
+  pub fn load_defaults(path: Option<&Path>) -> Result<Self, Error> {
+    let base = Self::from_env();
+
+    match ConfigFile::find(path)? {
+      Some(file) => file.defaults(&base),
+      None => Ok(base),
+    }
+  }
+
+  /// Forgets that the variable `name` could not be parsed, once its value has been
+  /// replaced.
+  pub fn clear_invalid_var(&mut self, name: &str) {
+    self.invalid_vars.retain(|var| var.name != name);
+  }
+
+  /// Checks everything needed to fetch the feeds, render them and send the email.
+  pub fn validate(&self) -> Validation {
+    self
+      .validate_feeds()
+      .and(self.validate_templates())
+      .and(self.validate_email())
+      .and(self.validate_state())
+  }
+
+  /// Checks the settings needed to fetch the feeds.
+  pub fn validate_feeds(&self) -> Validation {
+    let mut validation = Validation::default();
+
+    for var in &self.invalid_vars {
+      validation.error(var.to_string());
+    }
+
+    match self.window.as_deref() {
+      None if self.days < 1 => {
+        validation.warning(format!("DAYS is {}, no posts will be included", self.days));
+      }
+      Some(window)
+        if window.trim().eq_ignore_ascii_case(SINCE_LAST_RUN)
+          && self.state_backend.trim().eq_ignore_ascii_case("none") =>
+      {
+        validation.warning(format!(
+          "WINDOW is {SINCE_LAST_RUN} but STATE_BACKEND is none, DAYS is used instead"
+        ));
+      }
+      Some(window) if window.trim().eq_ignore_ascii_case(SINCE_LAST_RUN) => {}
+      Some(window) => {
+        if let Err(e) = parse_timezone(&self.timezone).and_then(|tz| Window::parse(window, tz)) {
+          validation.error(e.to_string());
+        }
+      }
+      None => {}
+    }
+
+    if let Err(e) = self.future_tolerance() {
+      validation.error(e.to_string());
+    }
+    if let Err(e) = self.future_posts() {
+      validation.error(e.to_string());
+    }
+    for rule in &self.rewrites {
+      if let Err(e) = rule.check() {
+        validation.error(e);
+      }
+    }
+
+    match self.feeds() {
+      Ok(feeds) if feeds.is_empty() => validation.warning("No feeds are configured"),
+      Ok(feeds) => {
+        for feed in feeds {
+          let lower = feed.to_lowercase();
+          if !lower.starts_with("http://") && !lower.starts_with("https://") {
+            validation.warning(format!("'{feed}' is not an http(s) URL"));
+          }
+        }
+      }
+      Err(e) => validation.error(e.to_string()),
+    }
+
+    validation
+  }
+
+  /// Checks the theme, stylesheet and templates used to render the email.
+  pub fn validate_templates(&self) -> Validation {
+    let mut validation = Validation::default();
+
+    if let Some(theme) = &self.theme {
+      if let Err(e) = Theme::try_from(theme.clone()) {
+        validation.error(e);
+      }
+    }
+
+    let files = [
+      ("CUSTOM_CSS", &self.custom_css),
+      ("HTML_TEMPLATE", &self.html_template),
+      ("TEXT_TEMPLATE", &self.text_template),
+    ];
+
+    for (name, path) in files {
+      if let Some(path) = path.as_ref().filter(|path| !path.is_file()) {
+        validation.error(format!(
+          "{name} is set to {}, which is not a file",
+          path.display()
+        ));
+      }
+    }
+
+    if validation.is_valid() {
+      if let Err(e) = self.templates().and_then(|templates| templates.check()) {
+        validation.error(e.to_string());
+      }
+    }
+
+    validation
+  }
+
+  /// Checks the state backend.
+  pub fn validate_state(&self) -> Validation {
+    let mut validation = Validation::default();
+
+    if let Err(e) = self.state_store() {
+      validation.error(e.to_string());
+    }
+
+    validation
+  }
+
+  /// Checks the schedule of the daemon mode.
+  pub fn validate_schedule(&self) -> Validation {
+    let mut validation = Validation::default();
+
+    match &self.schedule {
+      Some(expression) => {
+        if let Err(e) = parse_cron(expression) {
+          validation.error(e.to_string());
+        }
+      }
+      None => validation.error("SCHEDULE must be set to run as a daemon"),
+    }
+
+    if let Err(e) = parse_timezone(&self.timezone) {
+      validation.error(e.to_string());
+    }
+
+    validation
+  }
+
+  /// Checks the addresses, subject and provider used to send the email.
+  pub fn validate_email(&self) -> Validation {
+    let mut validation = Validation::default();
+
+    match &self.sender_address {
+      None => validation.error("EMAIL_ADDRESS must be set"),
+      Some(address) if !is_email_address(address) => {
+        validation.error(format!(
+          "EMAIL_ADDRESS '{address}' is not a valid email address"
+        ));
+      }
+      Some(_) => {}
+    }
+
+    if self.recipient_addresses.is_empty() {
+      validation.error("RECIPIENT_ADDRESSES must be set");
+    }
+    for address in &self.recipient_addresses {
+      if !is_email_address(address) {
+        validation.error(format!(
+          "RECIPIENT_ADDRESSES contains '{address}', which is not a valid email address"
+        ));
+      }
+    }
+
+    if self.subject.is_none() {
+      validation.error("SUBJECT must be set");
+    }
+
+    match EmailProviders::required_env_vars(&self.email_provider) {
+      Some(vars) => {
+        for var in vars {
+          if std::env::var(var).map_or(true, |value| value.trim().is_empty()) {
+            validation.error(format!(
+              "{var} must be set when using the {} provider",
+              self.email_provider.trim().to_uppercase()
+            ));
+          }
+        }
+      }
+      None => validation.error(format!(
+        "EMAIL is set to '{}', expected one of {}",
+        self.email_provider,
+        PROVIDER_NAMES.join(", ")
+      )),
+    }
+
+    validation
+  }
+
+  /// The feed URLs, from `feeds` if set or read from the feeds file otherwise.
+  pub fn feeds(&self) -> Result<Vec<String>, Error> {
+    self.feeds.as_ref().map_or_else(
+      || FeedsFile::new(&self.feeds_file).feeds(),
+      |feeds| Ok(feeds.clone()),
+    )
+  }
+
+  /// A [`Pipeline`] that fetches the feeds from the last `days` days, filters them and
+  /// renders them with the configured templates.
+  ///
+  /// The feeds are read once, when the pipeline is created.
+  pub fn pipeline(&self) -> Result<Pipeline, Error> {
+    Ok(self.fetch_pipeline()?.with_renderer(self.templates()?))
+  }
+
+  /// Same as [`Config::pipeline`] with the built-in templates, for when the digest is
+  /// not rendered.
+  ///
+  /// Responses and resolved links are cached in the state store, if there is one.
+  pub fn fetch_pipeline(&self) -> Result<Pipeline, Error> {
+    let store: Option<Arc<dyn StateStore>> = self.state_store()?.map(Arc::from);
+    let fetcher = store.clone().map_or_else(HttpFetcher::default, |store| {
+      HttpFetcher::default().with_cache(store)
+    });
+
+    let pipeline = Pipeline::new(self.feeds()?)
+      .with_fetcher(fetcher)
+      .with_parser(WebFeedParser::new(self.parse_options()))
+      .with_filter(self.recent_posts()?);
+    let pipeline = if self.unwrap_redirects {
+      pipeline.with_filter(store.map_or_else(LinkResolver::new, |store| {
+        LinkResolver::new().with_cache(store)
+      }))
+    } else {
+      pipeline
+    };
+
+    Ok(
+      pipeline
+        .with_filter(self.link_rewriter())
+        .with_filter(PostFilter::new(self.filters.clone())),
+    )
+  }
+
+  /// Cleans up the links of the posts, before the `link` rules of the filters are matched.
+  pub fn link_rewriter(&self) -> LinkRewriter {
+    let rewriter = LinkRewriter::new()
+      .with_unwrap_redirects(self.unwrap_redirects)
+      .with_rules(self.rewrites.clone());
+
+    match &self.strip_params {
+      Some(params) => rewriter.with_strip_params(
+        params
+          .iter()
+          .flat_map(|param| {
+            if param.trim().eq_ignore_ascii_case("default") {
+              TRACKING_PARAMS.to_vec()
+            } else {
+              vec![param.trim()]
+            }
+          })
+          .map(ToOwned::to_owned)
+          .collect(),
+      ),
+      None => rewriter,
+    }
+  }
+
+  /// The time window posts are included from, `days` if `window` is not set.
+  ///
+  /// `since-last-run` is resolved with the last successful run of the profile in the
+  /// state store, and falls back to `days` if there is none.
+  pub fn window(&self) -> Result<Window, Error> {
+    let Some(window) = self.window.as_deref() else {
+      return Ok(Window::days(self.days));
+    };
+
+    if !window.trim().eq_ignore_ascii_case(SINCE_LAST_RUN) {
+      return Window::parse(window, parse_timezone(&self.timezone)?);
+    }
+
+    let last_run = match self.state_store()? {
+      Some(store) => state::last_successful_run(store.as_ref(), self.profile.as_deref())?,
+      None => None,
+    };
+
+    Ok(last_run.map_or_else(
+      || Window::days(self.days),
+      |run| Window::Since(run.started_at.unwrap_or(run.finished_at)),
+    ))
+  }
+
+  /// How far in the future posts can be dated.
+  pub fn future_tolerance(&self) -> Result<Duration, Error> {
+    let value = self
+      .future_tolerance
+      .as_deref()
+      .unwrap_or(DEFAULT_FUTURE_TOLERANCE);
+
+    parse_duration(value).map_err(|_e| {
+      Error::Generic(format!(
+        "FUTURE_TOLERANCE is set to '{value}', expected a duration such as 1h or 30m"
+      ))
+    })
+  }
+
+  /// What happens to posts dated beyond the future tolerance.
+  pub fn future_posts(&self) -> Result<FuturePosts, Error> {
+    self
+      .future_posts
+      .clone()
+      .map_or_else(|| Ok(FuturePosts::default()), FuturePosts::try_from)
+      .map_err(Error::Generic)
+  }
+
+  /// Keeps the posts of the [window](Config::window).
+  pub fn recent_posts(&self) -> Result<RecentPosts, Error> {
+    Ok(
+      RecentPosts::within(self.window()?)
+        .with_future_tolerance(self.future_tolerance()?)
+        .with_future_posts(self.future_posts()?),
+    )
+  }
+
+  /// Delivers the digest with the configured provider, sender, recipients and subject.
+  pub fn email_delivery(&self) -> Result<EmailDelivery, Error> {
+    let sender_address = self
+      .sender_address
+      .clone()
+      .ok_or_else(|| Error::Generic("EMAIL_ADDRESS must be set.".to_owned()))?;
+    if self.recipient_addresses.is_empty() {
+      return Err(Error::Generic(
+        "RECIPIENT_ADDRESSES must be set.".to_owned(),
+      ));
+    }
+    let subject = self
+      .subject
+      .clone()
+      .ok_or_else(|| Error::Generic("SUBJECT must be set.".to_owned()))?;
+    let provider = EmailProviders::try_from(self.email_provider.clone()).map_err(Error::Generic)?;
+
+    Ok(EmailDelivery::new(
+      provider,
+      sender_address,
+      self.recipient_addresses.clone(),
+      subject,
+    ))
+  }
+
+  /// The options used when parsing feeds, whose URL is set by the parser.
+  pub const fn parse_options(&self) -> ParseOptions {
+    ParseOptions {
+      description_length: self.description_length,
+      base_url: None,
+    }
+  }
+
+  /// The templates and styling used to render the email.
+  pub fn templates(&self) -> Result<Templates, Error> {
+    let mut templates = Templates::default().with_full_text(self.full_text);
+
+    if let Some(theme) = &self.theme {
+      templates = templates.with_theme(Theme::try_from(theme.clone()).map_err(Error::Generic)?);
+    }
+
+    if let Some(path) = &self.custom_css {
+      templates = templates.with_css_file(path)?;
+    }
+
+    if let Some(path) = &self.html_template {
+      templates = templates.with_html_file(path)?;
+    }
+
+    if let Some(path) = &self.text_template {
+      templates = templates.with_text_file(path)?;
+    }
+
+    Ok(templates)
+  }
+
+  /// The schedule of the daemon mode.
+  pub fn schedule(&self) -> Result<Schedule, Error> {
+    let expression = self
+      .schedule
+      .as_deref()
+      .ok_or_else(|| Error::Generic("SCHEDULE must be set to run as a daemon".to_owned()))?;
+
+    Schedule::new(expression, &self.timezone)
+  }
+
+  /// The store state is kept in between runs, or `None` if the backend is `none`.
+  ///
+  /// Creating a store does not touch the filesystem or the network.
+  pub fn state_store(&self) -> Result<Option<Box<dyn StateStore>>, Error> {
+    match self.state_backend.trim().to_lowercase().as_str() {
+      "none" => Ok(None),
+      "fs" => Ok(Some(Box::new(FsStore::new(&self.state_dir)))),
+      "memory" => Ok(Some(Box::new(MemoryStore::shared()))),
+      "s3" => self.s3_store().map(Some),
+      _ => Err(Error::Generic(format!(
+        "STATE_BACKEND is set to '{}', expected one of {}",
+        self.state_backend,
+        STATE_BACKENDS.join(", ")
+      ))),
+    }
+  }
+
+  #[cfg(feature = "s3")]
+  fn s3_store(&self) -> Result<Box<dyn StateStore>, Error> {
+    use crate::state::S3Store;
+
+    let bucket = self.s3_bucket.as_deref().ok_or_else(|| {
+      Error::Generic("S3_BUCKET must be set when using the s3 state backend".to_owned())
+    })?;
+    let region = self.s3_region.as_deref().unwrap_or("us-east-1");
+    let endpoint = self
+      .s3_endpoint
+      .clone()
+      .unwrap_or_else(|| format!("https://s3.{region}.amazonaws.com"));
+
+    Ok(Box::new(S3Store::new(
+      &endpoint,
+      region,
+      bucket,
+      &self.s3_prefix,
+      S3Store::credentials_from_env(),
+    )?))
+  }
+
+  #[cfg(not(feature = "s3"))]
+  #[allow(clippy::unused_self)]
+  fn s3_store(&self) -> Result<Box<dyn StateStore>, Error> {
+    Err(Error::Generic(
+      "The s3 state backend requires building rss2email with the s3 feature".to_owned(),
+    ))
+  }
+
+  /// The email subject with `$POST_COUNT` replaced.
+  pub fn subject(&self, post_count: usize) -> Option<String> {
+    self
+      .subject
+      .as_ref()
+      .map(|subject| subject.replace("$POST_COUNT", &post_count.to_string()))
+  }
+}
+
+/// Parses the environment variable `name`, recording it in `invalid_vars` if
+/// it is set to something that cannot be parsed.
+fn parse_var<T: FromStr>(
+  name: &'static str,
+  expected: &'static str,
+  invalid_vars: &mut Vec<InvalidVar>,
+) -> Option<T> {
+  let value = std::env::var(name).ok()?;
+
+  let parsed = value.trim().parse().ok();
+  if parsed.is_none() {
+    invalid_vars.push(InvalidVar {
+      name,
+      value,
+      expected,
+    });
+  }
+
+  parsed
+}
+
+/// A loose check that `address` looks like `local@domain.tld`, optionally
+/// written as `Name <local@domain.tld>`.
+pub fn is_email_address(address: &str) -> bool {
+  let address = address.trim();
+  let address = match address.strip_suffix('>').and_then(|a| a.rsplit_once('<')) {
+    Some((_name, address)) => address,
+    None => address,
+  };
+
+  let Some((local, domain)) = address.rsplit_once('@') else {
+    return false;
+  };
+
+  !local.is_empty()
+    && !address.contains(char::is_whitespace)
+    && domain.contains('.')
+    && domain.split('.').all(|part| !part.is_empty())
+}
+
+/// Splits a comma-delimited list of email addresses.
+pub fn split_addresses(addresses: &str) -> Vec<String> {
+  split_list(addresses)
+}
+
+/// Splits a comma-delimited list, skipping empty items.
+fn split_list(list: &str) -> Vec<String> {
+  list
+    .split(',')
+    .map(str::trim)
+    .filter(|item| !item.is_empty())
+    .map(ToOwned::to_owned)
+    .collect()
+}
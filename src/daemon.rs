@@ -0,0 +1,156 @@
+//! Keeps the binary resident and runs the digests on their schedules.
+
+use std::{pin::pin, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use rss2email_lib::{error, info, schedule::Schedule, warn};
+
+/// A digest sent every time its schedule fires.
+pub struct Job {
+  /// The name the job is logged as.
+  pub name: String,
+  pub schedule: Schedule,
+  pub run: Box<dyn Fn() -> Result<(), String> + Send + Sync>,
+}
+
+/// Runs every job when its schedule fires until SIGINT or SIGTERM is received.
+///
+/// Runs never overlap: jobs that are due at the same time run one after the other. A
+/// job whose fire time passed while other runs were in progress runs as soon as they
+/// have finished, once even if several of its fire times were missed, which are
+/// logged. Runs in progress when a signal arrives are allowed to finish.
+pub fn run(jobs: Vec<Job>) -> Result<(), String> {
+  let rt = tokio::runtime::Builder::new_multi_thread()
+    .enable_all()
+    .build()
+    .map_err(|e| format!("Could not build tokio runtime: {e}"))?;
+
+  rt.block_on(run_async(jobs.into()));
+  Ok(())
+}
+
+async fn run_async(jobs: Arc<[Job]>) {
+  let mut shutdown = pin!(shutdown_signal());
+
+  let now = Utc::now();
+  let mut next_runs = jobs
+    .iter()
+    .map(|job| job.schedule.next_after(now))
+    .collect::<Vec<_>>();
+
+  loop {
+    let Some(next) = next_runs
+      .iter()
+      .flatten()
+      .map(|time| time.with_timezone(&Utc))
+      .min()
+    else {
+      info!("No schedule will fire again, exiting...");
+      return;
+    };
+
+    for (job, time) in jobs.iter().zip(&next_runs) {
+      if let Some(time) = time {
+        info!("Next run of {} scheduled at {time}", job.name);
+      }
+    }
+
+    tokio::select! {
+      () = sleep_until(next) => {}
+      () = &mut shutdown => {
+        info!("Shutting down...");
+        return;
+      }
+    }
+
+    let now = Utc::now();
+    let due = next_runs
+      .iter()
+      .enumerate()
+      .filter(|(_, time)| time.is_some_and(|time| time.with_timezone(&Utc) <= now))
+      .map(|(i, _)| i)
+      .collect::<Vec<_>>();
+
+    for &i in &due {
+      let job = &jobs[i];
+      let mut fire_time = next_runs[i];
+      let mut missed = 0;
+      while let Some(time) = fire_time.and_then(|time| following_run(job, time)) {
+        if time.with_timezone(&Utc) > now {
+          break;
+        }
+        missed += 1;
+        fire_time = Some(time);
+      }
+      if missed > 0 {
+        warn!(
+          "Missed {missed} scheduled run(s) of {} while other runs were in progress",
+          job.name
+        );
+      }
+      next_runs[i] = fire_time.and_then(|time| following_run(job, time));
+    }
+
+    let running = Arc::clone(&jobs);
+    let mut current = tokio::task::spawn_blocking(move || {
+      for i in due {
+        let job = &running[i];
+        info!("Running {}", job.name);
+        match (job.run)() {
+          Ok(()) => info!("Run of {} finished.", job.name),
+          Err(e) => error!("Run of {} failed: {e}", job.name),
+        }
+      }
+    });
+
+    tokio::select! {
+      res = &mut current => log_panic(res),
+      () = &mut shutdown => {
+        info!("Waiting for the current run to finish before shutting down...");
+        log_panic(current.await);
+        return;
+      }
+    }
+  }
+}
+
+/// The fire time of `job` that follows `time`.
+fn following_run(job: &Job, time: DateTime<Tz>) -> Option<DateTime<Tz>> {
+  job.schedule.next_after(time.with_timezone(&Utc))
+}
+
+async fn sleep_until(time: DateTime<Utc>) {
+  let wait = (time - Utc::now()).to_std().unwrap_or_default();
+  tokio::time::sleep(wait).await;
+}
+
+fn log_panic(res: Result<(), tokio::task::JoinError>) {
+  if let Err(e) = res {
+    error!("Run panicked: {e}");
+  }
+}
+
+/// Resolves once SIGINT (Ctrl+C) or, on Unix, SIGTERM is received.
+async fn shutdown_signal() {
+  #[cfg(unix)]
+  {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+      Ok(mut terminate) => {
+        tokio::select! {
+          _ = tokio::signal::ctrl_c() => {}
+          _ = terminate.recv() => {}
+        }
+        return;
+      }
+      Err(e) => warn!("Could not listen for SIGTERM: {e}"),
+    }
+  }
+
+  if let Err(e) = tokio::signal::ctrl_c().await {
+    error!("Could not listen for Ctrl+C: {e}");
+    std::future::pending::<()>().await;
+  }
+}
@@ -1,19 +1,39 @@
 //! An email provider abstraction to allow for multiple backends.
 
-use super::{error::EmailError, resend::Resend, sendgrid::SendGrid};
+use super::{error::EmailError, message::EmailMessage, resend::Resend, sendgrid::SendGrid};
 use super::{mail_cmd::MailCommand, EnvLoader};
+use crate::block_on;
 use enum_dispatch::enum_dispatch;
+use futures::future::BoxFuture;
 
 #[enum_dispatch]
 pub trait EmailProvider {
   /// Sends an email to and from the specified address.
+  ///
+  /// Both parts of the `message` should be sent if the
+  /// provider supports it.
+  fn send_email_async<'a>(
+    &'a self,
+    from_address: &'a str,
+    recipient_addresses: Vec<&'a str>,
+    subject: &'a str,
+    message: &'a EmailMessage,
+  ) -> BoxFuture<'a, Result<(), EmailError>>;
+
+  /// Same as [`EmailProvider::send_email_async`], for callers outside of an async context.
   fn send_email(
     &self,
     from_address: &str,
     recipient_addresses: Vec<&str>,
     subject: &str,
-    contents: &str,
-  ) -> Result<(), EmailError>;
+    message: &EmailMessage,
+  ) -> Result<(), EmailError>
+  where
+    Self: Sync,
+  {
+    block_on(self.send_email_async(from_address, recipient_addresses, subject, message))
+      .map_err(|e| EmailError::Other(e.to_string()))?
+  }
 }
 
 /// An enum containing all Email Provider implementations.
@@ -39,6 +59,21 @@ pub fn get_email_provider() -> Result<impl EmailProvider, String> {
   EmailProviders::try_from(env_var)
 }
 
+/// The names accepted by the `EMAIL` environment variable (case insensitive).
+pub const PROVIDER_NAMES: [&str; 3] = ["SENDGRID", "RESEND", "MAIL_COMMAND"];
+
+impl EmailProviders {
+  /// The environment variables the provider called `name` needs, or `None` if
+  /// there is no such provider.
+  pub fn required_env_vars(name: &str) -> Option<&'static [&'static str]> {
+    match name.trim().to_uppercase().as_str() {
+      "SENDGRID" | "RESEND" => Some(&["API_KEY"]),
+      "MAIL_COMMAND" => Some(&[]),
+      _ => None,
+    }
+  }
+}
+
 impl TryFrom<String> for EmailProviders {
   type Error = String;
 
@@ -50,7 +85,10 @@ impl TryFrom<String> for EmailProviders {
       "SENDGRID" => Ok(Self::SendGrid(SendGrid::new(&env_vars))),
       "RESEND" => Ok(Self::Resend(Resend::new(&env_vars))),
       "MAIL_COMMAND" => Ok(Self::MailCommand(MailCommand {})),
-      _ => Err("Requested client not found".to_owned()),
+      _ => Err(format!(
+        "Unknown email provider '{value}', expected one of {}",
+        PROVIDER_NAMES.join(", ")
+      )),
     }
   }
 }
@@ -40,3 +40,12 @@ impl Display for EmailError {
     }
   }
 }
+
+impl std::error::Error for EmailError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Request(e) => Some(e),
+      Self::Config(_) | Self::Io(_) | Self::Other(_) => None,
+    }
+  }
+}
@@ -1,19 +1,50 @@
 //! Implementation for default `mail` command in linux.
 
-use super::{email_provider::EmailProvider, error::EmailError};
+use futures::future::BoxFuture;
+
+use super::{email_provider::EmailProvider, error::EmailError, message::EmailMessage};
 
 #[derive(Default, Debug, Clone, Copy)]
 pub struct MailCommand {}
 
 impl EmailProvider for MailCommand {
+  fn send_email_async<'a>(
+    &'a self,
+    from_address: &'a str,
+    recipient_addresses: Vec<&'a str>,
+    subject: &'a str,
+    message: &'a EmailMessage,
+  ) -> BoxFuture<'a, Result<(), EmailError>> {
+    // The command is run on the blocking thread pool, which needs owned arguments.
+    let from_address = from_address.to_owned();
+    let recipient_addresses = recipient_addresses
+      .into_iter()
+      .map(str::to_owned)
+      .collect::<Vec<_>>();
+    let subject = subject.to_owned();
+    let message = message.clone();
+
+    Box::pin(async move {
+      tokio::task::spawn_blocking(move || {
+        let recipient_addresses = recipient_addresses
+          .iter()
+          .map(String::as_str)
+          .collect::<Vec<_>>();
+        send_email(&from_address, &recipient_addresses, &subject, &message)
+      })
+      .await
+      .map_err(|e| EmailError::Other(e.to_string()))?
+    })
+  }
+
   fn send_email(
     &self,
     from_address: &str,
     recipient_addresses: Vec<&str>,
     subject: &str,
-    contents: &str,
+    message: &EmailMessage,
   ) -> Result<(), EmailError> {
-    send_email(from_address, &recipient_addresses, subject, contents)
+    send_email(from_address, &recipient_addresses, subject, message)
   }
 }
 
@@ -22,7 +53,7 @@ fn send_email(
   from_address: &str,
   recipient_addresses: &[&str],
   subject: &str,
-  contents: &str,
+  message: &EmailMessage,
 ) -> Result<(), EmailError> {
   use crate::info;
   use std::{fs::File, io::Write, process::Command};
@@ -30,13 +61,16 @@ fn send_email(
   const TEMPORARY_FILE_NAME: &str = "/tmp/rss2-email.txt";
 
   let mut file = File::create(TEMPORARY_FILE_NAME).expect("Can't create temporary file");
+  let boundary = message.boundary();
   file
-    .write_all(contents.as_bytes())
+    .write_all(message.multipart_body(&boundary).as_bytes())
     .map_err(|_e| EmailError::Io("Failed to write temporary email file".to_owned()))?;
 
   let recipients = recipient_addresses.join(",");
-  let mail_command =
-    format!("mail -s \"{subject}\" \"{recipients}\" -aFrom:{from_address} < {TEMPORARY_FILE_NAME}");
+  let mail_command = format!(
+    "mail -s \"{subject}\" \"{recipients}\" -aFrom:{from_address} -a\"MIME-Version: 1.0\" \
+     -a'Content-Type: multipart/alternative; boundary=\"{boundary}\"' < {TEMPORARY_FILE_NAME}"
+  );
 
   let mut mail_sender = Command::new("sh")
     .args(["-c", &mail_command])
@@ -61,7 +95,7 @@ fn send_email(
   _from_address: &str,
   _recipient_addresses: &[&str],
   _subject: &str,
-  _contents: &str,
+  _message: &EmailMessage,
 ) -> Result<(), EmailError> {
   Err(EmailError::Config(
     "No known mail/sendmail/smtp command for Windows OS".to_owned(),
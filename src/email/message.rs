@@ -0,0 +1,55 @@
+//! The body of an email, carried as both HTML and plain text.
+
+use std::fmt::Write as _;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// An email body with an HTML and a plain-text part.
+///
+/// Providers that support it send both parts as `multipart/alternative`
+/// so that text-only clients (and spam filters) get a readable version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailMessage {
+  pub html: String,
+  pub text: String,
+}
+
+impl EmailMessage {
+  pub fn new(html: impl Into<String>, text: impl Into<String>) -> Self {
+    Self {
+      html: html.into(),
+      text: text.into(),
+    }
+  }
+
+  /// Generates a MIME boundary that does not occur in either part.
+  pub fn boundary(&self) -> String {
+    let seed = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |d| d.as_nanos());
+
+    let mut boundary = format!("rss2email-{seed:x}");
+    while self.html.contains(&boundary) || self.text.contains(&boundary) {
+      boundary.push('x');
+    }
+
+    boundary
+  }
+
+  /// Builds a `multipart/alternative` body separated by `boundary`.
+  ///
+  /// The plain-text part comes first as clients display the last
+  /// part they are able to render.
+  pub fn multipart_body(&self, boundary: &str) -> String {
+    let mut res = String::new();
+
+    for (content_type, part) in [("text/plain", &self.text), ("text/html", &self.html)] {
+      let _ = write!(
+        res,
+        "--{boundary}\r\nContent-Type: {content_type}; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n{part}\r\n"
+      );
+    }
+    let _ = write!(res, "--{boundary}--\r\n");
+
+    res
+  }
+}
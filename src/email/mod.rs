@@ -5,6 +5,7 @@
 pub mod email_provider;
 pub mod error;
 pub mod mail_cmd;
+pub mod message;
 pub mod resend;
 pub mod sendgrid;
 
@@ -1,10 +1,11 @@
 //! [`EmailProvider`] implementation using [`Resend`](https://resend.com/).
 
+use futures::future::BoxFuture;
 use resend_rs::types::CreateEmailBaseOptions;
 
 use crate::info;
 
-use super::{email_provider::EmailProvider, error::EmailError, EnvLoader};
+use super::{email_provider::EmailProvider, error::EmailError, message::EmailMessage, EnvLoader};
 
 #[derive(Default, Debug)]
 pub struct Resend {
@@ -20,31 +21,29 @@ impl Resend {
 }
 
 impl EmailProvider for Resend {
-  fn send_email(
-    &self,
-    from_address: &str,
-    recipient_addresses: Vec<&str>,
-    subject: &str,
-    contents: &str,
-  ) -> Result<(), EmailError> {
-    let api_key = self
-      .api_key
-      .as_ref()
-      .ok_or_else(|| EmailError::Config("Cannot use Resend without API_KEY".to_owned()))
-      .cloned()?;
-
-    let resend = resend_rs::Resend::new(&api_key);
-
-    let email =
-      CreateEmailBaseOptions::new(from_address, recipient_addresses, subject).with_html(contents);
-
-    match resend.emails.send(email) {
-      Ok(_id) => {
-        info!("Email request sent");
-      }
-      Err(e) => return Err(EmailError::from(e)),
-    }
-
-    Ok(())
+  fn send_email_async<'a>(
+    &'a self,
+    from_address: &'a str,
+    recipient_addresses: Vec<&'a str>,
+    subject: &'a str,
+    message: &'a EmailMessage,
+  ) -> BoxFuture<'a, Result<(), EmailError>> {
+    Box::pin(async move {
+      let api_key = self
+        .api_key
+        .as_ref()
+        .ok_or_else(|| EmailError::Config("Cannot use Resend without API_KEY".to_owned()))?;
+
+      let resend = resend_rs::Resend::new(api_key);
+
+      let email = CreateEmailBaseOptions::new(from_address, recipient_addresses, subject)
+        .with_html(&message.html)
+        .with_text(&message.text);
+
+      let _id = resend.emails.send(email).await?;
+      info!("Email request sent");
+
+      Ok(())
+    })
   }
 }
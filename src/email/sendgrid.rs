@@ -1,10 +1,11 @@
 //! [`EmailProvider`] implementation using [`SendGrid`](https://sendgrid.com/).
 
-use itertools::Itertools;
+use futures::future::BoxFuture;
+use serde_json::json;
 
 use crate::info;
 
-use super::{email_provider::EmailProvider, error::EmailError, EnvLoader};
+use super::{email_provider::EmailProvider, error::EmailError, message::EmailMessage, EnvLoader};
 
 #[derive(Default, Debug)]
 pub struct SendGrid {
@@ -20,42 +21,46 @@ impl SendGrid {
 }
 
 impl EmailProvider for SendGrid {
-  fn send_email(
-    &self,
-    from_address: &str,
-    recipient_addresses: Vec<&str>,
-    subject: &str,
-    contents: &str,
-  ) -> Result<(), EmailError> {
-    let api_key = self
-      .api_key
-      .as_ref()
-      .ok_or_else(|| EmailError::Config("Cannot use SendGrid without API_KEY".to_owned()))?;
-
-    let personalizations = recipient_addresses
-      .iter()
-      .map(|address| format!(r#"{{"to": [{{"email": "{address}"}}]}}"#))
-      .join(",");
-
-    let message = format!(
-      r#"{{"personalizations": [{personalizations}],"from": {{"email": "{from_address}"}},"subject": "{subject}","content": [{{"type": "text/html", "value": "{contents}"}}]}}"#
-    );
-
-    let http_client = reqwest::blocking::Client::new();
-    let req = http_client
-      .post("https://api.sendgrid.com/v3/mail/send")
-      .header("Authorization", &format!("Bearer {api_key}"))
-      .header("Content-Type", "application/json")
-      .body(message)
-      .build()?;
-    let response = http_client.execute(req);
-
-    match response {
-      Ok(response) => {
-        info!("Email request sent with {}", response.status().as_str());
-        Ok(())
-      }
-      Err(e) => Err(e.into()),
-    }
+  fn send_email_async<'a>(
+    &'a self,
+    from_address: &'a str,
+    recipient_addresses: Vec<&'a str>,
+    subject: &'a str,
+    message: &'a EmailMessage,
+  ) -> BoxFuture<'a, Result<(), EmailError>> {
+    Box::pin(async move {
+      let api_key = self
+        .api_key
+        .as_ref()
+        .ok_or_else(|| EmailError::Config("Cannot use SendGrid without API_KEY".to_owned()))?;
+
+      let personalizations = recipient_addresses
+        .iter()
+        .map(|address| json!({ "to": [{ "email": address }] }))
+        .collect::<Vec<_>>();
+
+      // SendGrid requires `text/plain` to come before `text/html`.
+      let body = json!({
+        "personalizations": personalizations,
+        "from": { "email": from_address },
+        "subject": subject,
+        "content": [
+          { "type": "text/plain", "value": message.text },
+          { "type": "text/html", "value": message.html },
+        ],
+      });
+
+      let response = reqwest::Client::new()
+        .post("https://api.sendgrid.com/v3/mail/send")
+        .header("Authorization", &format!("Bearer {api_key}"))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?
+        .error_for_status()?;
+
+      info!("Email request sent with {}", response.status().as_str());
+      Ok(())
+    })
   }
 }
@@ -1,8 +1,43 @@
+use std::fmt::Display;
+
+use crate::{
+  email::error::EmailError,
+  xml::error::{DateError, ParserError},
+};
+
 /// Represents anything that could go wrong when dealing with web feeds.
+///
+/// Errors about a feed carry its URL, which is filled in by [`Error::with_url`] where
+/// the code that failed did not know it.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
-  /// Wrapper for [`reqwest::Error`].
-  Reqwest(Box<reqwest::Error>),
+  /// The server answered with a status that is not a success.
+  HttpStatus { url: String, status: u16 },
+  /// The response is not a web feed, judging by its `Content-Type` (`None` if it had none).
+  UnsupportedContentType {
+    url: String,
+    content_type: Option<String>,
+  },
+  /// The request timed out.
+  Timeout { url: Option<String> },
+  /// The request failed before a response was received, e.g. on DNS or TLS errors.
+  Request {
+    url: Option<String>,
+    source: Box<reqwest::Error>,
+  },
+  /// The response is not a valid RSS or Atom feed.
+  Parse {
+    url: Option<String>,
+    source: ParserError,
+  },
+  /// A date in the feed could not be parsed.
+  Date {
+    url: Option<String>,
+    source: DateError,
+  },
+  /// The email could not be sent.
+  Email(EmailError),
   /// Wrapper for [`http::header::ToStrError`].
   HeaderString(Box<http::header::ToStrError>),
   /// Wrapper for [`std::io::Error`].
@@ -10,3 +45,145 @@ pub enum Error {
   /// Generic error
   Generic(String),
 }
+
+impl Error {
+  /// Attaches the URL of the feed that failed, unless the error already has one.
+  #[must_use]
+  pub fn with_url(self, url: &str) -> Self {
+    match self {
+      Self::Timeout { url: None } => Self::Timeout {
+        url: Some(url.to_owned()),
+      },
+      Self::Request { url: None, source } => Self::Request {
+        url: Some(url.to_owned()),
+        source,
+      },
+      Self::Parse { url: None, source } => Self::Parse {
+        url: Some(url.to_owned()),
+        source,
+      },
+      Self::Date { url: None, source } => Self::Date {
+        url: Some(url.to_owned()),
+        source,
+      },
+      e => e,
+    }
+  }
+
+  /// The URL of the feed that failed, if known.
+  pub fn url(&self) -> Option<&str> {
+    match self {
+      Self::HttpStatus { url, .. } | Self::UnsupportedContentType { url, .. } => Some(url),
+      Self::Timeout { url }
+      | Self::Request { url, .. }
+      | Self::Parse { url, .. }
+      | Self::Date { url, .. } => url.as_deref(),
+      Self::Email(_) | Self::HeaderString(_) | Self::Io(_) | Self::Generic(_) => None,
+    }
+  }
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match &self {
+      Self::HttpStatus { url, status } => write!(f, "{url} returned HTTP {status}"),
+      Self::UnsupportedContentType {
+        url,
+        content_type: Some(content_type),
+      } => write!(f, "Invalid content {content_type} for {url}"),
+      Self::UnsupportedContentType {
+        url,
+        content_type: None,
+      } => write!(f, "No content type header found for {url}"),
+      Self::Timeout { url: Some(url) } => write!(f, "The request for {url} timed out"),
+      Self::Timeout { url: None } => write!(f, "The request timed out"),
+      Self::Request { source: e, .. } => write!(f, "{e}"),
+      Self::Parse { source: e, .. } => write!(f, "{e}"),
+      Self::Date { source: e, .. } => write!(f, "{e}"),
+      Self::Email(e) => write!(f, "{e}"),
+      Self::HeaderString(e) => write!(f, "{e}"),
+      Self::Io(e) => write!(f, "{e}"),
+      Self::Generic(e) => write!(f, "{e}"),
+    }
+  }
+}
+
+impl std::error::Error for Error {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Request { source, .. } => Some(source.as_ref()),
+      Self::Parse { source, .. } => Some(source),
+      Self::Date { source, .. } => Some(source),
+      Self::Email(e) => Some(e),
+      Self::HeaderString(e) => Some(e.as_ref()),
+      Self::Io(e) => Some(e),
+      Self::HttpStatus { .. }
+      | Self::UnsupportedContentType { .. }
+      | Self::Timeout { .. }
+      | Self::Generic(_) => None,
+    }
+  }
+}
+
+impl From<std::io::Error> for Error {
+  fn from(error: std::io::Error) -> Self {
+    Self::Io(error)
+  }
+}
+
+impl From<reqwest::Error> for Error {
+  fn from(error: reqwest::Error) -> Self {
+    let url = error.url().map(ToString::to_string);
+
+    if error.is_timeout() {
+      Self::Timeout { url }
+    } else {
+      Self::Request {
+        url,
+        source: Box::new(error),
+      }
+    }
+  }
+}
+
+impl From<http::header::ToStrError> for Error {
+  fn from(error: http::header::ToStrError) -> Self {
+    Self::HeaderString(Box::new(error))
+  }
+}
+
+impl From<ParserError> for Error {
+  /// Date errors become [`Error::Date`], also when they made a document that is otherwise a
+  /// valid feed of one format fail as [`ParserError::UnknownFormat`].
+  fn from(error: ParserError) -> Self {
+    match error {
+      ParserError::Date(source) => source.into(),
+      ParserError::UnknownFormat { rss, atom } => match (*rss, *atom) {
+        (ParserError::Date(source), _) | (_, ParserError::Date(source)) => source.into(),
+        (rss, atom) => Self::Parse {
+          url: None,
+          source: ParserError::UnknownFormat {
+            rss: Box::new(rss),
+            atom: Box::new(atom),
+          },
+        },
+      },
+      source @ ParserError::Parse { .. } => Self::Parse { url: None, source },
+    }
+  }
+}
+
+impl From<DateError> for Error {
+  fn from(error: DateError) -> Self {
+    Self::Date {
+      url: None,
+      source: error,
+    }
+  }
+}
+
+impl From<EmailError> for Error {
+  fn from(error: EmailError) -> Self {
+    Self::Email(error)
+  }
+}
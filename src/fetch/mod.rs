@@ -0,0 +1,317 @@
+//! Downloads web feeds.
+//!
+//! A [`Fetcher`] returns the raw [`Response`] to a request, which is then checked to be a
+//! web feed with [`Response::into_feed`]. [`HttpFetcher`] is the default implementation,
+//! [`RecordingFetcher`] and [`ReplayFetcher`] save responses to fixture files and serve them
+//! back so that tests and benchmarks do not depend on the network.
+//!
+//! An [`HttpFetcher`] can keep the responses it receives in a [`StateStore`] (see
+//! [`HttpFetcher::with_cache`]), so that feeds that did not change are not downloaded
+//! again.
+
+use std::{collections::BTreeMap, fmt::Debug, sync::Arc};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use futures::future::BoxFuture;
+use reqwest::Client;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{state::StateStore, warn, Error};
+
+pub use self::replay::{RecordingFetcher, ReplayFetcher};
+
+mod replay;
+
+/// The content types accepted as web feeds.
+const SUPPORTED_CONTENT_TYPES: [&str; 5] = [
+  "application/xml",
+  "text/xml",
+  "application/rss+xml",
+  "application/atom+xml",
+  "text/html",
+];
+
+/// Downloads the contents of a feed.
+pub trait Fetcher: Debug + Send + Sync {
+  /// Requests `url`, returning the response whatever its status.
+  fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response, Error>>;
+
+  /// The URL that `url` redirects to, `None` if it does not redirect. Follows the
+  /// redirects of [`Fetcher::fetch`] by default.
+  fn resolve<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Option<String>, Error>> {
+    Box::pin(async move { Ok(self.fetch(url).await?.redirected_to) })
+  }
+}
+
+/// The response to a request for a feed.
+///
+/// It is serialized with its body as text if that is valid UTF-8, and as base64 in
+/// `body_base64` otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SavedResponse", into = "SavedResponse")]
+pub struct Response {
+  /// The HTTP status code.
+  pub status: u16,
+  /// The headers, with lowercase names.
+  pub headers: BTreeMap<String, String>,
+  /// The body, as received.
+  pub body: Vec<u8>,
+  /// The URL the response was served from, if the request was redirected.
+  pub redirected_to: Option<String>,
+}
+
+/// The serialized form of a [`Response`].
+#[derive(Serialize, Deserialize)]
+struct SavedResponse {
+  status: u16,
+  headers: BTreeMap<String, String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  body: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  body_base64: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  redirected_to: Option<String>,
+}
+
+impl From<Response> for SavedResponse {
+  fn from(response: Response) -> Self {
+    let (body, body_base64) = match String::from_utf8(response.body) {
+      Ok(body) => (Some(body), None),
+      Err(e) => (None, Some(BASE64_STANDARD.encode(e.into_bytes()))),
+    };
+
+    Self {
+      status: response.status,
+      headers: response.headers,
+      body,
+      body_base64,
+      redirected_to: response.redirected_to,
+    }
+  }
+}
+
+impl TryFrom<SavedResponse> for Response {
+  type Error = String;
+
+  fn try_from(saved: SavedResponse) -> Result<Self, Self::Error> {
+    let body = match (saved.body, saved.body_base64) {
+      (_, Some(body)) => BASE64_STANDARD
+        .decode(body)
+        .map_err(|e| format!("Invalid base64 body: {e}"))?,
+      (body, None) => body.unwrap_or_default().into_bytes(),
+    };
+
+    Ok(Self {
+      status: saved.status,
+      headers: saved.headers,
+      body,
+      redirected_to: saved.redirected_to,
+    })
+  }
+}
+
+impl Response {
+  /// The value of the header called `name` (case insensitive), if it was sent.
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self.headers.get(&name.to_lowercase()).map(String::as_str)
+  }
+
+  /// The body as text, if this is a successful response with a web feed content type.
+  /// Invalid UTF-8 is replaced.
+  ///
+  /// `url` is only used in error messages.
+  pub fn into_feed(self, url: &str) -> Result<String, Error> {
+    if !(200..300).contains(&self.status) {
+      return Err(Error::HttpStatus {
+        url: url.to_owned(),
+        status: self.status,
+      });
+    }
+
+    let content_type = self
+      .header("content-type")
+      .map(|content_type| content_type.split(';').next().unwrap_or_default().trim());
+
+    if !content_type.is_some_and(|content_type| SUPPORTED_CONTENT_TYPES.contains(&content_type)) {
+      return Err(Error::UnsupportedContentType {
+        url: url.to_owned(),
+        content_type: content_type.map(str::to_owned),
+      });
+    }
+
+    Ok(String::from_utf8_lossy(&self.body).trim().to_owned())
+  }
+}
+
+/// Downloads feeds over HTTP with [`reqwest`].
+#[derive(Debug, Clone, Default)]
+pub struct HttpFetcher {
+  client: Client,
+  cache: Option<Arc<dyn StateStore>>,
+}
+
+impl HttpFetcher {
+  /// Downloads feeds with `client`, to configure timeouts or proxies.
+  pub const fn new(client: Client) -> Self {
+    Self {
+      client,
+      cache: None,
+    }
+  }
+
+  /// Keeps the successful responses that have an `ETag` or a `Last-Modified` header in
+  /// `store`, under [`cache_key`], and revalidates them with a conditional request the
+  /// next time their URL is requested. The cached response is returned if the server
+  /// answers that it was not modified.
+  ///
+  /// Failing to read or write the cache is logged and otherwise ignored.
+  #[must_use]
+  pub fn with_cache(mut self, store: Arc<dyn StateStore>) -> Self {
+    self.cache = Some(store);
+    self
+  }
+
+  async fn fetch_cached(&self, url: &str, cache: &Arc<dyn StateStore>) -> Result<Response, Error> {
+    let key = cache_key(url);
+
+    let store = Arc::clone(cache);
+    let entry_key = key.clone();
+    let cached = match blocking(move || store.as_ref().get_json::<CacheEntry>(&entry_key)).await {
+      Ok(entry) => entry
+        .filter(|entry| entry.url == url)
+        .map(|entry| entry.response),
+      Err(e) => {
+        warn!("Could not read the cached response of {url}: {e}");
+        None
+      }
+    };
+
+    let response = request(url, &self.client, cached.as_ref()).await?;
+    if response.status == 304 {
+      if let Some(cached) = cached {
+        return Ok(cached);
+      }
+    }
+
+    if (200..300).contains(&response.status)
+      && (response.header("etag").is_some() || response.header("last-modified").is_some())
+    {
+      let store = Arc::clone(cache);
+      let entry = CacheEntry {
+        url: url.to_owned(),
+        response: response.clone(),
+      };
+      if let Err(e) = blocking(move || store.as_ref().put_json(&key, &entry)).await {
+        warn!("Could not cache the response of {url}: {e}");
+      }
+    }
+
+    Ok(response)
+  }
+}
+
+impl Fetcher for HttpFetcher {
+  fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response, Error>> {
+    match &self.cache {
+      Some(cache) => Box::pin(self.fetch_cached(url, cache)),
+      None => Box::pin(get_response(url, &self.client)),
+    }
+  }
+
+  /// Follows the redirects with a `HEAD` request, so that the page is not downloaded.
+  /// Responses are not cached.
+  fn resolve<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Option<String>, Error>> {
+    Box::pin(async move {
+      let response = self
+        .client
+        .head(url)
+        .header("User-Agent", "Rss2Email")
+        .send()
+        .await?;
+
+      Ok(
+        Some(response.url().as_str())
+          .filter(|target| *target != url)
+          .map(ToOwned::to_owned),
+      )
+    })
+  }
+}
+
+/// Runs `f` on a thread where blocking is allowed, as state stores may block.
+pub(crate) async fn blocking<T: Send + 'static>(
+  f: impl FnOnce() -> Result<T, Error> + Send + 'static,
+) -> Result<T, Error> {
+  tokio::task::spawn_blocking(f)
+    .await
+    .map_err(|e| Error::Generic(format!("State store task failed: {e}")))?
+}
+
+/// A response kept in the cache of an [`HttpFetcher`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheEntry {
+  url: String,
+  #[serde(flatten)]
+  response: Response,
+}
+
+/// The key of the [`StateStore`] the cached response to `url` is kept under.
+pub fn cache_key(url: &str) -> String {
+  format!("cache/{:016x}.json", url_hash(url))
+}
+
+/// A hash of `url` that is stable across runs and platforms (64-bit FNV-1a).
+pub(crate) fn url_hash(url: &str) -> u64 {
+  url.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+    (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+  })
+}
+
+/// Requests `url` with `client`, asking for a web feed.
+pub async fn get_response(url: &str, client: &Client) -> Result<Response, Error> {
+  request(url, client, None).await
+}
+
+/// Requests `url` with `client`, only asking for the body if it changed since `cached`
+/// was received.
+async fn request(url: &str, client: &Client, cached: Option<&Response>) -> Result<Response, Error> {
+  let mut request = client
+    .get(url)
+    .header(
+      "Accept",
+      "application/xml, text/xml, application/rss+xml, application/atom+xml",
+    )
+    .header("User-Agent", "Rss2Email");
+  if let Some(etag) = cached.and_then(|cached| cached.header("etag")) {
+    request = request.header("If-None-Match", etag);
+  }
+  if let Some(last_modified) = cached.and_then(|cached| cached.header("last-modified")) {
+    request = request.header("If-Modified-Since", last_modified);
+  }
+
+  let response = request.send().await?;
+
+  let redirected_to = Some(response.url().as_str())
+    .filter(|final_url| *final_url != url)
+    .map(ToOwned::to_owned);
+  let status = response.status().as_u16();
+  // Header values are not always ASCII, e.g. Latin-1 file names in `Content-Disposition`.
+  let headers = response
+    .headers()
+    .iter()
+    .map(|(name, value)| {
+      (
+        name.as_str().to_owned(),
+        String::from_utf8_lossy(value.as_bytes()).into_owned(),
+      )
+    })
+    .collect();
+  let body = response.bytes().await?.to_vec();
+
+  Ok(Response {
+    status,
+    headers,
+    body,
+    redirected_to,
+  })
+}
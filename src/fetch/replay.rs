@@ -0,0 +1,157 @@
+use std::{
+  fs,
+  io::ErrorKind,
+  path::{Path, PathBuf},
+};
+
+use futures::future::BoxFuture;
+use serde_derive::{Deserialize, Serialize};
+
+use super::{url_hash, Fetcher, HttpFetcher, Response};
+use crate::Error;
+
+/// A response saved to a fixture file, along with the URL it was requested from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Recording {
+  url: String,
+  #[serde(flatten)]
+  response: Response,
+}
+
+/// The fixture file the response to `url` is saved in, relative to the fixtures directory.
+///
+/// The scheme is dropped and every character that is not alphanumeric, `.` or `-` is
+/// replaced with `_`, followed by a hash of the whole URL so that URLs that only differ by
+/// these characters are saved to different files.
+fn file_name(url: &str) -> String {
+  let name = url
+    .split_once("://")
+    .map_or(url, |(_scheme, rest)| rest)
+    .chars()
+    .map(|c| {
+      if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+        c
+      } else {
+        '_'
+      }
+    })
+    .collect::<String>();
+
+  format!("{}-{:016x}.json", name.trim_matches('_'), url_hash(url))
+}
+
+/// Passes requests on to another [`Fetcher`] and saves every response to a fixture file,
+/// to be served back by a [`ReplayFetcher`].
+#[derive(Debug, Clone)]
+pub struct RecordingFetcher<F = HttpFetcher> {
+  inner: F,
+  dir: PathBuf,
+}
+
+impl<F: Fetcher> RecordingFetcher<F> {
+  /// Saves the responses of `inner` to JSON files under `dir`, which is created if needed.
+  pub fn new(inner: F, dir: impl Into<PathBuf>) -> Self {
+    Self {
+      inner,
+      dir: dir.into(),
+    }
+  }
+
+  async fn record(&self, url: &str) -> Result<Response, Error> {
+    let response = self.inner.fetch(url).await?;
+
+    let recording = Recording {
+      url: url.to_owned(),
+      response,
+    };
+    let json = serde_json::to_string_pretty(&recording)
+      .map_err(|e| Error::Generic(format!("Could not serialize the response of {url}: {e}")))?;
+
+    fs::create_dir_all(&self.dir)?;
+    fs::write(self.dir.join(file_name(url)), json + "\n")?;
+
+    Ok(recording.response)
+  }
+}
+
+impl<F: Fetcher> Fetcher for RecordingFetcher<F> {
+  fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response, Error>> {
+    Box::pin(self.record(url))
+  }
+}
+
+/// Serves the responses saved by a [`RecordingFetcher`], without touching the network.
+///
+/// Requesting a URL that was not recorded is an error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayFetcher {
+  dir: PathBuf,
+}
+
+impl ReplayFetcher {
+  /// Serves the responses saved under `dir`.
+  pub fn new(dir: impl Into<PathBuf>) -> Self {
+    Self { dir: dir.into() }
+  }
+
+  /// The directory the responses are read from.
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  /// The URLs that were recorded, sorted.
+  pub fn urls(&self) -> Result<Vec<String>, Error> {
+    let mut urls = Vec::new();
+
+    for entry in fs::read_dir(&self.dir)? {
+      let path = entry?.path();
+      if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+      {
+        urls.push(read_recording(&path)?.url);
+      }
+    }
+
+    urls.sort_unstable();
+    Ok(urls)
+  }
+
+  fn replay(&self, url: &str) -> Result<Response, Error> {
+    let path = self.dir.join(file_name(url));
+
+    let recording = match read_recording(&path) {
+      Err(Error::Io(e)) if e.kind() == ErrorKind::NotFound => {
+        return Err(Error::Generic(format!(
+          "No recorded response for {url} in {}",
+          self.dir.display()
+        )))
+      }
+      recording => recording?,
+    };
+
+    if recording.url != url {
+      return Err(Error::Generic(format!(
+        "{} holds the response for {}, not {url}",
+        path.display(),
+        recording.url
+      )));
+    }
+
+    Ok(recording.response)
+  }
+}
+
+impl Fetcher for ReplayFetcher {
+  fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response, Error>> {
+    let response = self.replay(url);
+    Box::pin(async move { response })
+  }
+}
+
+fn read_recording(path: &Path) -> Result<Recording, Error> {
+  let json = fs::read_to_string(path)?;
+
+  serde_json::from_str(&json)
+    .map_err(|e| Error::Generic(format!("Invalid recording {}: {e}", path.display())))
+}
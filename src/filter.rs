@@ -0,0 +1,216 @@
+//! Rules that include or exclude posts based on their contents.
+//!
+//! Rules are read from the `filters` of the [config file](crate::config::file), where
+//! they are appended to the ones of `[defaults]`:
+//!
+//! ```toml
+//! # Applies to every feed.
+//! [[defaults.filters]]
+//! name = "no ads"
+//! exclude = [{ title = "sponsored" }, { link = "/^https://[^/]+/sponsored//" }]
+//!
+//! # Only keeps the Rust and Go posts of a busy feed.
+//! [[profiles.daily.filters]]
+//! feeds = ["https://news.ycombinator.com/rss"]
+//! include = [
+//!   { any = [{ title = "rust" }, { category = "rust" }] },
+//!   { all = [{ title = "go" }, { not = { title = "/\\bgoogle\\b/" } }] },
+//! ]
+//! ```
+//!
+//! A pattern is a case insensitive keyword, or a regular expression when written
+//! between slashes. `title`, `description`, `link`, `category` and `author` match a
+//! field of a post (any of its categories or authors), `any`, `all` and `not` combine
+//! other conditions. `link` matches the link once it has been cleaned up, see
+//! [`link`](crate::link).
+
+use std::{
+  fmt::{self, Display},
+  sync::atomic::{AtomicUsize, Ordering},
+};
+
+use regex::Regex;
+use serde_derive::Deserialize;
+
+use crate::{info, pipeline::Filter, Blog, Post};
+
+/// A set of conditions a post must or must not match.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilterRule {
+  /// Shown in the logs, defaults to the position of the rule.
+  pub name: Option<String>,
+  /// The feeds the rule applies to, every feed if empty.
+  pub feeds: Vec<String>,
+  /// If not empty, posts that match none of these are dropped.
+  pub include: Vec<Condition>,
+  /// Posts that match any of these are dropped.
+  pub exclude: Vec<Condition>,
+}
+
+impl FilterRule {
+  /// Whether the rule applies to the feed at `feed`.
+  pub fn applies_to(&self, feed: &str) -> bool {
+    self.feeds.is_empty() || self.feeds.iter().any(|url| url == feed)
+  }
+
+  /// Whether `post` passes the rule.
+  pub fn keeps(&self, post: &Post) -> bool {
+    (self.include.is_empty() || self.include.iter().any(|c| c.matches(post)))
+      && !self.exclude.iter().any(|c| c.matches(post))
+  }
+}
+
+/// A condition on the contents of a post.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
+pub enum Condition {
+  Title(Pattern),
+  /// Never matches posts without a description.
+  Description(Pattern),
+  Link(Pattern),
+  /// Matches if any of the categories of the post matches.
+  Category(Pattern),
+  /// Matches if any of the authors of the post matches.
+  Author(Pattern),
+  /// Matches if any of the conditions matches.
+  Any(Vec<Self>),
+  /// Matches if all of the conditions match.
+  All(Vec<Self>),
+  Not(Box<Self>),
+}
+
+impl Condition {
+  /// Whether `post` matches the condition.
+  pub fn matches(&self, post: &Post) -> bool {
+    match self {
+      Self::Title(pattern) => pattern.matches(&post.title),
+      Self::Description(pattern) => post
+        .description
+        .as_deref()
+        .is_some_and(|description| pattern.matches(description)),
+      Self::Link(pattern) => pattern.matches(&post.link),
+      Self::Category(pattern) => post
+        .categories
+        .iter()
+        .any(|category| pattern.matches(category)),
+      Self::Author(pattern) => post.authors.iter().any(|author| pattern.matches(author)),
+      Self::Any(conditions) => conditions.iter().any(|c| c.matches(post)),
+      Self::All(conditions) => conditions.iter().all(|c| c.matches(post)),
+      Self::Not(condition) => !condition.matches(post),
+    }
+  }
+}
+
+/// A case insensitive keyword, or a regular expression if written as `/regex/`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Pattern {
+  Keyword(String),
+  Regex(Regex),
+}
+
+impl Pattern {
+  /// Whether `text` contains the keyword or matches the regular expression.
+  pub fn matches(&self, text: &str) -> bool {
+    match self {
+      Self::Keyword(keyword) => text.to_lowercase().contains(keyword),
+      Self::Regex(regex) => regex.is_match(text),
+    }
+  }
+}
+
+impl TryFrom<String> for Pattern {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    match value
+      .strip_prefix('/')
+      .and_then(|value| value.strip_suffix('/'))
+    {
+      Some(regex) => Regex::new(regex)
+        .map(Self::Regex)
+        .map_err(|e| format!("Invalid regular expression {value}: {e}")),
+      None if value.trim().is_empty() => Err("Filter keywords cannot be empty".to_owned()),
+      None => Ok(Self::Keyword(value.to_lowercase())),
+    }
+  }
+}
+
+impl Display for Pattern {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Keyword(keyword) => write!(f, "{keyword}"),
+      Self::Regex(regex) => write!(f, "/{regex}/"),
+    }
+  }
+}
+
+impl PartialEq for Pattern {
+  fn eq(&self, other: &Self) -> bool {
+    self.to_string() == other.to_string()
+  }
+}
+
+impl Eq for Pattern {}
+
+/// Drops the posts that do not pass every [`FilterRule`] that applies to their feed.
+///
+/// Counts how many posts each rule dropped and logs it at the end of every run.
+#[derive(Debug, Default)]
+pub struct PostFilter {
+  rules: Vec<FilterRule>,
+  dropped: Vec<AtomicUsize>,
+}
+
+impl PostFilter {
+  pub fn new(rules: Vec<FilterRule>) -> Self {
+    let dropped = rules.iter().map(|_| AtomicUsize::new(0)).collect();
+    Self { rules, dropped }
+  }
+
+  /// The rules, with the number of posts each of them dropped since the last run ended.
+  pub fn dropped(&self) -> Vec<(String, usize)> {
+    self
+      .rules
+      .iter()
+      .zip(&self.dropped)
+      .enumerate()
+      .map(|(i, (rule, dropped))| (rule_name(i, rule), dropped.load(Ordering::Relaxed)))
+      .collect()
+  }
+}
+
+impl Filter for PostFilter {
+  fn apply(&self, feed: &str, mut blog: Blog) -> Option<Blog> {
+    let rules = self
+      .rules
+      .iter()
+      .zip(&self.dropped)
+      .filter(|(rule, _)| rule.applies_to(feed))
+      .collect::<Vec<_>>();
+
+    blog.posts.retain(|post| {
+      rules
+        .iter()
+        .find(|(rule, _)| !rule.keeps(post))
+        .map(|(_, dropped)| dropped.fetch_add(1, Ordering::Relaxed))
+        .is_none()
+    });
+
+    Some(blog)
+  }
+
+  fn finish(&self) {
+    for (i, (rule, dropped)) in self.rules.iter().zip(&self.dropped).enumerate() {
+      // Reset outside of `info!`, which skips its arguments when logging is disabled.
+      let dropped = dropped.swap(0, Ordering::Relaxed);
+      info!("Filter {} dropped {dropped} posts", rule_name(i, rule));
+    }
+  }
+}
+
+/// The name of the `i`th rule in the logs.
+fn rule_name(i: usize, rule: &FilterRule) -> String {
+  rule.name.clone().unwrap_or_else(|| format!("#{}", i + 1))
+}
@@ -1,104 +1,299 @@
 #![allow(clippy::missing_panics_doc)]
 
-use std::{fmt::Display, fs, time::SystemTime};
+use std::{
+  collections::{HashMap, HashSet},
+  future::Future,
+  time::SystemTime,
+};
 
-use chrono::{DateTime, Utc};
+use chrono::Utc;
 pub use error::Error;
-use futures::{stream, StreamExt};
+use futures::{stream::BoxStream, StreamExt};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
 use reqwest::Client;
-use std::fmt::Write as _;
+use serde_derive::Serialize;
 use tokio::runtime::Handle;
 
-pub use blog::{Blog, Post};
+pub use blog::{Blog, Enclosure, FailedFeed, Post};
 mod blog;
+pub mod config;
 pub mod email;
 mod error;
+pub mod fetch;
+pub mod filter;
+pub mod link;
 pub mod logger;
+pub mod pipeline;
+pub mod sanitize;
+pub mod schedule;
+pub mod state;
+pub mod summary;
+pub mod template;
+pub mod text;
+pub mod window;
 pub mod xml;
 
-use crate::xml::parse_web_feed;
-
-const CONCURRENT_REQUESTS: usize = 10;
+use crate::{
+  config::Config,
+  pipeline::{FeedResult, Pipeline, RecentPosts, WebFeedParser},
+  template::Templates,
+  xml::ParseOptions,
+};
 
 /// Downloads all the web feeds specified in `feeds.txt` and converts them to `Blog`s.
 pub fn download_blogs(days: i64) -> Vec<Blog> {
-  let links = read_feeds();
+  download_digest(days).blogs
+}
 
-  let contents = if let Ok(handle) = Handle::try_current() {
-    std::thread::spawn(move || handle.block_on(get_blogs(links)))
-      .join()
-      .expect("Error spawning blog download")
-  } else {
-    let rt = tokio::runtime::Builder::new_current_thread()
-      .enable_all()
-      .build()
-      .expect("Could not build tokio runtime");
+/// The outcome of downloading every web feed.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Digest {
+  /// The blogs with posts from the last `n` days.
+  pub blogs: Vec<Blog>,
+  /// The feeds that could not be downloaded or parsed.
+  pub failed_feeds: Vec<FailedFeed>,
+}
 
-    rt.block_on(get_blogs(links))
-  };
+impl Extend<FeedResult> for Digest {
+  fn extend<T: IntoIterator<Item = FeedResult>>(&mut self, results: T) {
+    for result in results {
+      match result {
+        Ok(blog) => self.blogs.push(blog),
+        Err(failed) => self.failed_feeds.push(failed),
+      }
+    }
+  }
+}
 
-  let contents: Vec<Blog> = contents
-    .into_iter()
-    .filter_map(|x| match x {
-      Some(x) => {
-        if !within_n_days(days, &x.most_recent_pub_date) {
-          return None;
+impl Digest {
+  /// The number of posts across all blogs.
+  pub fn post_count(&self) -> usize {
+    self.blogs.iter().map(|blog| blog.posts.len()).sum()
+  }
+
+  /// Merges the posts that were found in several feeds, such as a post syndicated by
+  /// planets, keeping one copy that lists all of their [`Post::feeds`].
+  ///
+  /// Posts are the same if their [canonical links](link::canonical_link) are equal, or
+  /// their ids if these are URIs (feed-local ids such as `1234` are ignored). The copy
+  /// from a feed on the same host as the post is kept if there is one. Posts are also
+  /// the same if they are both the same as a third one. Blogs left without posts are
+  /// dropped.
+  ///
+  /// Posts of the same blog are only the same if they have the same id, as links such as
+  /// `changelog#v1` and `changelog#v2` have the same canonical link.
+  pub fn dedupe(&mut self) {
+    let positions = self
+      .blogs
+      .iter()
+      .enumerate()
+      .flat_map(|(b, blog)| (0..blog.posts.len()).map(move |p| (b, p)))
+      .collect::<Vec<_>>();
+
+    // Union-find over the indices of `positions`, along with the blogs of each set.
+    let mut parents = (0..positions.len()).collect::<Vec<_>>();
+    let mut blogs_of = positions
+      .iter()
+      .map(|&(b, _)| HashSet::from([b]))
+      .collect::<Vec<_>>();
+
+    let mut post_of_id = HashMap::new();
+    for (i, &(b, p)) in positions.iter().enumerate() {
+      if let Some(id) = non_empty_id(&self.blogs[b].posts[p]) {
+        let first = *post_of_id.entry((b, id)).or_insert(i);
+        merge_sets(&mut parents, &mut blogs_of, first, i);
+      }
+    }
+
+    // Sets are not merged if that would merge different posts of one blog.
+    let mut posts_of_key: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, &(b, p)) in positions.iter().enumerate() {
+      for key in identity_keys(&self.blogs[b].posts[p]) {
+        let posts = posts_of_key.entry(key).or_default();
+        for &other in posts.iter() {
+          let (root, other) = (find_root(&mut parents, other), find_root(&mut parents, i));
+          if blogs_of[root].is_disjoint(&blogs_of[other]) {
+            merge_sets(&mut parents, &mut blogs_of, root, other);
+          }
         }
+        posts.push(i);
+      }
+    }
 
-        let recent_posts: Vec<Post> = x
-          .posts
-          .into_iter()
-          .filter(|x| within_n_days(days, &x.pub_date))
-          .collect();
+    let mut groups: Vec<Vec<(usize, usize)>> = vec![Vec::new(); positions.len()];
+    for (i, &position) in positions.iter().enumerate() {
+      groups[find_root(&mut parents, i)].push(position);
+    }
 
-        let non_empty = !recent_posts.is_empty();
+    let mut removed = HashSet::new();
+    for group in groups.into_iter().filter(|group| group.len() > 1) {
+      let (b, p) = group
+        .iter()
+        .copied()
+        .find(|&(b, p)| is_from_own_site(&self.blogs[b].posts[p]))
+        .unwrap_or(group[0]);
+
+      let feeds = group
+        .iter()
+        .flat_map(|&(b, p)| self.blogs[b].posts[p].feeds.clone())
+        .unique()
+        .collect();
+      self.blogs[b].posts[p].feeds = feeds;
+
+      removed.extend(group.into_iter().filter(|&kept| kept != (b, p)));
+    }
 
-        non_empty.then_some(Blog {
-          posts: recent_posts,
-          ..x
-        })
+    for (b, blog) in self.blogs.iter_mut().enumerate() {
+      let count = blog.posts.len();
+      let mut p = 0;
+      blog.posts.retain(|_| {
+        p += 1;
+        !removed.contains(&(b, p - 1))
+      });
+
+      if blog.posts.len() < count {
+        if let Some(date) = blog.posts.iter().map(|post| post.pub_date).max() {
+          blog.most_recent_pub_date = date;
+        }
       }
-      None => None,
-    })
-    .collect();
+    }
+    self.blogs.retain(|blog| !blog.posts.is_empty());
+  }
+}
+
+/// The representative of the set `i` belongs to in the union-find `parents`, shortening
+/// the path to it on the way.
+fn find_root(parents: &mut [usize], i: usize) -> usize {
+  let mut root = i;
+  while parents[root] != root {
+    root = parents[root];
+  }
 
-  contents
+  let mut i = i;
+  while parents[i] != root {
+    let next = parents[i];
+    parents[i] = root;
+    i = next;
+  }
+  root
 }
 
-/// Helper method for [download_blogs](download_blogs).
-async fn get_blogs(links: Vec<String>) -> Vec<Option<Blog>> {
-  let client = Client::new();
-  stream::iter(links)
-    .map(|link| {
-      let client = &client;
-      async move {
-        let xml = get_page_async(link.as_str(), client)
-          .await
-          .map_err(|e| warn!("Error in {}\n{}", link, e))
-          .ok()?;
-
-        parse_web_feed(&xml)
-          .map_err(|e| warn!("Error in {}\n{}", link, e))
-          .ok()
-      }
+/// Merges the sets of `a` and `b` in the union-find `parents`, along with their blogs.
+fn merge_sets(parents: &mut [usize], blogs_of: &mut [HashSet<usize>], a: usize, b: usize) {
+  let (root, other) = (find_root(parents, a), find_root(parents, b));
+  if root != other {
+    parents[other] = root;
+    let blogs = std::mem::take(&mut blogs_of[other]);
+    blogs_of[root].extend(blogs);
+  }
+}
+
+/// The trimmed id of `post`, if it has one.
+fn non_empty_id(post: &Post) -> Option<&str> {
+  post
+    .id
+    .as_deref()
+    .map(str::trim)
+    .filter(|id| !id.is_empty())
+}
+
+/// The keys that identify `post` across feeds, see [`Digest::dedupe`].
+fn identity_keys(post: &Post) -> Vec<String> {
+  let link = Some(post.link.trim())
+    .filter(|link| !link.is_empty())
+    .map(|link| format!("link:{}", link::canonical_link(link)));
+  let id = post
+    .id
+    .as_deref()
+    .filter(|id| id.contains(':'))
+    .map(|id| format!("id:{id}"));
+
+  link.into_iter().chain(id).collect()
+}
+
+/// Whether `post` was found in a feed on the same host as its link.
+fn is_from_own_site(post: &Post) -> bool {
+  let host = |url: &str| {
+    url::Url::parse(url).ok().and_then(|url| {
+      url
+        .host_str()
+        .map(|host| host.trim_start_matches("www.").to_owned())
     })
-    .buffer_unordered(CONCURRENT_REQUESTS)
-    .collect::<Vec<Option<Blog>>>()
-    .await
+  };
+
+  host(&post.link).is_some_and(|site| {
+    post
+      .feeds
+      .iter()
+      .any(|feed| host(feed).as_ref() == Some(&site))
+  })
 }
 
-/// Parses links from `feeds.txt`.
+/// Downloads all the web feeds specified in `feeds.txt`, keeping track
+/// of the ones that failed.
 ///
-/// Assumed one link per line. Any text between a `#` and a line end
-/// is considered a comment.
+/// Posts are filtered with the `filters` of the `[defaults]` of the config file, if any.
+/// Any configured `WINDOW` is ignored, posts are kept from the last `days` days.
+pub fn download_digest(days: i64) -> Digest {
+  let config = Config {
+    days,
+    window: None,
+    ..Config::load_defaults(None).expect("Error in reading the config file")
+  };
+
+  config
+    .fetch_pipeline()
+    .and_then(|pipeline| pipeline.fetch_blocking())
+    .expect("Error in reading the feeds")
+}
+
+/// Downloads the given web feeds, keeping the posts from the last `days` days.
+///
+/// This blocks until every feed is downloaded, see [`fetch_digest_async`] or
+/// [`stream_blogs`] for async code and [`Pipeline`] to replace any of the stages.
+pub fn fetch_digest(links: Vec<String>, days: i64, options: ParseOptions) -> Digest {
+  block_on(fetch_digest_async(links, days, options)).expect("Could not build tokio runtime")
+}
+
+/// Same as [`fetch_digest`], without blocking.
+pub async fn fetch_digest_async(links: Vec<String>, days: i64, options: ParseOptions) -> Digest {
+  let mut digest: Digest = stream_blogs(links, days, options).collect().await;
+  digest.dedupe();
+  digest
+}
+
+/// Downloads the given web feeds, yielding each of them as soon as it is downloaded and parsed.
+///
+/// Blogs without posts from the last `days` days are skipped. Posts are not deduplicated
+/// across feeds, see [`Digest::dedupe`].
+pub fn stream_blogs(
+  links: Vec<String>,
+  days: i64,
+  options: ParseOptions,
+) -> BoxStream<'static, FeedResult> {
+  Pipeline::new(links)
+    .with_parser(WebFeedParser::new(options))
+    .with_filter(RecentPosts::new(days))
+    .into_stream()
+    .expect("A list of feeds can always be read")
+}
+
+/// Parses links from `feeds.txt`, or the `FEEDS` environment variable if it is set.
+///
+/// See [`parse_feeds`].
 pub fn read_feeds() -> Vec<String> {
-  let links = std::env::var("FEEDS")
-    .or_else(|_| fs::read_to_string("feeds.txt"))
-    .expect("Error in reading the feeds");
+  Config::from_env()
+    .feeds()
+    .expect("Error in reading the feeds")
+}
 
+/// Parses a list of feed links.
+///
+/// Assumed one link per line (or separated by `;`). Any text between a `#` and
+/// a line end is considered a comment.
+pub fn parse_feeds(links: &str) -> Vec<String> {
   // Not really necessary but yes
   // https://docs.rs/regex/latest/regex/#example-avoid-compiling-the-same-regex-in-a-loop
   lazy_static! {
@@ -129,83 +324,57 @@ pub fn html_title() -> String {
   format!("<h1>Rss2Email - {}</h1>", Utc::now().date_naive())
 }
 
-/// Generates the HTML contents corresponding to the given Blog collection.
-pub fn map_to_html(blogs: &Vec<Blog>) -> String {
-  let mut res = html_title();
-
-  for blog in blogs {
-    let mut tmp = format!("<h2>{}</h2><ul>", blog.title);
-    for post in &blog.posts {
-      let _ = write!(tmp, "<li><a href=\"{}\">{}</a></li>", post.link, post.title);
-
-      // Removed for now, see https://github.com/AntoniosBarotsis/Rss2Email/issues/38
-      // if let Some(desc) = &post.description {
-      //   tmp.push_str(&format!("<p>{}</p>", desc));
-      // }
-    }
-    tmp.push_str("</ul>");
-    res.push_str(&tmp);
-  }
-
-  res
-}
-
-/// Returns true if the passed date is within `n` days from the current date.
-fn within_n_days(n: i64, date: &DateTime<Utc>) -> bool {
-  let today = Utc::now();
-  let date = date.with_timezone(&Utc);
-  (today - date).num_days() <= n
+/// Generates the HTML contents corresponding to the given Blog collection
+/// using the built-in template.
+pub fn map_to_html(blogs: &[Blog]) -> String {
+  Templates::default()
+    .render_html(blogs, &[])
+    .expect("The built-in HTML template should render")
 }
 
-fn is_supported_content(content_type: &str) -> bool {
-  let supported = [
-    "application/xml",
-    "text/xml",
-    "application/rss+xml",
-    "application/atom+xml",
-    "text/html",
-  ];
-  supported.contains(&content_type)
+/// Generates the plain-text contents corresponding to the given Blog collection
+/// using the built-in template.
+///
+/// This mirrors [`map_to_html`] and is meant to be sent alongside it.
+pub fn map_to_text(blogs: &[Blog]) -> String {
+  Templates::default()
+    .render_text(blogs, &[])
+    .expect("The built-in text template should render")
 }
 
 /// Helper function for downloading the contents of a web page.
+///
+/// Fails if the page is not a web feed (see [`Response::into_feed`](fetch::Response::into_feed)).
 pub async fn get_page_async(url: &str, client: &Client) -> Result<String, Error> {
-  let response = client
-    .get(url)
-    .header(
-      "Accept",
-      "application/xml, text/xml, application/rss+xml, application/atom+xml",
-    )
-    .header("User-Agent", "Rss2Email");
-  let response = response.send().await?;
-
-  let content_type = response
-    .headers()
-    .get(reqwest::header::CONTENT_TYPE)
-    .ok_or_else(|| Error::Generic("No content type header found on request.".to_string()))?
-    .to_str()
-    .map_err(|_e| Error::Generic("Content Type parsing error".to_string()))?
-    .split(';')
-    .collect::<Vec<&str>>()[0]
-    .to_owned();
-
-  if !is_supported_content(&content_type) {
-    return Err(Error::Generic(format!(
-      "Invalid content {} for {}",
-      content_type.as_str(),
-      url
-    )));
-  }
+  fetch::get_response(url, client).await?.into_feed(url)
+}
 
-  if !response.status().is_success() {
-    return Err(Error::Generic(response.text().await?));
-  }
+/// Runs `future` to completion on a new current-thread runtime.
+///
+/// The runtime is started on a separate thread when called from within another runtime,
+/// since blocking one of its threads could deadlock it.
+pub(crate) fn block_on<F>(future: F) -> Result<F::Output, std::io::Error>
+where
+  F: Future + Send,
+  F::Output: Send,
+{
+  let run = || {
+    tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .map(|runtime| runtime.block_on(future))
+  };
 
-  response
-    .text()
-    .await
-    .map(|text| text.trim().to_string())
-    .map_err(|_e| Error::Generic("Body decode error".to_string()))
+  if Handle::try_current().is_ok() {
+    std::thread::scope(|scope| {
+      scope
+        .spawn(run)
+        .join()
+        .expect("The runtime thread panicked")
+    })
+  } else {
+    run()
+  }
 }
 
 /// Helper function that times and prints the elapsed execution time
@@ -213,7 +382,7 @@ pub async fn get_page_async(url: &str, client: &Client) -> Result<String, Error>
 ///
 /// # Usage
 ///
-/// ```
+/// ```no_run
 /// use rss2email_lib::*;
 /// let blogs: Vec<Blog> = time_func(|| download_blogs(7), "download_blogs");
 /// ```
@@ -240,32 +409,3 @@ where
 
   res
 }
-
-impl Display for Error {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    match &self {
-      Self::Reqwest(e) => write!(f, "{e}"),
-      Self::HeaderString(e) => write!(f, "{e}"),
-      Self::Io(e) => write!(f, "{e}"),
-      Self::Generic(e) => write!(f, "{e}"),
-    }
-  }
-}
-
-impl From<std::io::Error> for Error {
-  fn from(error: std::io::Error) -> Self {
-    Self::Io(error)
-  }
-}
-
-impl From<reqwest::Error> for Error {
-  fn from(error: reqwest::Error) -> Self {
-    Self::Reqwest(Box::new(error))
-  }
-}
-
-impl From<http::header::ToStrError> for Error {
-  fn from(error: http::header::ToStrError) -> Self {
-    Self::HeaderString(Box::new(error))
-  }
-}
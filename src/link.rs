@@ -0,0 +1,556 @@
+//! Helpers for comparing and rewriting links.
+//!
+//! [`LinkRewriter`] cleans up the link of every post before it is rendered: it unwraps
+//! [`REDIRECTORS`], strips tracking parameters (`STRIP_PARAMS`, [`TRACKING_PARAMS`] by
+//! default) and applies the `rewrites` of the [config file](crate::config::file):
+//!
+//! ```toml
+//! [[defaults.rewrites]]
+//! from = "reddit.com"
+//! to = "old.reddit.com"
+//!
+//! [[defaults.rewrites]]
+//! from = "/^https://(?:www\\.)?youtube\\.com/(.*)$/"
+//! to = "https://invidious.example/$1"
+//! ```
+//!
+//! `from` is a host, which also matches its `www.` subdomain and is replaced by the host
+//! in `to` (without a scheme, port or path), or a regular expression when written between
+//! slashes, replaced in the whole link with `$1`-style groups. Rules are applied in order.
+//!
+//! Feedburner's `feedproxy` links are replaced by their `origLink` when parsing, if the
+//! feed has one. Other `feedproxy` links and [`SHORTENERS`] such as `t.co` only redirect
+//! through an HTTP request, which [`LinkResolver`] follows with the
+//! [`Fetcher`] of the pipeline, before the links are cleaned up.
+
+use std::{
+  collections::HashMap,
+  fmt::{self, Display},
+  sync::{Arc, Mutex, PoisonError},
+  time::Duration,
+};
+
+use futures::{future::BoxFuture, stream, StreamExt};
+use regex::Regex;
+use serde_derive::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+  fetch::{blocking, url_hash, Fetcher},
+  info,
+  pipeline::{Filter, CONCURRENT_REQUESTS},
+  state::StateStore,
+  Blog,
+};
+
+/// Query parameters that only track where a visitor came from. A trailing `*`
+/// matches any suffix.
+pub const TRACKING_PARAMS: [&str; 14] = [
+  "utm_*", "fbclid", "gclid", "dclid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "_hsenc",
+  "_hsmi", "mkt_tok", "ref_src", "ref_url",
+];
+
+/// Links that wrap another one, as the host, path prefix and query parameter the
+/// wrapped link is in.
+pub const REDIRECTORS: [(&str, &str, &str); 11] = [
+  ("l.facebook.com", "/l.php", "u"),
+  ("lm.facebook.com", "/l.php", "u"),
+  ("l.instagram.com", "/", "u"),
+  ("www.google.com", "/url", "q"),
+  ("google.com", "/url", "q"),
+  ("out.reddit.com", "/", "url"),
+  ("www.youtube.com", "/redirect", "q"),
+  ("t.umblr.com", "/redirect", "z"),
+  ("slack-redir.net", "/link", "url"),
+  ("getpocket.com", "/redirect", "url"),
+  ("click.linksynergy.com", "/deeplink", "murl"),
+];
+
+/// Shorteners whose links only redirect through an HTTP request, as the host and path
+/// prefix of their links.
+pub const SHORTENERS: [(&str, &str); 11] = [
+  ("t.co", "/"),
+  ("bit.ly", "/"),
+  ("buff.ly", "/"),
+  ("ow.ly", "/"),
+  ("dlvr.it", "/"),
+  ("trib.al", "/"),
+  ("lnkd.in", "/"),
+  ("tinyurl.com", "/"),
+  ("goo.gl", "/"),
+  ("feedproxy.google.com", "/~r/"),
+  ("feeds.feedburner.com", "/~r/"),
+];
+
+/// How long following the redirect of a link can take by default.
+pub const RESOLVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How many nested redirectors are unwrapped.
+const MAX_REDIRECTS: usize = 5;
+
+/// Whether the query parameter `name` matches one of `patterns`, see [`TRACKING_PARAMS`].
+pub fn matches_param<S: AsRef<str>>(name: &str, patterns: &[S]) -> bool {
+  patterns.iter().map(AsRef::as_ref).any(|pattern| {
+    pattern.strip_suffix('*').map_or_else(
+      || name.eq_ignore_ascii_case(pattern),
+      |prefix| {
+        name
+          .get(..prefix.len())
+          .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+      },
+    )
+  })
+}
+
+/// A normalized form of `link`, used to tell whether two links point to the same page.
+///
+/// The scheme becomes `https`, the host is lowercased without a leading `www.` or the
+/// default port, and the fragment, [`TRACKING_PARAMS`] and trailing slashes are dropped.
+/// Links that are not absolute URLs are only trimmed.
+pub fn canonical_link(link: &str) -> String {
+  let link = link.trim();
+  let Ok(mut url) = Url::parse(link) else {
+    return link.to_owned();
+  };
+  if url.cannot_be_a_base() {
+    return url.to_string();
+  }
+
+  if url.scheme() == "http" {
+    let _ = url.set_scheme("https");
+  }
+  if let Some(host) = url.host_str().and_then(|host| host.strip_prefix("www.")) {
+    let host = host.to_owned();
+    let _ = url.set_host(Some(&host));
+  }
+  url.set_fragment(None);
+
+  let _ = strip_params(&mut url, &TRACKING_PARAMS);
+  if url.query() == Some("") {
+    url.set_query(None);
+  }
+
+  let path = url.path().trim_end_matches('/').to_owned();
+  url.set_path(&path);
+
+  url.to_string()
+}
+
+/// Removes the query parameters of `url` that match `patterns` (see [`matches_param`]),
+/// keeping the others as they were written. Returns whether any was removed.
+fn strip_params<S: AsRef<str>>(url: &mut Url, patterns: &[S]) -> bool {
+  let Some(query) = url.query() else {
+    return false;
+  };
+
+  let kept = query
+    .split('&')
+    .filter(|pair| {
+      let name = pair.split_once('=').map_or(*pair, |(name, _)| name);
+      !matches_param(name, patterns)
+    })
+    .collect::<Vec<_>>();
+  if kept.len() == query.split('&').count() {
+    return false;
+  }
+
+  let kept = kept.join("&");
+  url.set_query(Some(kept.as_str()).filter(|kept| !kept.is_empty()));
+  true
+}
+
+/// The link wrapped by `url` if it is one of the [`REDIRECTORS`].
+fn unwrap_redirect(url: &Url) -> Option<Url> {
+  let host = url.host_str()?;
+  let (_, _, param) = REDIRECTORS
+    .iter()
+    .find(|(h, path, _)| host == *h && url.path().starts_with(path))?;
+
+  url
+    .query_pairs()
+    .find(|(name, _)| name == param)
+    .and_then(|(_, target)| Url::parse(&target).ok())
+    .filter(|target| matches!(target.scheme(), "http" | "https"))
+}
+
+/// Rewrites links matching `from` with `to`, see the [module](self) documentation.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "RewriteRuleFields")]
+pub struct RewriteRule {
+  pub from: LinkPattern,
+  pub to: String,
+}
+
+/// A [`RewriteRule`] as written in the config file, before it is checked.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RewriteRuleFields {
+  from: LinkPattern,
+  to: String,
+}
+
+impl TryFrom<RewriteRuleFields> for RewriteRule {
+  type Error = String;
+
+  fn try_from(fields: RewriteRuleFields) -> Result<Self, Self::Error> {
+    let rule = Self {
+      from: fields.from,
+      to: fields.to,
+    };
+    rule.check().map(|()| rule)
+  }
+}
+
+impl RewriteRule {
+  /// Fails if `from` is a host but `to` is not a bare host, such as
+  /// `https://old.reddit.com`.
+  pub fn check(&self) -> Result<(), String> {
+    let is_bare_host = |to: &str| {
+      Url::parse(&format!("https://{to}")).is_ok_and(|url| {
+        url.host_str() == Some(to.to_lowercase().as_str())
+          && url.port().is_none()
+          && url.path() == "/"
+          && url.query().is_none()
+      })
+    };
+
+    match &self.from {
+      LinkPattern::Host(host) if !is_bare_host(&self.to) => Err(format!(
+        "Invalid rewrite of {host} to '{}', expected a host such as old.reddit.com",
+        self.to
+      )),
+      _ => Ok(()),
+    }
+  }
+
+  /// `link` rewritten by the rule, or `None` if it does not match.
+  pub fn rewrite(&self, link: &str) -> Option<String> {
+    match &self.from {
+      LinkPattern::Host(host) => {
+        let mut url = Url::parse(link).ok()?;
+        let current = url.host_str()?;
+        if current != host && current.strip_prefix("www.") != Some(host) {
+          return None;
+        }
+        url.set_host(Some(&self.to)).ok()?;
+        Some(url.to_string())
+      }
+      LinkPattern::Regex(regex) => regex
+        .is_match(link)
+        .then(|| regex.replace(link, self.to.as_str()).into_owned()),
+    }
+  }
+}
+
+/// A host, or a regular expression if written as `/regex/`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub enum LinkPattern {
+  Host(String),
+  Regex(Regex),
+}
+
+impl TryFrom<String> for LinkPattern {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    match value
+      .strip_prefix('/')
+      .and_then(|value| value.strip_suffix('/'))
+    {
+      Some(regex) => Regex::new(regex)
+        .map(Self::Regex)
+        .map_err(|e| format!("Invalid regular expression {value}: {e}")),
+      None if value.trim().is_empty() || value.contains('/') => Err(format!(
+        "Invalid rewrite '{value}', expected a host or a /regular expression/"
+      )),
+      None => Ok(Self::Host(value.trim().to_lowercase())),
+    }
+  }
+}
+
+impl Display for LinkPattern {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Host(host) => write!(f, "{host}"),
+      Self::Regex(regex) => write!(f, "/{regex}/"),
+    }
+  }
+}
+
+impl PartialEq for LinkPattern {
+  fn eq(&self, other: &Self) -> bool {
+    self.to_string() == other.to_string()
+  }
+}
+
+impl Eq for LinkPattern {}
+
+/// Cleans up the link of every post, see the [module](self) documentation.
+#[derive(Debug, Clone)]
+pub struct LinkRewriter {
+  strip_params: Vec<String>,
+  unwrap_redirects: bool,
+  rules: Vec<RewriteRule>,
+}
+
+impl Default for LinkRewriter {
+  fn default() -> Self {
+    Self {
+      strip_params: TRACKING_PARAMS.map(ToOwned::to_owned).to_vec(),
+      unwrap_redirects: true,
+      rules: Vec::new(),
+    }
+  }
+}
+
+impl LinkRewriter {
+  /// Strips the [`TRACKING_PARAMS`] and unwraps the [`REDIRECTORS`].
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Replaces the query parameters that are stripped, see [`matches_param`].
+  #[must_use]
+  pub fn with_strip_params(mut self, strip_params: Vec<String>) -> Self {
+    self.strip_params = strip_params;
+    self
+  }
+
+  /// Sets whether the [`REDIRECTORS`] are unwrapped.
+  #[must_use]
+  pub const fn with_unwrap_redirects(mut self, unwrap_redirects: bool) -> Self {
+    self.unwrap_redirects = unwrap_redirects;
+    self
+  }
+
+  /// Sets the rules applied after the redirectors and parameters.
+  #[must_use]
+  pub fn with_rules(mut self, rules: Vec<RewriteRule>) -> Self {
+    self.rules = rules;
+    self
+  }
+
+  /// `link` cleaned up. Links that are left unchanged keep their original form.
+  pub fn rewrite(&self, link: &str) -> String {
+    let mut link = link.to_owned();
+
+    if let Ok(mut url) = Url::parse(link.trim()) {
+      let mut changed = false;
+
+      if self.unwrap_redirects {
+        for _ in 0..MAX_REDIRECTS {
+          let Some(target) = unwrap_redirect(&url) else {
+            break;
+          };
+          url = target;
+          changed = true;
+        }
+      }
+      changed |= strip_params(&mut url, &self.strip_params);
+
+      if changed {
+        link = url.to_string();
+      }
+    }
+
+    for rule in &self.rules {
+      if let Some(rewritten) = rule.rewrite(&link) {
+        link = rewritten;
+      }
+    }
+
+    link
+  }
+}
+
+impl Filter for LinkRewriter {
+  fn apply(&self, _feed: &str, mut blog: Blog) -> Option<Blog> {
+    for post in &mut blog.posts {
+      post.link = self.rewrite(&post.link);
+    }
+
+    Some(blog)
+  }
+}
+
+/// Whether `link` is one of the [`SHORTENERS`].
+pub fn is_shortened(link: &str) -> bool {
+  Url::parse(link.trim()).is_ok_and(|url| {
+    url.host_str().is_some_and(|host| {
+      SHORTENERS
+        .iter()
+        .any(|(h, path)| host.eq_ignore_ascii_case(h) && url.path().starts_with(path))
+    })
+  })
+}
+
+/// Replaces the links of the posts that are [shortened](is_shortened) by the link they
+/// redirect to, see the [module](self) documentation.
+///
+/// Only redirects to web pages are followed, and links that cannot be resolved within
+/// the timeout are left as is. Resolved links are remembered for the lifetime of the
+/// resolver, and kept in a [`StateStore`] if one is set.
+#[derive(Debug)]
+pub struct LinkResolver {
+  timeout: Duration,
+  cache: Option<Arc<dyn StateStore>>,
+  resolved: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl Default for LinkResolver {
+  fn default() -> Self {
+    Self {
+      timeout: RESOLVE_TIMEOUT,
+      cache: None,
+      resolved: Mutex::default(),
+    }
+  }
+}
+
+/// A resolved link kept in the cache of a [`LinkResolver`].
+#[derive(Debug, Serialize, Deserialize)]
+struct ResolvedLink {
+  link: String,
+  target: String,
+}
+
+impl LinkResolver {
+  /// Follows the redirects of the [`SHORTENERS`] for up to [`RESOLVE_TIMEOUT`].
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets how long following the redirect of a single link can take.
+  #[must_use]
+  pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  /// Keeps the resolved links in `store`, under [`resolved_link_key`], so that they are
+  /// only requested once.
+  ///
+  /// Failing to read or write the cache is logged and otherwise ignored.
+  #[must_use]
+  pub fn with_cache(mut self, store: Arc<dyn StateStore>) -> Self {
+    self.cache = Some(store);
+    self
+  }
+
+  /// The link `link` redirects to, `None` if it cannot be resolved.
+  pub async fn resolve(&self, link: &str, fetcher: &dyn Fetcher) -> Option<String> {
+    let remembered = self
+      .resolved
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .get(link)
+      .cloned();
+    if let Some(target) = remembered {
+      return target;
+    }
+
+    let target = match self.cached(link).await {
+      Some(target) => Some(target),
+      None => self.request(link, fetcher).await,
+    };
+    let _ = self
+      .resolved
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .insert(link.to_owned(), target.clone());
+
+    target
+  }
+
+  async fn request(&self, link: &str, fetcher: &dyn Fetcher) -> Option<String> {
+    let target = match tokio::time::timeout(self.timeout, fetcher.resolve(link)).await {
+      Ok(Ok(target)) => target.filter(|target| {
+        Url::parse(target).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+      })?,
+      Ok(Err(e)) => {
+        info!("Could not resolve {link}: {e}");
+        return None;
+      }
+      Err(_) => {
+        info!("Could not resolve {link} within {:?}", self.timeout);
+        return None;
+      }
+    };
+
+    if let Some(store) = &self.cache {
+      let store = Arc::clone(store);
+      let entry = ResolvedLink {
+        link: link.to_owned(),
+        target: target.clone(),
+      };
+      let key = resolved_link_key(link);
+      if let Err(e) = blocking(move || store.as_ref().put_json(&key, &entry)).await {
+        info!("Could not cache the resolved link of {link}: {e}");
+      }
+    }
+
+    Some(target)
+  }
+
+  async fn cached(&self, link: &str) -> Option<String> {
+    let store = Arc::clone(self.cache.as_ref()?);
+    let key = resolved_link_key(link);
+
+    match blocking(move || store.as_ref().get_json::<ResolvedLink>(&key)).await {
+      Ok(entry) => entry
+        .filter(|entry| entry.link == link)
+        .map(|entry| entry.target),
+      Err(e) => {
+        info!("Could not read the resolved link of {link}: {e}");
+        None
+      }
+    }
+  }
+}
+
+/// The key of the [`StateStore`] the link that `link` redirects to is kept under.
+pub fn resolved_link_key(link: &str) -> String {
+  format!("links/{:016x}.json", url_hash(link))
+}
+
+impl Filter for LinkResolver {
+  /// Links can only be resolved with a fetcher, see [`Filter::apply_async`].
+  fn apply(&self, _feed: &str, blog: Blog) -> Option<Blog> {
+    Some(blog)
+  }
+
+  fn apply_async<'a>(
+    &'a self,
+    _feed: &'a str,
+    mut blog: Blog,
+    fetcher: &'a dyn Fetcher,
+  ) -> BoxFuture<'a, Option<Blog>> {
+    Box::pin(async move {
+      let links = blog
+        .posts
+        .iter()
+        .map(|post| post.link.trim().to_owned())
+        .collect::<Vec<_>>();
+      let targets = stream::iter(links)
+        .map(|link| async move {
+          if is_shortened(&link) {
+            self.resolve(&link, fetcher).await
+          } else {
+            None
+          }
+        })
+        .buffered(CONCURRENT_REQUESTS)
+        .collect::<Vec<_>>()
+        .await;
+
+      for (post, target) in blog.posts.iter_mut().zip(targets) {
+        if let Some(target) = target {
+          post.link = target;
+        }
+      }
+
+      Some(blog)
+    })
+  }
+}
@@ -1,81 +1,287 @@
-mod email;
+#[cfg(not(feature = "aws-lambda"))]
+mod cli;
+#[cfg(not(feature = "aws-lambda"))]
+mod daemon;
 mod logger;
 
-use crate::email::email_provider::{get_email_provider, EmailProvider};
+use std::path::PathBuf;
+
+use chrono::Utc;
 use dotenvy::dotenv;
 use env_logger::Env;
-use rss2email_lib::{download_blogs, html_title, map_to_html, time_func};
+use rss2email_lib::{
+  config::{Config, Validation},
+  state,
+  summary::{Delivery, RunSummary},
+  time_func,
+};
+
+/// What [`core_main`] does with the rendered email.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "aws-lambda", allow(dead_code))]
+enum Mode {
+  /// Sends the email.
+  Send,
+  /// Logs the email instead of sending it.
+  DryRun,
+  /// Writes one of the bodies of the email to a file, or stdout if there is none.
+  Preview { output: Option<PathBuf>, text: bool },
+}
+
+/// Sets up logging and loads the `.env` file.
+fn init() {
+  let _ = env_logger::Builder::from_env(Env::default().default_filter_or("info")).try_init();
+  let _env = dotenv();
+}
+
+/// Prints the problems found in `config`, failing if any of them is an error.
+fn check(config: &Config, validation: &Validation) -> Result<(), String> {
+  if let Some(profile) = config
+    .profile
+    .as_ref()
+    .filter(|_| !validation.errors.is_empty() || !validation.warnings.is_empty())
+  {
+    eprintln!("[{profile}]");
+  }
+  eprint!("{validation}");
+
+  if validation.is_valid() {
+    Ok(())
+  } else {
+    Err(format!(
+      "Found {} configuration error(s)",
+      validation.errors.len()
+    ))
+  }
+}
+
+/// Checks the parts of `config` that are needed in `mode`.
+fn validate(config: &Config, mode: &Mode) -> Validation {
+  match mode {
+    Mode::Send => config.validate(),
+    Mode::DryRun | Mode::Preview { .. } => config
+      .validate_feeds()
+      .and(config.validate_templates())
+      .and(config.validate_state()),
+  }
+}
 
 /// The core logic of the main function. This should be called regardless of where
 /// you are running the project at.
-fn core_main() -> Result<(), String> {
-  if env_logger::Builder::from_env(Env::default().default_filter_or("info"))
-    .try_init()
-    .is_ok()
-  {};
+///
+/// Errors that happen before the feeds are downloaded are returned as `Err`, a failed
+/// delivery is reported in the summary. Runs that send the email are recorded in the
+/// state store.
+fn core_main(config: &Config, mode: &Mode) -> Result<RunSummary, String> {
+  check(config, &validate(config, mode))?;
+  let store = config.state_store().map_err(|e| e.to_string())?;
 
-  let _env = dotenv();
-  let days_default: i64 = 7;
+  let started_at = Utc::now();
+  let summary = digest(config, mode)?;
 
-  let days = std::env::var("DAYS").map_or(days_default, |v| {
-    v.parse::<i64>()
-      .map_err(|e| {
-        warn!("Invalid number for days, using defaults! error: {e}");
-      })
-      .unwrap_or(days_default)
-  });
+  if let (Some(store), Mode::Send) = (&store, mode) {
+    if let Err(e) = state::record_run(store.as_ref(), &summary, started_at) {
+      error!("Could not record the run: {e}");
+    }
+  }
+
+  Ok(summary)
+}
 
-  info!("Days set to {days}",);
+/// Downloads the feeds of `config` and renders the email, handling it according to `mode`.
+fn digest(config: &Config, mode: &Mode) -> Result<RunSummary, String> {
+  if let Some(profile) = &config.profile {
+    info!("Running profile {profile}");
+  }
+  match &config.window {
+    Some(window) => info!("Window set to {window}"),
+    None => info!("Days set to {}", config.days),
+  }
+
+  let mut pipeline = config.pipeline().map_err(|e| e.to_string())?;
+  if matches!(mode, Mode::Send) {
+    pipeline = pipeline.with_mailer(config.email_delivery().map_err(|e| e.to_string())?);
+  }
+  let feeds = pipeline.feeds().map_err(|e| e.to_string())?;
 
-  let blogs = time_func(|| download_blogs(days), "download_blogs");
+  let digest = time_func(
+    || pipeline.fetch_blocking().map_err(|e| e.to_string()),
+    "download_blogs",
+  )?;
 
-  let posts_amt = blogs.iter().flat_map(|x| &x.posts).count();
+  let mut summary = RunSummary::new(config.profile.clone(), feeds.len(), &digest);
+  let posts_amt = summary.posts;
   info!(
     "Downloaded {} blogs with {} posts total.",
-    blogs.len(),
-    posts_amt
+    summary.blogs, posts_amt
   );
 
-  if posts_amt == 0
-    && std::env::var("SKIP_IF_NO_NEW_POSTS").is_ok_and(|v| v.to_lowercase() == "true")
-  {
+  if posts_amt == 0 && config.skip_if_no_new_posts && matches!(mode, Mode::Send) {
     info!("No posts found and SKIP_IF_NO_NEW_POSTS was set to true, exiting...");
-    return Ok(());
+    summary.delivery = Delivery::Skipped;
+    return Ok(summary);
   }
 
-  let html = if blogs.is_empty() {
-    format!("{}\nNo new posts were found. You can set \"SKIP_IF_NO_NEW_POSTS\" to \"true\" to avoid sending this email.", html_title())
-  } else {
-    map_to_html(&blogs)
+  let message = match pipeline.render(&digest) {
+    Ok(message) => message,
+    Err(e) => {
+      error!("{}", e);
+      summary.delivery = Delivery::Failed {
+        error: e.to_string(),
+      };
+      return Ok(summary);
+    }
   };
 
-  if cfg!(debug_assertions) {
-    info!("{}", html);
+  match mode {
+    Mode::Send => {
+      summary.delivery = match pipeline.send_blocking(&digest, &message) {
+        Ok(()) => Delivery::Sent,
+        Err(e) => {
+          error!("{}", e);
+          Delivery::Failed {
+            error: e.to_string(),
+          }
+        }
+      };
+    }
+    Mode::DryRun => {
+      info!("Dry run, the email was not sent.");
+      info!("{}", message.html);
+      info!("{}", message.text);
+      summary.delivery = Delivery::DryRun;
+    }
+    Mode::Preview { output, text } => {
+      let body = if *text { message.text } else { message.html };
+      match output {
+        Some(path) => std::fs::write(path, body)
+          .map_err(|e| format!("Could not write {}: {e}", path.display()))?,
+        None => println!("{body}"),
+      }
+      summary.delivery = Delivery::DryRun;
+    }
+  }
+
+  Ok(summary)
+}
+
+/// Calls [`core_main`] for every config, continuing past the ones that fail.
+fn run_all(configs: &[Config], mode: &Mode) -> Vec<RunSummary> {
+  configs
+    .iter()
+    .map(|config| {
+      core_main(config, mode).unwrap_or_else(|e| {
+        error!("{e}");
+        RunSummary::failed(config.profile.clone(), e)
+      })
+    })
+    .collect()
+}
+
+/// Fails if any of the runs failed, naming them.
+fn failures(summaries: &[RunSummary]) -> Result<(), String> {
+  let failed = summaries
+    .iter()
+    .filter(|summary| summary.is_failure())
+    .map(|summary| summary.profile.as_deref().unwrap_or("the digest"))
+    .collect::<Vec<_>>();
+
+  if failed.is_empty() {
+    Ok(())
   } else {
-    // Only load email related variables if ran on release
-    let sender_address = std::env::var("EMAIL_ADDRESS").expect("EMAIL_ADDRESS must be set.");
-    let recipient_addresses =
-      std::env::var("RECIPIENT_ADDRESSES").expect("RECIPIENT_ADDRESSES must be set");
-
-    let recipient_addresses = recipient_addresses.split(',').collect::<Vec<&str>>();
-    let subject = std::env::var("SUBJECT")
-      .expect("SUBJECT must be set.")
-      .replace("$POST_COUNT", &posts_amt.to_string());
-
-    if let Err(e) = get_email_provider()
-      .map(|provider| provider.send_email(&sender_address, recipient_addresses, &subject, &html))?
-    {
-      error!("{}", e);
-    };
+    Err(format!("Failed to run {}", failed.join(", ")))
   }
+}
 
-  Ok(())
+/// The only config, failing if there are several profiles to pick from.
+#[cfg(not(feature = "aws-lambda"))]
+fn single(configs: &[Config]) -> Result<&Config, String> {
+  match configs {
+    [config] => Ok(config),
+    _ => Err(format!(
+      "Several profiles are configured, pick one with --profile: {}",
+      configs
+        .iter()
+        .filter_map(|config| config.profile.as_deref())
+        .collect::<Vec<_>>()
+        .join(", ")
+    )),
+  }
 }
 
-/// Calls [`core_main`].
+/// Parses the command line and runs the requested subcommand.
 #[cfg(not(feature = "aws-lambda"))]
 fn main() -> Result<(), String> {
-  core_main()
+  use clap::Parser;
+  use cli::{Cli, Command};
+
+  let cli = Cli::parse();
+  init();
+
+  let mut configs =
+    Config::load(cli.config.as_deref(), cli.profile.as_deref()).map_err(|e| e.to_string())?;
+  for config in &mut configs {
+    cli.overrides.apply(config);
+  }
+
+  match cli.command.unwrap_or(Command::Run { dry_run: false }) {
+    Command::Run { dry_run } => {
+      let mode = if dry_run { Mode::DryRun } else { Mode::Send };
+      failures(&run_all(&configs, &mode))
+    }
+    Command::Preview { output, text } => {
+      core_main(single(&configs)?, &Mode::Preview { output, text }).map(|_summary| ())
+    }
+    Command::Fetch { json } => {
+      let config = single(&configs)?;
+      check(config, &config.validate_feeds())?;
+      cli::fetch(config, json)
+    }
+    Command::Daemon {
+      schedule,
+      timezone,
+      dry_run,
+    } => {
+      let mode = if dry_run { Mode::DryRun } else { Mode::Send };
+      let mut jobs = Vec::new();
+      let mut errors = 0;
+
+      for mut config in configs {
+        if schedule.is_some() {
+          config.schedule.clone_from(&schedule);
+        }
+        if let Some(timezone) = &timezone {
+          config.timezone.clone_from(timezone);
+        }
+
+        let validation = validate(&config, &mode).and(config.validate_schedule());
+        if check(&config, &validation).is_err() {
+          errors += validation.errors.len();
+          continue;
+        }
+
+        let schedule = config.schedule().map_err(|e| e.to_string())?;
+        let mode = mode.clone();
+        jobs.push(daemon::Job {
+          name: config
+            .profile
+            .clone()
+            .unwrap_or_else(|| "the digest".to_owned()),
+          schedule,
+          run: Box::new(move || core_main(&config, &mode).and_then(|summary| failures(&[summary]))),
+        });
+      }
+
+      if errors > 0 {
+        return Err(format!("Found {errors} configuration error(s)"));
+      }
+
+      info!("Running as a daemon");
+      daemon::run(jobs)
+    }
+    Command::ValidateConfig => cli::validate_config(&configs),
+    Command::Feeds(command) => cli::feeds(single(&configs)?, command),
+  }
 }
 
 /// Calls [`aws_lambda::lambda_wrapper()`].
@@ -87,20 +293,75 @@ fn main() -> Result<(), aws_lambda::LambdaErr> {
 /// Contains necessary boiler-plate that allows the project to run on AWS Lambda.
 #[cfg(feature = "aws-lambda")]
 mod aws_lambda {
-  use crate::core_main;
+  use crate::{failures, init, run_all, Mode};
   use lambda_runtime::{run, service_fn, Error, LambdaEvent};
-  use rss2email_lib::warn;
+  use rss2email_lib::{config::Config, summary::RunSummary};
   use serde::Deserialize;
   pub type LambdaErr = Error;
 
-  #[derive(Deserialize)]
-  struct Request {}
+  /// The event the function is invoked with. Every field is optional, so that
+  /// one function can serve several schedules with different inputs.
+  #[derive(Debug, Default, Deserialize)]
+  #[serde(default)]
+  struct Request {
+    /// Runs only this profile of the config file, instead of all of them.
+    profile: Option<String>,
+    /// Overrides `DAYS`, and `WINDOW` if it is set.
+    days: Option<i64>,
+    /// Overrides `WINDOW`.
+    window: Option<String>,
+    /// Overrides `RECIPIENT_ADDRESSES`.
+    recipients: Option<Vec<String>>,
+    /// Renders the email without sending it.
+    dry_run: bool,
+    /// Fetches only these feeds.
+    feeds: Option<Vec<String>>,
+  }
+
+  impl Request {
+    fn apply(&self, config: &mut Config) {
+      if let Some(days) = self.days {
+        config.days = days;
+        config.window = None;
+        config.clear_invalid_var("DAYS");
+      }
+      if self.window.is_some() {
+        config.window.clone_from(&self.window);
+      }
+      if self.recipients.is_some() {
+        config.recipient_addresses = self.recipients.clone().unwrap_or_default();
+      }
+      if self.feeds.is_some() {
+        config.feeds.clone_from(&self.feeds);
+      }
+    }
+  }
+
+  /// Runs the requested profiles and returns their summaries, failing if any of
+  /// them could not be delivered.
+  #[allow(clippy::unused_async)]
+  async fn function_handler(event: LambdaEvent<Request>) -> Result<Vec<RunSummary>, Error> {
+    init();
+    let request = event.payload;
 
-  #[allow(clippy::unused_async, clippy::no_effect_underscore_binding)]
-  async fn function_handler(_event: LambdaEvent<Request>) -> Result<(), Error> {
-    // Extract some useful information from the request
-    let _res = core_main().map_err(|x| warn!("{}", x));
-    Ok(())
+    let mut configs = Config::load(None, request.profile.as_deref())?;
+    for config in &mut configs {
+      request.apply(config);
+    }
+
+    let mode = if request.dry_run {
+      Mode::DryRun
+    } else {
+      Mode::Send
+    };
+    // The profiles are run with blocking calls, which must not run on the async runtime.
+    let summaries = tokio::task::spawn_blocking(move || run_all(&configs, &mode)).await?;
+
+    if let Err(e) = failures(&summaries) {
+      return Err(format!("{e}: {}", serde_json::to_string(&summaries)?).into());
+    }
+
+    Ok(summaries)
   }
 
   #[tokio::main]
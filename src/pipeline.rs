@@ -0,0 +1,563 @@
+//! A reusable pipeline that turns a list of web feeds into a digest email.
+//!
+//! Every stage is a trait object, so that any of them can be replaced without
+//! touching environment variables:
+//!
+//! 1. a [`FeedSource`] lists the feeds to download
+//! 2. a [`Fetcher`] downloads each of them (see [`fetch`](crate::fetch))
+//! 3. the [`Parser`]s turn them into [`Blog`]s, the first one that succeeds wins
+//! 4. the [`Filter`]s drop or change blogs and posts, blogs left without posts are dropped
+//!    (see [`filter`](crate::filter) for rules read from the config file)
+//! 5. a [`Renderer`] turns the [`Digest`] into an [`EmailMessage`]
+//! 6. a [`Mailer`] delivers it
+//!
+//! ```no_run
+//! use rss2email_lib::pipeline::{Pipeline, RecentPosts};
+//!
+//! let pipeline = Pipeline::new(vec!["https://blog.rust-lang.org/feed.xml".to_owned()])
+//!   .with_filter(RecentPosts::new(7));
+//!
+//! let digest = pipeline.fetch_blocking().expect("The feeds are listed");
+//! let message = pipeline.render(&digest).expect("The built-in templates render");
+//! println!("{}", message.text);
+//! ```
+
+use std::{fmt::Debug, path::PathBuf, sync::Arc};
+
+use chrono::{DateTime, Duration, Utc};
+use futures::{
+  future::{self, BoxFuture},
+  stream::{self, BoxStream},
+  Stream, StreamExt,
+};
+use itertools::Itertools;
+use url::Url;
+
+use crate::{
+  block_on,
+  blog::{Blog, FailedFeed},
+  email::{
+    email_provider::{EmailProvider, EmailProviders},
+    message::EmailMessage,
+  },
+  parse_feeds,
+  template::Templates,
+  warn,
+  window::{default_future_tolerance, FuturePosts, Window},
+  xml::{parse_web_feed_with, ParseOptions},
+  Digest, Error,
+};
+
+pub use crate::fetch::{Fetcher, HttpFetcher};
+
+/// The outcome of downloading and parsing a single feed.
+pub type FeedResult = Result<Blog, FailedFeed>;
+
+/// How many feeds are downloaded at the same time.
+pub const CONCURRENT_REQUESTS: usize = 10;
+
+/// Lists the feeds to download.
+pub trait FeedSource: Debug + Send + Sync {
+  /// The links of the feeds.
+  fn feeds(&self) -> Result<Vec<String>, Error>;
+}
+
+/// Turns the contents of a feed into a [`Blog`].
+pub trait Parser: Debug + Send + Sync {
+  /// Parses `content`, the body of the feed downloaded from `feed`. If the request was
+  /// redirected, `feed` is the URL it was served from.
+  fn parse(&self, feed: &str, content: &str) -> Result<Blog, Error>;
+}
+
+/// Drops or changes the blogs of a digest.
+pub trait Filter: Debug + Send + Sync {
+  /// Returns `blog`, downloaded from `feed`, with only the posts to keep, or `None` to
+  /// drop it altogether.
+  fn apply(&self, feed: &str, blog: Blog) -> Option<Blog>;
+
+  /// Same as [`Filter::apply`], for filters that make requests with the `fetcher` of the
+  /// pipeline, such as [`LinkResolver`](crate::link::LinkResolver). The pipeline only
+  /// calls this one, which calls [`Filter::apply`] by default.
+  fn apply_async<'a>(
+    &'a self,
+    feed: &'a str,
+    blog: Blog,
+    _fetcher: &'a dyn Fetcher,
+  ) -> BoxFuture<'a, Option<Blog>> {
+    Box::pin(future::ready(self.apply(feed, blog)))
+  }
+
+  /// Called once every feed went through the filter, for example to log statistics.
+  fn finish(&self) {}
+}
+
+/// Turns a [`Digest`] into the body of an email.
+pub trait Renderer: Debug + Send + Sync {
+  /// Renders the HTML and plain-text bodies of `digest`.
+  fn render(&self, digest: &Digest) -> Result<EmailMessage, Error>;
+}
+
+/// Delivers a rendered digest.
+pub trait Mailer: Debug + Send + Sync {
+  /// Delivers `message`, the rendered `digest`.
+  fn send<'a>(
+    &'a self,
+    digest: &'a Digest,
+    message: &'a EmailMessage,
+  ) -> BoxFuture<'a, Result<(), Error>>;
+}
+
+impl FeedSource for Vec<String> {
+  fn feeds(&self) -> Result<Vec<String>, Error> {
+    Ok(self.clone())
+  }
+}
+
+/// Reads the feeds from a file in the `feeds.txt` format (see [`parse_feeds`]).
+///
+/// The file is read every time the feeds are listed, so that changes are picked up
+/// between runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedsFile {
+  path: PathBuf,
+}
+
+impl FeedsFile {
+  pub fn new(path: impl Into<PathBuf>) -> Self {
+    Self { path: path.into() }
+  }
+}
+
+impl FeedSource for FeedsFile {
+  fn feeds(&self) -> Result<Vec<String>, Error> {
+    let links = std::fs::read_to_string(&self.path).map_err(|e| {
+      Error::Generic(format!(
+        "Error in reading the feeds from {}: {e}",
+        self.path.display()
+      ))
+    })?;
+
+    Ok(parse_feeds(&links))
+  }
+}
+
+/// Parses RSS and Atom feeds (see [`parse_web_feed_with`]).
+///
+/// Relative links are resolved against the URL the feed was served from, which replaces
+/// the [`ParseOptions::base_url`] of the options.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WebFeedParser {
+  options: ParseOptions,
+}
+
+impl WebFeedParser {
+  pub const fn new(options: ParseOptions) -> Self {
+    Self { options }
+  }
+}
+
+impl Parser for WebFeedParser {
+  fn parse(&self, feed: &str, content: &str) -> Result<Blog, Error> {
+    let options = ParseOptions {
+      base_url: Url::parse(feed)
+        .ok()
+        .or_else(|| self.options.base_url.clone()),
+      ..self.options.clone()
+    };
+
+    Ok(parse_web_feed_with(content, &options)?)
+  }
+}
+
+/// Keeps the posts of a time [`Window`].
+///
+/// Posts dated more than the future tolerance (an hour by default) from now are
+/// dropped, or clamped to now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecentPosts {
+  window: Window,
+  future_tolerance: Duration,
+  future_posts: FuturePosts,
+}
+
+impl RecentPosts {
+  /// Keeps the posts from the last `days` days, to the second.
+  pub fn new(days: i64) -> Self {
+    Self::within(Window::days(days))
+  }
+
+  /// Keeps the posts of `window`, which is evaluated when the posts are filtered.
+  pub fn within(window: Window) -> Self {
+    Self {
+      window,
+      future_tolerance: default_future_tolerance(),
+      future_posts: FuturePosts::default(),
+    }
+  }
+
+  /// Sets how far in the future posts can be dated.
+  #[must_use]
+  pub const fn with_future_tolerance(mut self, future_tolerance: Duration) -> Self {
+    self.future_tolerance = future_tolerance;
+    self
+  }
+
+  /// Sets what happens to posts dated beyond the future tolerance.
+  #[must_use]
+  pub const fn with_future_posts(mut self, future_posts: FuturePosts) -> Self {
+    self.future_posts = future_posts;
+    self
+  }
+}
+
+impl Filter for RecentPosts {
+  fn apply(&self, _feed: &str, mut blog: Blog) -> Option<Blog> {
+    let now = Utc::now();
+    let (start, end) = self.window.bounds(now);
+    let latest = now + self.future_tolerance;
+    let within = |date: DateTime<Utc>| date >= start && end.map_or(true, |end| date < end);
+
+    if blog.most_recent_pub_date < start {
+      return None;
+    }
+
+    blog.posts.retain_mut(|post| {
+      if post.pub_date > latest {
+        match self.future_posts {
+          FuturePosts::Clamp => post.pub_date = now,
+          FuturePosts::Drop => return false,
+        }
+      }
+
+      within(post.pub_date)
+    });
+    if blog.most_recent_pub_date > latest {
+      blog.most_recent_pub_date = match self.future_posts {
+        FuturePosts::Clamp => now,
+        FuturePosts::Drop => blog
+          .posts
+          .iter()
+          .map(|post| post.pub_date)
+          .max()
+          .unwrap_or(start),
+      };
+    }
+    Some(blog)
+  }
+}
+
+impl Renderer for Templates {
+  fn render(&self, digest: &Digest) -> Result<EmailMessage, Error> {
+    Self::render(self, &digest.blogs, &digest.failed_feeds)
+  }
+}
+
+/// Sends the digest through one of the [`EmailProviders`].
+#[derive(Debug)]
+pub struct EmailDelivery {
+  provider: EmailProviders,
+  sender_address: String,
+  recipient_addresses: Vec<String>,
+  subject: String,
+}
+
+impl EmailDelivery {
+  /// Sends the digest from `sender_address` to `recipient_addresses`.
+  ///
+  /// `$POST_COUNT` in `subject` is replaced with the number of posts in the digest.
+  pub fn new(
+    provider: EmailProviders,
+    sender_address: impl Into<String>,
+    recipient_addresses: Vec<String>,
+    subject: impl Into<String>,
+  ) -> Self {
+    Self {
+      provider,
+      sender_address: sender_address.into(),
+      recipient_addresses,
+      subject: subject.into(),
+    }
+  }
+}
+
+impl Mailer for EmailDelivery {
+  fn send<'a>(
+    &'a self,
+    digest: &'a Digest,
+    message: &'a EmailMessage,
+  ) -> BoxFuture<'a, Result<(), Error>> {
+    Box::pin(async move {
+      let subject = self
+        .subject
+        .replace("$POST_COUNT", &digest.post_count().to_string());
+      let recipient_addresses = self
+        .recipient_addresses
+        .iter()
+        .map(String::as_str)
+        .collect();
+
+      self
+        .provider
+        .send_email_async(&self.sender_address, recipient_addresses, &subject, message)
+        .await
+        .map_err(Error::from)
+    })
+  }
+}
+
+/// Downloads, parses, filters, renders and delivers a digest.
+///
+/// Created with [`Pipeline::new`], which uses the built-in stages for everything but
+/// the feed source and does not filter or deliver anything. Every stage can then be
+/// replaced with the `with_*` methods.
+#[derive(Debug)]
+pub struct Pipeline {
+  source: Box<dyn FeedSource>,
+  fetcher: Box<dyn Fetcher>,
+  parsers: Vec<Box<dyn Parser>>,
+  filters: Vec<Box<dyn Filter>>,
+  renderer: Box<dyn Renderer>,
+  mailer: Option<Box<dyn Mailer>>,
+  concurrency: usize,
+  dedupe: bool,
+}
+
+impl Pipeline {
+  /// A pipeline that downloads the feeds listed by `source` over HTTP, parses them as
+  /// RSS or Atom and renders them with the built-in [`Templates`].
+  pub fn new(source: impl FeedSource + 'static) -> Self {
+    Self {
+      source: Box::new(source),
+      fetcher: Box::new(HttpFetcher::default()),
+      parsers: vec![Box::new(WebFeedParser::default())],
+      filters: Vec::new(),
+      renderer: Box::new(Templates::default()),
+      mailer: None,
+      concurrency: CONCURRENT_REQUESTS,
+      dedupe: true,
+    }
+  }
+
+  /// Replaces the feed source.
+  #[must_use]
+  pub fn with_source(mut self, source: impl FeedSource + 'static) -> Self {
+    self.source = Box::new(source);
+    self
+  }
+
+  /// Replaces the fetcher.
+  #[must_use]
+  pub fn with_fetcher(mut self, fetcher: impl Fetcher + 'static) -> Self {
+    self.fetcher = Box::new(fetcher);
+    self
+  }
+
+  /// Replaces every parser with `parser`.
+  #[must_use]
+  pub fn with_parser(mut self, parser: impl Parser + 'static) -> Self {
+    self.parsers = vec![Box::new(parser)];
+    self
+  }
+
+  /// Adds a parser that is tried when the previous ones fail.
+  #[must_use]
+  pub fn with_fallback_parser(mut self, parser: impl Parser + 'static) -> Self {
+    self.parsers.push(Box::new(parser));
+    self
+  }
+
+  /// Adds a filter, applied after the previous ones.
+  #[must_use]
+  pub fn with_filter(mut self, filter: impl Filter + 'static) -> Self {
+    self.filters.push(Box::new(filter));
+    self
+  }
+
+  /// Replaces the renderer.
+  #[must_use]
+  pub fn with_renderer(mut self, renderer: impl Renderer + 'static) -> Self {
+    self.renderer = Box::new(renderer);
+    self
+  }
+
+  /// Sets how the digest is delivered.
+  #[must_use]
+  pub fn with_mailer(mut self, mailer: impl Mailer + 'static) -> Self {
+    self.mailer = Some(Box::new(mailer));
+    self
+  }
+
+  /// Sets how many feeds are downloaded at the same time, [`CONCURRENT_REQUESTS`] by
+  /// default.
+  #[must_use]
+  pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+    self.concurrency = concurrency.max(1);
+    self
+  }
+
+  /// Sets whether [`Pipeline::fetch`] merges the posts found in several feeds, which it
+  /// does by default.
+  #[must_use]
+  pub const fn with_dedupe(mut self, dedupe: bool) -> Self {
+    self.dedupe = dedupe;
+    self
+  }
+
+  /// The feeds listed by the source.
+  pub fn feeds(&self) -> Result<Vec<String>, Error> {
+    self.source.feeds()
+  }
+
+  /// Downloads, parses and filters every feed, yielding each result as soon as it is ready.
+  ///
+  /// Blogs that the filters leave without posts are skipped. Posts are not deduplicated
+  /// across feeds. Fails only if the feeds cannot be listed.
+  pub fn stream(&self) -> Result<impl Stream<Item = FeedResult> + Send + '_, Error> {
+    Self::feed_stream(self)
+  }
+
+  /// Same as [`Pipeline::stream`], but the stream owns the pipeline so that it can be
+  /// handed to another task.
+  pub fn into_stream(self) -> Result<BoxStream<'static, FeedResult>, Error> {
+    Self::feed_stream(Arc::new(self)).map(StreamExt::boxed)
+  }
+
+  /// Builds the stream of [`Pipeline::stream`] from anything that points to a pipeline,
+  /// cloning it into every download.
+  fn feed_stream<'a, P>(pipeline: P) -> Result<impl Stream<Item = FeedResult> + Send + 'a, Error>
+  where
+    P: std::ops::Deref<Target = Self> + Clone + Send + Sync + 'a,
+  {
+    let feeds = pipeline.feeds()?;
+    let concurrency = pipeline.concurrency;
+    let feed_pipeline = pipeline.clone();
+
+    Ok(
+      stream::iter(feeds)
+        .map(move |link| {
+          let pipeline = feed_pipeline.clone();
+          async move { pipeline.fetch_feed(link).await }
+        })
+        .buffer_unordered(concurrency)
+        .chain(stream::once(future::lazy(move |_| {
+          pipeline.finish();
+          Ok(None)
+        })))
+        .filter_map(|result| future::ready(result.transpose())),
+    )
+  }
+
+  /// Downloads, parses and filters every feed, then merges the posts found in several
+  /// of them (see [`Digest::dedupe`]) unless disabled with [`Pipeline::with_dedupe`].
+  ///
+  /// Feeds that cannot be downloaded or parsed are listed in
+  /// [`Digest::failed_feeds`], only a failing source is an error.
+  pub async fn fetch(&self) -> Result<Digest, Error> {
+    let mut digest: Digest = self.stream()?.collect().await;
+    if self.dedupe {
+      digest.dedupe();
+    }
+
+    Ok(digest)
+  }
+
+  /// Same as [`Pipeline::fetch`], for callers outside of an async context.
+  ///
+  /// Runs on a new runtime, on a separate thread if called from within a runtime.
+  pub fn fetch_blocking(&self) -> Result<Digest, Error> {
+    block_on(self.fetch())?
+  }
+
+  /// Renders `digest` into the body of an email.
+  pub fn render(&self, digest: &Digest) -> Result<EmailMessage, Error> {
+    self.renderer.render(digest)
+  }
+
+  /// Delivers `message`, failing if no mailer was set.
+  pub async fn send(&self, digest: &Digest, message: &EmailMessage) -> Result<(), Error> {
+    self
+      .mailer
+      .as_ref()
+      .ok_or_else(|| Error::Generic("No mailer was set on the pipeline".to_owned()))?
+      .send(digest, message)
+      .await
+  }
+
+  /// Same as [`Pipeline::send`], for callers outside of an async context.
+  pub fn send_blocking(&self, digest: &Digest, message: &EmailMessage) -> Result<(), Error> {
+    block_on(self.send(digest, message))?
+  }
+
+  /// Fetches, renders and delivers the digest, returning it.
+  pub async fn run(&self) -> Result<Digest, Error> {
+    let digest = self.fetch().await?;
+    let message = self.render(&digest)?;
+    self.send(&digest, &message).await?;
+
+    Ok(digest)
+  }
+
+  /// Same as [`Pipeline::run`], for callers outside of an async context.
+  pub fn run_blocking(&self) -> Result<Digest, Error> {
+    block_on(self.run())?
+  }
+
+  /// Downloads, parses and filters the feed at `link`, returning `None` if the filters
+  /// dropped it.
+  async fn fetch_feed(&self, link: String) -> Result<Option<Blog>, FailedFeed> {
+    match self.fetch_blog(&link).await {
+      Ok(blog) => Ok(self.filter(&link, blog).await),
+      Err(e) => {
+        let error = e.to_string();
+        warn!("Error in {}\n{}", link, error);
+        Err(FailedFeed { url: link, error })
+      }
+    }
+  }
+
+  async fn fetch_blog(&self, link: &str) -> Result<Blog, Error> {
+    let response = self.fetcher.fetch(link).await?;
+    let served_from = response
+      .redirected_to
+      .clone()
+      .unwrap_or_else(|| link.to_owned());
+    let content = response.into_feed(link)?;
+
+    let mut errors = Vec::new();
+    for parser in &self.parsers {
+      match parser.parse(&served_from, &content) {
+        Ok(mut blog) => {
+          let _ = blog.feed_url.get_or_insert_with(|| link.to_owned());
+          for post in blog.posts.iter_mut().filter(|post| post.feeds.is_empty()) {
+            post.feeds.push(link.to_owned());
+          }
+          return Ok(blog);
+        }
+        Err(e) => errors.push(e.with_url(link)),
+      }
+    }
+
+    if errors.len() == 1 {
+      return Err(errors.remove(0));
+    }
+
+    Err(Error::Generic(errors.iter().join("\n")))
+  }
+
+  async fn filter(&self, link: &str, mut blog: Blog) -> Option<Blog> {
+    for filter in &self.filters {
+      blog = filter
+        .apply_async(link, blog, self.fetcher.as_ref())
+        .await?;
+    }
+
+    Some(blog).filter(|blog| !blog.posts.is_empty())
+  }
+
+  /// Tells the filters that every feed went through them, once the stream is exhausted.
+  fn finish(&self) {
+    for filter in &self.filters {
+      filter.finish();
+    }
+  }
+}
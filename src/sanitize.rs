@@ -0,0 +1,177 @@
+//! Sanitizes feed-supplied content before it ends up in an email.
+//!
+//! Titles and descriptions are plain text and are escaped by the HTML template,
+//! links are restricted to a few safe schemes and any HTML that is meant to be
+//! rendered is cleaned with an allow-list of tags and attributes, with its relative
+//! links resolved against the page it comes from when that is known. The full content
+//! of posts also keeps headings, images and tables.
+
+use std::collections::HashSet;
+
+use ammonia::{Builder, UrlRelative};
+use lazy_static::lazy_static;
+use url::Url;
+
+use crate::blog::{Blog, Enclosure, Post};
+
+/// The URL schemes that are allowed in links.
+const ALLOWED_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// The tags that are allowed in descriptions.
+const ALLOWED_TAGS: [&str; 17] = [
+  "a",
+  "abbr",
+  "b",
+  "blockquote",
+  "br",
+  "code",
+  "del",
+  "em",
+  "i",
+  "li",
+  "ol",
+  "p",
+  "pre",
+  "strong",
+  "sub",
+  "sup",
+  "ul",
+];
+
+/// The tags that are allowed in the full content of posts on top of [`ALLOWED_TAGS`].
+const CONTENT_TAGS: [&str; 20] = [
+  "caption",
+  "dd",
+  "dl",
+  "dt",
+  "figcaption",
+  "figure",
+  "h1",
+  "h2",
+  "h3",
+  "h4",
+  "h5",
+  "h6",
+  "hr",
+  "img",
+  "table",
+  "tbody",
+  "td",
+  "th",
+  "thead",
+  "tr",
+];
+
+lazy_static! {
+  static ref CLEANER: Builder<'static> = cleaner();
+  static ref CONTENT_CLEANER: Builder<'static> = content_cleaner();
+}
+
+fn cleaner() -> Builder<'static> {
+  let mut builder = Builder::empty();
+  let _ = builder
+    .add_tags(ALLOWED_TAGS)
+    .add_tag_attributes("a", ["href", "title"])
+    .add_tag_attributes("abbr", ["title"])
+    .url_schemes(HashSet::from(ALLOWED_SCHEMES))
+    .link_rel(Some("noopener noreferrer"));
+  builder
+}
+
+fn content_cleaner() -> Builder<'static> {
+  let mut builder = cleaner();
+  let _ = builder
+    .add_tags(CONTENT_TAGS)
+    .add_tag_attributes("img", ["src", "alt", "title", "width", "height"])
+    .add_tag_attributes("td", ["colspan", "rowspan"])
+    .add_tag_attributes("th", ["colspan", "rowspan"]);
+  builder
+}
+
+/// Returns `url` if it is an absolute link with an allowed scheme
+/// (`http`, `https` or `mailto`).
+pub fn safe_url(url: &str) -> Option<&str> {
+  let url = url.trim();
+  let (scheme, _) = url.split_once(':')?;
+
+  ALLOWED_SCHEMES
+    .iter()
+    .any(|allowed| scheme.eq_ignore_ascii_case(allowed))
+    .then_some(url)
+}
+
+/// Cleans an HTML fragment, removing any tag or attribute that is not
+/// explicitly allowed along with links with unsafe schemes.
+pub fn clean_html(html: &str) -> String {
+  CLEANER.clean(html).to_string()
+}
+
+/// Cleans the full content of a post like [`clean_html`], also allowing headings,
+/// images and tables. Relative links are resolved against `base` if it is known.
+pub fn clean_content(html: &str, base: Option<&Url>) -> String {
+  base.map_or_else(
+    || CONTENT_CLEANER.clean(html).to_string(),
+    |base| {
+      let mut builder = content_cleaner();
+      let _ = builder.url_relative(UrlRelative::RewriteWithBase(base.clone()));
+      builder.clean(html).to_string()
+    },
+  )
+}
+
+/// Sanitizes the links and the content of a post.
+///
+/// Links with an unsafe scheme are replaced with an empty string, thumbnails, enclosures
+/// and comments links with one are removed.
+pub fn sanitize_post(post: &Post) -> Post {
+  Post {
+    link: safe_url(&post.link).unwrap_or_default().to_owned(),
+    thumbnail: post
+      .thumbnail
+      .as_deref()
+      .and_then(safe_url)
+      .map(ToOwned::to_owned),
+    enclosures: post
+      .enclosures
+      .iter()
+      .filter_map(|enclosure| {
+        safe_url(&enclosure.url).map(|url| Enclosure {
+          url: url.to_owned(),
+          ..enclosure.clone()
+        })
+      })
+      .collect(),
+    comments: post
+      .comments
+      .as_deref()
+      .and_then(safe_url)
+      .map(ToOwned::to_owned),
+    content: post
+      .content
+      .as_deref()
+      .map(|content| clean_content(content, None)),
+    ..post.clone()
+  }
+}
+
+/// Sanitizes every post of every blog, and drops the site links and icons of blogs that
+/// have an unsafe scheme.
+pub fn sanitize_blogs(blogs: &[Blog]) -> Vec<Blog> {
+  blogs
+    .iter()
+    .map(|blog| Blog {
+      link: blog
+        .link
+        .as_deref()
+        .and_then(safe_url)
+        .map(ToOwned::to_owned),
+      icon: blog
+        .icon
+        .as_deref()
+        .and_then(safe_url)
+        .map(ToOwned::to_owned),
+      posts: blog.posts.iter().map(sanitize_post).collect(),
+      ..blog.clone()
+    })
+    .collect()
+}
@@ -0,0 +1,126 @@
+//! Cron schedules used by the daemon mode.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+
+use crate::Error;
+
+/// A cron expression evaluated in a timezone.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+  cron: cron::Schedule,
+  timezone: Tz,
+}
+
+impl Schedule {
+  /// Parses a cron `expression` in the given IANA `timezone` (e.g. `Europe/Athens`).
+  ///
+  /// Both the standard five-field format (`minute hour day-of-month month day-of-week`,
+  /// e.g. `0 7 * * MON-FRI` or `0 7 * * 1-5`) and the six or seven-field format with
+  /// seconds and years are accepted.
+  ///
+  /// Numeric days of the week follow crontab in the five-field format (0 to 7, Sunday
+  /// being both 0 and 7) and the [`cron`] crate otherwise (1 to 7, Sunday being 1).
+  pub fn new(expression: &str, timezone: &str) -> Result<Self, Error> {
+    Ok(Self {
+      cron: parse_cron(expression)?,
+      timezone: parse_timezone(timezone)?,
+    })
+  }
+
+  /// The timezone the schedule is evaluated in.
+  pub const fn timezone(&self) -> Tz {
+    self.timezone
+  }
+
+  /// The first time the schedule fires after `time`, if it ever does.
+  pub fn next_after(&self, time: DateTime<Utc>) -> Option<DateTime<Tz>> {
+    self.cron.after(&time.with_timezone(&self.timezone)).next()
+  }
+}
+
+/// The days of the week in the crontab numbering, where Sunday is 0.
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// Parses a cron expression, see [`Schedule::new`].
+pub fn parse_cron(expression: &str) -> Result<cron::Schedule, Error> {
+  let expression = expression.trim();
+  let invalid =
+    |e: &dyn std::fmt::Display| Error::Generic(format!("Invalid schedule '{expression}': {e}"));
+
+  let fields = expression.split_whitespace().collect::<Vec<_>>();
+  if let [minute, hour, day, month, weekday] = fields[..] {
+    let weekday = crontab_weekdays(weekday).map_err(|e| invalid(&e))?;
+    cron::Schedule::from_str(&format!("0 {minute} {hour} {day} {month} {weekday}"))
+  } else {
+    cron::Schedule::from_str(expression)
+  }
+  .map_err(|e| invalid(&e))
+}
+
+/// Translates a numeric crontab day-of-week field (e.g. `1-5`, `0,6` or `*/2`) to day
+/// names, since the [`cron`] crate numbers days from 1 (Sunday) to 7. Fields with names,
+/// `*` and `?` are kept as they are.
+fn crontab_weekdays(field: &str) -> Result<String, String> {
+  if matches!(field, "*" | "?") || field.contains(|c: char| c.is_ascii_alphabetic()) {
+    return Ok(field.to_owned());
+  }
+
+  let mut days = [false; 7];
+  for item in field.split(',') {
+    let (range, step) = match item.split_once('/') {
+      Some((range, step)) => (
+        range,
+        step
+          .parse::<usize>()
+          .ok()
+          .filter(|step| *step > 0)
+          .ok_or_else(|| format!("invalid step '{step}'"))?,
+      ),
+      None => (item, 1),
+    };
+    let (start, end) = match range.split_once('-') {
+      _ if range == "*" => (0, 6),
+      Some((start, end)) => (weekday(start)?, weekday(end)?),
+      // `5/2` is every other day from Friday to the end of the week.
+      None if item.contains('/') => (weekday(range)?, 6),
+      None => (weekday(range)?, weekday(range)?),
+    };
+    if start > end {
+      return Err(format!("invalid range of days of the week '{range}'"));
+    }
+
+    for day in (start..=end).step_by(step) {
+      days[day % 7] = true;
+    }
+  }
+
+  Ok(
+    WEEKDAYS
+      .iter()
+      .zip(days)
+      .filter(|(_, included)| *included)
+      .map(|(name, _)| *name)
+      .collect::<Vec<_>>()
+      .join(","),
+  )
+}
+
+/// Parses a crontab day of the week, from 0 to 7.
+fn weekday(value: &str) -> Result<usize, String> {
+  value
+    .parse::<usize>()
+    .ok()
+    .filter(|day| *day <= 7)
+    .ok_or_else(|| format!("invalid day of the week '{value}'"))
+}
+
+/// Parses an IANA timezone name such as `Europe/Athens`.
+pub fn parse_timezone(timezone: &str) -> Result<Tz, Error> {
+  timezone
+    .trim()
+    .parse::<Tz>()
+    .map_err(|e| Error::Generic(format!("Invalid timezone '{timezone}': {e}")))
+}
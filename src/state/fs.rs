@@ -0,0 +1,76 @@
+use std::{
+  fs,
+  io::ErrorKind,
+  path::{Path, PathBuf},
+  sync::atomic::{AtomicU64, Ordering},
+};
+
+use super::{check_key, StateStore};
+use crate::Error;
+
+/// Makes the names of the temporary files of concurrent writes unique within the process.
+static WRITES: AtomicU64 = AtomicU64::new(0);
+
+/// Keeps every value in its own file under a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsStore {
+  root: PathBuf,
+}
+
+impl FsStore {
+  /// Stores values under `root`, which is created when the first value is stored.
+  pub fn new(root: impl Into<PathBuf>) -> Self {
+    Self { root: root.into() }
+  }
+
+  /// The directory values are stored under.
+  pub fn root(&self) -> &Path {
+    &self.root
+  }
+
+  fn path(&self, key: &str) -> Result<PathBuf, Error> {
+    check_key(key)?;
+    Ok(self.root.join(key))
+  }
+}
+
+impl StateStore for FsStore {
+  fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+    match fs::read(self.path(key)?) {
+      Ok(value) => Ok(Some(value)),
+      Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(Error::Io(e)),
+    }
+  }
+
+  fn put(&self, key: &str, value: &[u8]) -> Result<(), Error> {
+    let path = self.path(key)?;
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+
+    // Write to a temporary file first so that a crash never leaves a partial value behind,
+    // named after the process and the write so that concurrent writes do not collide.
+    let mut tmp = path.clone().into_os_string();
+    tmp.push(format!(
+      ".{}.{}.tmp",
+      std::process::id(),
+      WRITES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, value)
+      .and_then(|()| fs::rename(&tmp, &path))
+      .map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        Error::Io(e)
+      })
+  }
+
+  fn delete(&self, key: &str) -> Result<(), Error> {
+    match fs::remove_file(self.path(key)?) {
+      Ok(()) => Ok(()),
+      Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+      Err(e) => Err(Error::Io(e)),
+    }
+  }
+}
@@ -0,0 +1,55 @@
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use lazy_static::lazy_static;
+
+use super::{check_key, StateStore};
+use crate::Error;
+
+lazy_static! {
+  static ref SHARED: Arc<MemoryStore> = Arc::default();
+}
+
+/// Keeps values in memory, for tests and one-off runs.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+  values: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl MemoryStore {
+  /// Creates an empty store.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The store shared by the whole process, so that state survives between the
+  /// runs of the daemon mode.
+  pub fn shared() -> Arc<Self> {
+    Arc::clone(&SHARED)
+  }
+
+  fn values(&self) -> MutexGuard<'_, HashMap<String, Vec<u8>>> {
+    self.values.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
+impl StateStore for MemoryStore {
+  fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+    check_key(key)?;
+    Ok(self.values().get(key).cloned())
+  }
+
+  fn put(&self, key: &str, value: &[u8]) -> Result<(), Error> {
+    check_key(key)?;
+    let _previous = self.values().insert(key.to_owned(), value.to_vec());
+    Ok(())
+  }
+
+  fn delete(&self, key: &str) -> Result<(), Error> {
+    check_key(key)?;
+    let _previous = self.values().remove(key);
+    Ok(())
+  }
+}
@@ -0,0 +1,153 @@
+//! Persistent state shared between runs, such as what was already sent.
+//!
+//! State is kept in a [`StateStore`], a simple key-value store. Keys are `/`-separated
+//! paths such as `runs/daily/last.json`. Every profile of a [config file](crate::config::file)
+//! shares the same store and namespaces its keys with its name.
+//!
+//! The store holds the last runs of every profile (see [`record_run`]), the HTTP cache
+//! of the feeds, under `cache/` (see [`HttpFetcher::with_cache`](crate::fetch::HttpFetcher::with_cache)),
+//! and the resolved shortened links, under `links/` (see
+//! [`LinkResolver::with_cache`](crate::link::LinkResolver::with_cache)).
+//!
+//! The backend is picked with `STATE_BACKEND`:
+//!
+//! - `none` (the default): nothing is remembered between runs
+//! - `fs`: files under `STATE_DIR` (see [`FsStore`])
+//! - `s3`: objects in an S3-compatible bucket (see `S3Store`), which requires the `s3` feature
+//! - `memory`: kept in memory for the lifetime of the process (see [`MemoryStore::shared`])
+
+use std::{fmt::Debug, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{summary::RunSummary, Error};
+
+#[cfg(feature = "s3")]
+pub use self::s3::S3Store;
+pub use self::{fs::FsStore, memory::MemoryStore};
+
+mod fs;
+mod memory;
+#[cfg(feature = "s3")]
+mod s3;
+
+/// A key-value store that outlives a run.
+pub trait StateStore: Debug + Send + Sync {
+  /// Reads the value stored under `key`, if any.
+  fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error>;
+
+  /// Stores `value` under `key`, replacing any previous value.
+  fn put(&self, key: &str, value: &[u8]) -> Result<(), Error>;
+
+  /// Removes the value stored under `key`, if any.
+  fn delete(&self, key: &str) -> Result<(), Error>;
+}
+
+impl<T: StateStore + ?Sized> StateStore for Arc<T> {
+  fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+    (**self).get(key)
+  }
+
+  fn put(&self, key: &str, value: &[u8]) -> Result<(), Error> {
+    (**self).put(key, value)
+  }
+
+  fn delete(&self, key: &str) -> Result<(), Error> {
+    (**self).delete(key)
+  }
+}
+
+impl dyn StateStore + '_ {
+  /// Reads and deserializes the JSON value stored under `key`, if any.
+  pub fn get_json<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, Error> {
+    self
+      .get(key)?
+      .map(|value| {
+        serde_json::from_slice(&value)
+          .map_err(|e| Error::Generic(format!("Invalid state in {key}: {e}")))
+      })
+      .transpose()
+  }
+
+  /// Serializes `value` to JSON and stores it under `key`.
+  pub fn put_json<T: serde::Serialize>(&self, key: &str, value: &T) -> Result<(), Error> {
+    let value = serde_json::to_vec(value)
+      .map_err(|e| Error::Generic(format!("Could not serialize state for {key}: {e}")))?;
+
+    self.put(key, &value)
+  }
+}
+
+/// What is remembered about the last run of a profile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunState {
+  /// When the run started, missing from the runs recorded by older versions.
+  #[serde(default)]
+  pub started_at: Option<DateTime<Utc>>,
+  /// When the run finished.
+  pub finished_at: DateTime<Utc>,
+  /// What happened during the run.
+  pub summary: RunSummary,
+}
+
+/// The key the last run of `profile` is stored under.
+pub fn last_run_key(profile: Option<&str>) -> String {
+  format!("runs/{}/last.json", profile.unwrap_or("default"))
+}
+
+/// The key the last successful run of `profile` is stored under.
+pub fn last_success_key(profile: Option<&str>) -> String {
+  format!("runs/{}/last-success.json", profile.unwrap_or("default"))
+}
+
+/// Remembers `summary`, of a run that started at `started_at`, as the last run of its
+/// profile and, unless it failed, as the last successful one.
+pub fn record_run(
+  store: &dyn StateStore,
+  summary: &RunSummary,
+  started_at: DateTime<Utc>,
+) -> Result<(), Error> {
+  let profile = summary.profile.as_deref();
+  let state = RunState {
+    started_at: Some(started_at),
+    finished_at: Utc::now(),
+    summary: summary.clone(),
+  };
+
+  store.put_json(&last_run_key(profile), &state)?;
+  if !summary.is_failure() {
+    store.put_json(&last_success_key(profile), &state)?;
+  }
+
+  Ok(())
+}
+
+/// The last run of `profile`, if it was recorded.
+pub fn last_run(store: &dyn StateStore, profile: Option<&str>) -> Result<Option<RunState>, Error> {
+  store.get_json(&last_run_key(profile))
+}
+
+/// The last run of `profile` that did not fail, if it was recorded.
+pub fn last_successful_run(
+  store: &dyn StateStore,
+  profile: Option<&str>,
+) -> Result<Option<RunState>, Error> {
+  store.get_json(&last_success_key(profile))
+}
+
+/// Checks that `key` is a relative `/`-separated path without `.` or `..` segments.
+pub(crate) fn check_key(key: &str) -> Result<(), Error> {
+  let valid = !key.is_empty()
+    && key
+      .split('/')
+      .all(|segment| !segment.is_empty() && segment != "." && segment != "..")
+    && !key.contains('\\');
+
+  if valid {
+    Ok(())
+  } else {
+    Err(Error::Generic(format!("Invalid state key '{key}'")))
+  }
+}
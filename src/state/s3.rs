@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use reqwest::{Client, RequestBuilder, StatusCode};
+use rusty_s3::{Bucket, Credentials, S3Action, UrlStyle};
+use url::Url;
+
+use super::{check_key, StateStore};
+use crate::{block_on, Error};
+
+/// How long the signature of a request is valid for.
+// `Duration::from_mins` is too recent to be required.
+#[allow(clippy::duration_suboptimal_units)]
+const SIGNATURE_LIFETIME: Duration = Duration::from_secs(60);
+
+/// Keeps every value in its own object of an S3-compatible bucket.
+///
+/// Works with AWS as well as self-hosted implementations such as `MinIO` through a
+/// custom endpoint.
+///
+/// Stores are used from blocking code, which may itself run inside a runtime, so every
+/// request is sent on a runtime of its own.
+#[derive(Debug)]
+pub struct S3Store {
+  bucket: Bucket,
+  credentials: Option<Credentials>,
+  prefix: String,
+  client: Client,
+}
+
+impl S3Store {
+  /// Stores values in `bucket` at `endpoint`, under `prefix`.
+  ///
+  /// Buckets are addressed with path-style URLs (`https://endpoint/bucket/key`), which
+  /// every implementation supports.
+  pub fn new(
+    endpoint: &str,
+    region: &str,
+    bucket: &str,
+    prefix: &str,
+    credentials: Option<Credentials>,
+  ) -> Result<Self, Error> {
+    let endpoint = Url::parse(endpoint)
+      .map_err(|e| Error::Generic(format!("Invalid S3 endpoint '{endpoint}': {e}")))?;
+
+    let bucket = Bucket::new(
+      endpoint,
+      UrlStyle::Path,
+      bucket.to_owned(),
+      region.to_owned(),
+    )
+    .map_err(|e| Error::Generic(format!("Invalid S3 bucket: {e}")))?;
+
+    let prefix = prefix.trim_matches('/');
+    let prefix = if prefix.is_empty() {
+      String::new()
+    } else {
+      format!("{prefix}/")
+    };
+
+    Ok(Self {
+      bucket,
+      credentials,
+      prefix,
+      // Connections are tied to the runtime of the request that opened them.
+      client: Client::builder().pool_max_idle_per_host(0).build()?,
+    })
+  }
+
+  /// Reads the credentials from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and the
+  /// optional `AWS_SESSION_TOKEN`, as set by Lambda.
+  pub fn credentials_from_env() -> Option<Credentials> {
+    let key = std::env::var("AWS_ACCESS_KEY_ID").ok()?;
+    let secret = std::env::var("AWS_SECRET_ACCESS_KEY").ok()?;
+
+    Some(match std::env::var("AWS_SESSION_TOKEN") {
+      Ok(token) => Credentials::new_with_token(key, secret, token),
+      Err(_) => Credentials::new(key, secret),
+    })
+  }
+
+  fn object(&self, key: &str) -> Result<String, Error> {
+    check_key(key)?;
+    Ok(format!("{}{key}", self.prefix))
+  }
+
+  /// Sends `request` and reads the body of the response, `None` if there is no object.
+  fn send(request: RequestBuilder) -> Result<Option<Vec<u8>>, Error> {
+    block_on(async {
+      let response = request.send().await?;
+      if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+      }
+
+      Ok(Some(response.error_for_status()?.bytes().await?.to_vec()))
+    })?
+  }
+}
+
+impl StateStore for S3Store {
+  fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+    let object = self.object(key)?;
+    let url = self
+      .bucket
+      .get_object(self.credentials.as_ref(), &object)
+      .sign(SIGNATURE_LIFETIME);
+
+    Self::send(self.client.get(url))
+  }
+
+  fn put(&self, key: &str, value: &[u8]) -> Result<(), Error> {
+    let object = self.object(key)?;
+    let url = self
+      .bucket
+      .put_object(self.credentials.as_ref(), &object)
+      .sign(SIGNATURE_LIFETIME);
+
+    Self::send(self.client.put(url).body(value.to_vec()))?
+      .map(|_body| ())
+      .ok_or_else(|| Error::Generic(format!("Could not store '{object}' in S3")))
+  }
+
+  fn delete(&self, key: &str) -> Result<(), Error> {
+    let object = self.object(key)?;
+    let url = self
+      .bucket
+      .delete_object(self.credentials.as_ref(), &object)
+      .sign(SIGNATURE_LIFETIME);
+
+    // A missing object is already deleted.
+    Self::send(self.client.delete(url)).map(|_body| ())
+  }
+}
@@ -0,0 +1,69 @@
+//! A machine-readable report of a run.
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{blog::FailedFeed, Digest};
+
+/// What happened during a run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunSummary {
+  /// The profile that was run, if any.
+  pub profile: Option<String>,
+  /// How many feeds were downloaded and parsed successfully.
+  pub feeds_fetched: usize,
+  /// The feeds that could not be downloaded or parsed.
+  pub feeds_failed: Vec<FailedFeed>,
+  /// How many blogs had new posts.
+  pub blogs: usize,
+  /// How many new posts were found.
+  pub posts: usize,
+  /// Whether the email was sent.
+  pub delivery: Delivery,
+}
+
+/// What happened to the email of a run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Delivery {
+  /// The run stopped before the email was rendered.
+  #[default]
+  NotAttempted,
+  /// The email was sent.
+  Sent,
+  /// The email was not sent because there were no new posts.
+  Skipped,
+  /// The email was rendered but not sent on purpose.
+  DryRun,
+  /// The run or the email provider failed.
+  Failed { error: String },
+}
+
+impl RunSummary {
+  /// Summarizes the feeds and posts of `digest`, out of `feed_count` feeds.
+  pub fn new(profile: Option<String>, feed_count: usize, digest: &Digest) -> Self {
+    Self {
+      profile,
+      feeds_fetched: feed_count.saturating_sub(digest.failed_feeds.len()),
+      feeds_failed: digest.failed_feeds.clone(),
+      blogs: digest.blogs.len(),
+      posts: digest.post_count(),
+      delivery: Delivery::NotAttempted,
+    }
+  }
+
+  /// A run that failed with `error` before anything was fetched.
+  pub fn failed(profile: Option<String>, error: impl Into<String>) -> Self {
+    Self {
+      profile,
+      delivery: Delivery::Failed {
+        error: error.into(),
+      },
+      ..Self::default()
+    }
+  }
+
+  /// Whether the run or the delivery of its email failed.
+  pub const fn is_failure(&self) -> bool {
+    matches!(self.delivery, Delivery::Failed { .. })
+  }
+}
@@ -0,0 +1,289 @@
+//! Renders the digest email from [`minijinja`] templates.
+//!
+//! A built-in template is provided for both the HTML and the plain-text body.
+//! Either can be replaced by pointing the `HTML_TEMPLATE` or `TEXT_TEMPLATE`
+//! environment variables at a template file (see [`Config`](crate::config::Config)).
+//!
+//! # Context
+//!
+//! Every template is rendered with the following variables:
+//!
+//! - `date`: the date of the run, formatted as `YYYY-MM-DD`
+//! - `now`: the time of the run, in RFC 3339
+//! - `blogs`: the [`Blog`]s with new posts, each with a `title`, a `most_recent_pub_date`,
+//!   its `posts`, the `feed_url` it was downloaded from and, when the feed has them, the
+//!   `link` of the site, a plain-text `description`, an `icon` URL and a `language`
+//! - `blogs[].posts`: the [`Post`](crate::Post)s, each with a `title`, a `link`, an optional
+//!   plain-text `description`, an optional HTML `content`, a `pub_date`, its `categories`
+//!   and `authors`, an optional `comments` link, an optional `thumbnail` URL and its
+//!   `enclosures`, each with a `url`, a `mime_type`, a `length` in bytes and a `duration`
+//!   in seconds
+//! - `post_count`: the total number of posts across all blogs
+//! - `full_text`: whether the full `content` of posts should be shown instead of their
+//!   `description` (`FULL_TEXT`)
+//! - `failed_feeds`: the [`FailedFeed`]s that could not be downloaded or parsed, each with a
+//!   `url` and an `error`
+//!
+//! Dates are RFC 3339 strings and can be formatted with the `date` filter, which takes an
+//! optional [`chrono` format string](https://docs.rs/chrono/latest/chrono/format/strftime/index.html):
+//! `{{ post.pub_date | date("%d %b %Y") }}`. The `text` filter turns HTML such as the
+//! `content` of a post into paragraphs of plain text, and the `duration` filter formats a
+//! number of seconds as `M:SS` or `H:MM:SS`.
+//!
+//! The output of the HTML template is always HTML-escaped and post links are limited to the
+//! `http`, `https` and `mailto` schemes (and are empty otherwise), as are thumbnails,
+//! enclosures, comments links and the links and icons of blogs (which are dropped
+//! otherwise). The `content` of posts is
+//! cleaned and can be rendered with `{{ post.content | safe }}`. See
+//! [`sanitize`](crate::sanitize).
+//!
+//! # Styling
+//!
+//! The HTML body is styled by a [`Theme`], picked with the `THEME` environment variable,
+//! and optionally by a user stylesheet pointed to by `CUSTOM_CSS` that is applied on top of it.
+//! Since most email clients strip `<style>` blocks, all CSS (including any `<style>` block
+//! in a custom template) is inlined into the `style` attribute of each element after rendering.
+
+use std::{fmt::Write as _, fs, path::Path};
+
+use chrono::{DateTime, Utc};
+use css_inline::CSSInliner;
+use minijinja::{AutoEscape, Environment, ErrorKind};
+use serde_derive::Serialize;
+
+use crate::{
+  blog::{Blog, FailedFeed},
+  email::message::EmailMessage,
+  sanitize::sanitize_blogs,
+  text::html_to_paragraphs,
+  Error,
+};
+
+pub use self::theme::Theme;
+
+mod theme;
+
+const DEFAULT_HTML: &str = include_str!("digest.html");
+const DEFAULT_TEXT: &str = include_str!("digest.txt");
+
+/// The templates used to render the HTML and plain-text bodies of the digest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Templates {
+  html: Template,
+  text: Template,
+  theme: Theme,
+  css: Option<String>,
+  full_text: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Template {
+  name: String,
+  source: String,
+}
+
+/// The variables available to every template.
+#[derive(Debug, Serialize)]
+struct Context<'a> {
+  date: String,
+  now: DateTime<Utc>,
+  blogs: &'a [Blog],
+  post_count: usize,
+  failed_feeds: &'a [FailedFeed],
+  full_text: bool,
+}
+
+impl Default for Templates {
+  fn default() -> Self {
+    Self {
+      html: Template::new("digest.html", DEFAULT_HTML),
+      text: Template::new("digest.txt", DEFAULT_TEXT),
+      theme: Theme::default(),
+      css: None,
+      full_text: false,
+    }
+  }
+}
+
+impl Template {
+  fn new(name: &str, source: &str) -> Self {
+    Self {
+      name: name.to_owned(),
+      source: source.to_owned(),
+    }
+  }
+
+  fn from_file(path: &Path) -> Result<Self, Error> {
+    let source = fs::read_to_string(path)
+      .map_err(|e| Error::Generic(format!("Could not read template {}: {e}", path.display())))?;
+
+    Ok(Self {
+      name: path.display().to_string(),
+      source,
+    })
+  }
+}
+
+impl Templates {
+  /// Replaces the HTML template with the contents of `path`.
+  pub fn with_html_file(mut self, path: impl AsRef<Path>) -> Result<Self, Error> {
+    self.html = Template::from_file(path.as_ref())?;
+    Ok(self)
+  }
+
+  /// Replaces the plain-text template with the contents of `path`.
+  pub fn with_text_file(mut self, path: impl AsRef<Path>) -> Result<Self, Error> {
+    self.text = Template::from_file(path.as_ref())?;
+    Ok(self)
+  }
+
+  /// Sets the built-in theme of the HTML body.
+  #[must_use]
+  pub const fn with_theme(mut self, theme: Theme) -> Self {
+    self.theme = theme;
+    self
+  }
+
+  /// Shows the full content of posts instead of their description, when they have one.
+  #[must_use]
+  pub const fn with_full_text(mut self, full_text: bool) -> Self {
+    self.full_text = full_text;
+    self
+  }
+
+  /// Applies the stylesheet at `path` on top of the theme.
+  pub fn with_css_file(mut self, path: impl AsRef<Path>) -> Result<Self, Error> {
+    let path = path.as_ref();
+    let css = fs::read_to_string(path)
+      .map_err(|e| Error::Generic(format!("Could not read stylesheet {}: {e}", path.display())))?;
+
+    self.css = Some(css);
+    Ok(self)
+  }
+
+  /// Checks that both templates are syntactically valid.
+  pub fn check(&self) -> Result<(), Error> {
+    for template in [&self.html, &self.text] {
+      let _compiled = Environment::new()
+        .template_from_named_str(&template.name, &template.source)
+        .map_err(|e| Error::Generic(format!("Invalid template {}: {e:#}", template.name)))?;
+    }
+
+    Ok(())
+  }
+
+  /// Renders the HTML body with its CSS inlined.
+  pub fn render_html(&self, blogs: &[Blog], failed_feeds: &[FailedFeed]) -> Result<String, Error> {
+    let html = render(
+      &self.html,
+      AutoEscape::Html,
+      blogs,
+      failed_feeds,
+      self.full_text,
+    )?;
+    self.inline_css(&html)
+  }
+
+  /// Renders the plain-text body.
+  pub fn render_text(&self, blogs: &[Blog], failed_feeds: &[FailedFeed]) -> Result<String, Error> {
+    render(
+      &self.text,
+      AutoEscape::None,
+      blogs,
+      failed_feeds,
+      self.full_text,
+    )
+  }
+
+  /// Renders both bodies of the digest email.
+  pub fn render(&self, blogs: &[Blog], failed_feeds: &[FailedFeed]) -> Result<EmailMessage, Error> {
+    Ok(EmailMessage::new(
+      self.render_html(blogs, failed_feeds)?,
+      self.render_text(blogs, failed_feeds)?,
+    ))
+  }
+
+  /// Moves the theme, the custom stylesheet and any `<style>` blocks into
+  /// the `style` attributes of the elements they apply to.
+  fn inline_css(&self, html: &str) -> Result<String, Error> {
+    let mut css = self.theme.css().to_owned();
+    if let Some(custom) = &self.css {
+      css.push('\n');
+      css.push_str(custom);
+    }
+
+    CSSInliner::options()
+      .load_remote_stylesheets(false)
+      .extra_css(Some(css.into()))
+      .build()
+      .inline(html)
+      .map_err(|e| Error::Generic(format!("Could not inline CSS: {e}")))
+  }
+}
+
+/// Renders `template` with the sanitized `blogs`, escaping its output
+/// according to `escape` regardless of the file name of the template.
+fn render(
+  template: &Template,
+  escape: AutoEscape,
+  blogs: &[Blog],
+  failed_feeds: &[FailedFeed],
+  full_text: bool,
+) -> Result<String, Error> {
+  let mut env = Environment::new();
+  env.set_auto_escape_callback(move |_name| escape);
+  env.add_filter("date", date_filter);
+  env.add_filter("text", |html: &str| html_to_paragraphs(html));
+  env.add_filter("duration", duration_filter);
+
+  let now = Utc::now();
+  let context = Context {
+    date: now.date_naive().to_string(),
+    now,
+    blogs: &sanitize_blogs(blogs),
+    post_count: blogs.iter().map(|blog| blog.posts.len()).sum(),
+    failed_feeds,
+    full_text,
+  };
+
+  env
+    .render_named_str(&template.name, &template.source, context)
+    .map_err(|e| {
+      Error::Generic(format!(
+        "Could not render template {}: {e:#}",
+        template.name
+      ))
+    })
+}
+
+/// Formats an RFC 3339 date, `%Y-%m-%d` by default.
+fn date_filter(value: &str, format: Option<&str>) -> Result<String, minijinja::Error> {
+  let date = DateTime::parse_from_rfc3339(value).map_err(|e| {
+    minijinja::Error::new(
+      ErrorKind::InvalidOperation,
+      format!("'{value}' is not a date ({e})"),
+    )
+  })?;
+
+  let format = format.unwrap_or("%Y-%m-%d");
+  let mut res = String::new();
+  write!(res, "{}", date.format(format)).map_err(|_e| {
+    minijinja::Error::new(
+      ErrorKind::InvalidOperation,
+      format!("'{format}' is not a valid date format"),
+    )
+  })?;
+
+  Ok(res)
+}
+
+/// Formats a number of seconds as `M:SS`, or `H:MM:SS` if it is an hour or longer.
+fn duration_filter(seconds: u64) -> String {
+  let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+  if hours > 0 {
+    format!("{hours}:{minutes:02}:{seconds:02}")
+  } else {
+    format!("{minutes}:{seconds:02}")
+  }
+}
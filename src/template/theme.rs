@@ -0,0 +1,41 @@
+//! Built-in stylesheets for the HTML body of the digest.
+
+/// A built-in stylesheet that is inlined into the HTML body.
+///
+/// The stylesheet targets the classes used by the built-in template
+/// (`wrapper`, `container`, `header`, `blog`, `icon`, `subtitle`, `byline`, `description`,
+/// `content`, `thumbnail`, `enclosure`, `empty` and `footer`) as well as plain elements, so it
+/// can also style custom templates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+  #[default]
+  Light,
+  Dark,
+  Compact,
+}
+
+impl Theme {
+  /// The CSS of the theme.
+  pub const fn css(self) -> &'static str {
+    match self {
+      Self::Light => include_str!("themes/light.css"),
+      Self::Dark => include_str!("themes/dark.css"),
+      Self::Compact => include_str!("themes/compact.css"),
+    }
+  }
+}
+
+impl TryFrom<String> for Theme {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    match value.trim().to_lowercase().as_str() {
+      "light" => Ok(Self::Light),
+      "dark" => Ok(Self::Dark),
+      "compact" => Ok(Self::Compact),
+      _ => Err(format!(
+        "Unknown theme '{value}', expected one of light, dark or compact"
+      )),
+    }
+  }
+}
@@ -0,0 +1,193 @@
+//! Turns feed-supplied HTML into short plain-text snippets, or into paragraphs of
+//! plain text for the full content of posts.
+
+/// Elements whose contents are not text and should be dropped entirely.
+const SKIPPED_ELEMENTS: [&str; 5] = ["script", "style", "head", "template", "noscript"];
+
+/// Elements that separate words even when there is no whitespace around them.
+const BLOCK_ELEMENTS: [&str; 29] = [
+  "address",
+  "article",
+  "aside",
+  "blockquote",
+  "br",
+  "dd",
+  "div",
+  "dl",
+  "dt",
+  "figcaption",
+  "figure",
+  "footer",
+  "h1",
+  "h2",
+  "h3",
+  "h4",
+  "h5",
+  "h6",
+  "header",
+  "hr",
+  "li",
+  "ol",
+  "p",
+  "pre",
+  "section",
+  "table",
+  "td",
+  "tr",
+  "ul",
+];
+
+/// Marks the boundaries of block elements in [`strip_tags`]. It is whitespace, so that
+/// [`html_to_text`] can collapse it along with the rest.
+const PARAGRAPH_SEPARATOR: char = '\u{2029}';
+
+/// Converts an HTML fragment to plain text.
+///
+/// Tags and comments are removed (along with the contents of elements such as
+/// `<script>`), entities are decoded and whitespace is collapsed into single spaces.
+pub fn html_to_text(html: &str) -> String {
+  collapse_whitespace(&strip_tags(html))
+}
+
+/// Same as [`html_to_text`], but block elements such as `<p>` or `<li>` start a new
+/// paragraph. Paragraphs are separated by an empty line.
+pub fn html_to_paragraphs(html: &str) -> String {
+  strip_tags(html)
+    .split(PARAGRAPH_SEPARATOR)
+    .map(collapse_whitespace)
+    .filter(|paragraph| !paragraph.is_empty())
+    .collect::<Vec<_>>()
+    .join("\n\n")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+  text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Removes the tags of `html` and decodes its entities, marking the boundaries of block
+/// elements with a [`PARAGRAPH_SEPARATOR`].
+fn strip_tags(html: &str) -> String {
+  let mut text = String::with_capacity(html.len());
+  let mut skipping: Option<String> = None;
+  let mut rest = html;
+
+  while let Some(start) = rest.find('<') {
+    if skipping.is_none() {
+      text.push_str(&rest[..start]);
+    }
+    rest = &rest[start..];
+
+    if let Some(comment) = rest.strip_prefix("<!--") {
+      rest = comment.split_once("-->").map_or("", |(_, after)| after);
+      continue;
+    }
+
+    if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+      let (content, after) = cdata.split_once("]]>").unwrap_or((cdata, ""));
+      if skipping.is_none() {
+        text.push_str(&html_escape::encode_text(content));
+      }
+      rest = after;
+      continue;
+    }
+
+    if !rest[1..]
+      .trim_start_matches('/')
+      .starts_with(|c: char| c.is_ascii_alphabetic() || c == '!' || c == '?')
+    {
+      // Not a tag, e.g. `a < b`.
+      if skipping.is_none() {
+        text.push('<');
+      }
+      rest = &rest[1..];
+      continue;
+    }
+
+    let Some(end) = tag_end(rest) else {
+      // An unclosed tag, most likely a truncated description.
+      rest = "";
+      break;
+    };
+
+    let tag = &rest[1..end];
+    let closing = tag.starts_with('/');
+    let name = tag
+      .trim_start_matches('/')
+      .split(|c: char| c.is_whitespace() || c == '/')
+      .next()
+      .unwrap_or_default()
+      .to_ascii_lowercase();
+
+    match &skipping {
+      Some(skipped) if closing && *skipped == name => skipping = None,
+      None if !closing && SKIPPED_ELEMENTS.contains(&name.as_str()) => skipping = Some(name),
+      None if BLOCK_ELEMENTS.contains(&name.as_str()) => text.push(PARAGRAPH_SEPARATOR),
+      _ => {}
+    }
+
+    rest = &rest[end + 1..];
+  }
+
+  if skipping.is_none() {
+    text.push_str(rest);
+  }
+
+  html_escape::decode_html_entities(&text).into_owned()
+}
+
+/// Finds the `>` that closes the tag at the start of `html`, skipping over quoted
+/// attribute values.
+fn tag_end(html: &str) -> Option<usize> {
+  let mut quote = None;
+
+  for (i, c) in html.char_indices().skip(1) {
+    match (quote, c) {
+      (None, '"' | '\'') => quote = Some(c),
+      (Some(q), _) if q == c => quote = None,
+      (None, '>') => return Some(i),
+      (None, '<') => return None,
+      _ => {}
+    }
+  }
+
+  None
+}
+
+/// Shortens `text` to at most `limit` characters (not counting the trailing `...`).
+///
+/// The cut is made after the last full sentence if that keeps at least half of the
+/// allowed length, otherwise at the last word boundary. Words are only split if
+/// the first one is longer than `limit`. A `limit` of 0 gives an empty string.
+pub fn truncate(text: &str, limit: usize) -> String {
+  let text = text.trim();
+  if limit == 0 {
+    return String::new();
+  }
+
+  let Some((cut, _)) = text.char_indices().nth(limit) else {
+    return text.to_owned();
+  };
+
+  let head = &text[..cut];
+
+  let sentence_end = head
+    .char_indices()
+    .filter(|(i, c)| {
+      matches!(c, '.' | '!' | '?') && text[i + c.len_utf8()..].starts_with(char::is_whitespace)
+    })
+    .map(|(i, c)| i + c.len_utf8())
+    .next_back();
+
+  if let Some(end) = sentence_end.filter(|end| head[..*end].chars().count() >= limit / 2) {
+    return head[..end].to_owned();
+  }
+
+  if text[cut..].starts_with(char::is_whitespace) {
+    return format!("{}...", head.trim_end());
+  }
+
+  match head.rfind(char::is_whitespace) {
+    Some(space) if space > 0 => format!("{}...", head[..space].trim_end()),
+    _ => format!("{head}..."),
+  }
+}
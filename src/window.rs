@@ -0,0 +1,183 @@
+//! The time window posts are included from.
+//!
+//! `WINDOW` takes precedence over `DAYS` and accepts:
+//!
+//! - a duration such as `36h`, `2d12h` or `1w`: that long up to now
+//! - `today`, `yesterday`, `this-week` or `last-week`: the calendar period in `TIMEZONE`,
+//!   weeks starting on Monday
+//! - `since-last-run`: everything since the last successful run of the profile, which
+//!   needs a `STATE_BACKEND`. Falls back to `DAYS` until a run was recorded
+
+use chrono::{DateTime, Datelike, Days, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::Error;
+
+/// The value of `WINDOW` that selects the posts since the last successful run.
+pub const SINCE_LAST_RUN: &str = "since-last-run";
+
+/// How far in the future a post can be dated before it is considered wrong, if
+/// `FUTURE_TOLERANCE` is not set.
+pub const DEFAULT_FUTURE_TOLERANCE: &str = "1h";
+
+/// [`DEFAULT_FUTURE_TOLERANCE`] as a duration.
+pub fn default_future_tolerance() -> Duration {
+  parse_duration(DEFAULT_FUTURE_TOLERANCE).unwrap_or_default()
+}
+
+/// The time window posts are included from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+  /// The given duration up to now.
+  Last(Duration),
+  /// From the given time up to now.
+  Since(DateTime<Utc>),
+  /// A calendar period in a timezone.
+  Calendar { period: Period, timezone: Tz },
+}
+
+/// A calendar period, relative to the current date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+  Today,
+  Yesterday,
+  ThisWeek,
+  LastWeek,
+}
+
+/// What happens to posts dated further in the future than the tolerance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FuturePosts {
+  /// Their date is replaced with the current time. They then fall in every window, so
+  /// they are included in every digest until their date has passed.
+  Clamp,
+  /// They are left out.
+  #[default]
+  Drop,
+}
+
+impl Window {
+  /// The last `days` days, or nothing if `days` is not positive.
+  pub fn days(days: i64) -> Self {
+    Self::Last(Duration::try_days(days.max(0)).unwrap_or_else(Duration::max_value))
+  }
+
+  /// Parses `value` (see the [module](self) documentation), with calendar periods in
+  /// `timezone`.
+  ///
+  /// `since-last-run` depends on the state and is resolved by
+  /// [`Config::window`](crate::config::Config::window) instead.
+  pub fn parse(value: &str, timezone: Tz) -> Result<Self, Error> {
+    let period = match value.trim().to_lowercase().as_str() {
+      "today" => Period::Today,
+      "yesterday" => Period::Yesterday,
+      "this-week" => Period::ThisWeek,
+      "last-week" => Period::LastWeek,
+      _ => {
+        return parse_duration(value).map(Self::Last).map_err(|_e| {
+          Error::Generic(format!(
+            "WINDOW is set to '{value}', expected a duration such as 36h or 2d12h, today, \
+             yesterday, this-week, last-week or {SINCE_LAST_RUN}"
+          ))
+        })
+      }
+    };
+
+    Ok(Self::Calendar { period, timezone })
+  }
+
+  /// The first instant of the window and, if it ends before `now`, the first instant
+  /// after it.
+  pub fn bounds(&self, now: DateTime<Utc>) -> (DateTime<Utc>, Option<DateTime<Utc>>) {
+    match *self {
+      Self::Last(duration) => (
+        now
+          .checked_sub_signed(duration)
+          .unwrap_or(DateTime::<Utc>::MIN_UTC),
+        None,
+      ),
+      Self::Since(start) => (start, None),
+      Self::Calendar { period, timezone } => {
+        let today = now.with_timezone(&timezone).date_naive();
+        let monday = today - Days::new(u64::from(today.weekday().num_days_from_monday()));
+        let midnight = |date: NaiveDate| start_of_day(date, timezone);
+
+        match period {
+          Period::Today => (midnight(today), None),
+          Period::Yesterday => (midnight(today - Days::new(1)), Some(midnight(today))),
+          Period::ThisWeek => (midnight(monday), None),
+          Period::LastWeek => (midnight(monday - Days::new(7)), Some(midnight(monday))),
+        }
+      }
+    }
+  }
+}
+
+impl TryFrom<String> for FuturePosts {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    match value.trim().to_lowercase().as_str() {
+      "clamp" => Ok(Self::Clamp),
+      "drop" => Ok(Self::Drop),
+      _ => Err(format!(
+        "FUTURE_POSTS is set to '{value}', expected clamp or drop"
+      )),
+    }
+  }
+}
+
+/// Parses a duration made of whole numbers of weeks (`w`), days (`d`), hours (`h`),
+/// minutes (`m`) and seconds (`s`), such as `36h` or `2d12h`. `0` is also accepted.
+pub fn parse_duration(value: &str) -> Result<Duration, Error> {
+  let invalid = || {
+    Error::Generic(format!(
+      "Invalid duration '{value}', expected for example 36h or 2d12h"
+    ))
+  };
+  let trimmed = value.trim();
+
+  if trimmed == "0" {
+    return Ok(Duration::zero());
+  }
+
+  let mut total = Duration::zero();
+  let mut number = String::new();
+  for c in trimmed.chars() {
+    if c.is_ascii_digit() {
+      number.push(c);
+      continue;
+    }
+
+    let amount = number.parse::<i64>().map_err(|_e| invalid())?;
+    number.clear();
+    let unit = match c.to_ascii_lowercase() {
+      'w' => Duration::try_weeks(amount),
+      'd' => Duration::try_days(amount),
+      'h' => Duration::try_hours(amount),
+      'm' => Duration::try_minutes(amount),
+      's' => Duration::try_seconds(amount),
+      _ => None,
+    }
+    .ok_or_else(invalid)?;
+    total = total.checked_add(&unit).ok_or_else(invalid)?;
+  }
+
+  if !number.is_empty() || trimmed.is_empty() {
+    return Err(invalid());
+  }
+
+  Ok(total)
+}
+
+/// The first instant of `date` in `timezone`, which is not always midnight when the
+/// clocks change.
+fn start_of_day(date: NaiveDate, timezone: Tz) -> DateTime<Utc> {
+  (0..24)
+    .filter_map(|hour| date.and_hms_opt(hour, 0, 0))
+    .find_map(|time| timezone.from_local_datetime(&time).earliest())
+    .map_or_else(
+      || date.and_time(NaiveTime::MIN).and_utc(),
+      |start| start.with_timezone(&Utc),
+    )
+}
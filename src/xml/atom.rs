@@ -1,38 +1,93 @@
 //! [Specification](https://www.rfc-editor.org/rfc/rfc4287)
 //!
 //! ```xml
-//! <feed>
-//!   <title></title>
+//! <feed xml:lang="">
+//!   <title type="text|html|xhtml"></title>
+//!   <subtitle type="text|html|xhtml"></subtitle>?
+//!   <link rel="alternate" type="text/html" href=""/>*
+//!   <icon></icon>?
+//!   <logo></logo>?
 //!   <updated>ISO.8601</updated>
+//!   <author><name></name></author>*
 //!   <entry>
-//!     <title></title>
-//!     <link href=""/>
-//!     <updated>ISO.8601</updated>
+//!     <id></id>
+//!     <title type="text|html|xhtml"></title>
+//!     <link rel="alternate" type="text/html" href=""/>*
+//!     <feedburner:origLink></feedburner:origLink>?
+//!     <updated>ISO.8601</updated>?
 //!     <published>ISO.8601</published>?
-//!     <summary></summary>?
+//!     <dc:date>W3C date</dc:date>?
+//!     <author><name></name></author>*
+//!     <dc:creator></dc:creator>*
+//!     <summary type="text|html|xhtml"></summary>?
+//!     <content type="text|html|xhtml" src=""?></content>?
 //!     <description></description>?
+//!     <category term=""/>*
+//!     <!-- Media RSS and iTunes elements, see `media` -->
 //!   </entry>
 //! </feed>
 //! ```
+//!
+//! Relative links, including the ones of `<content>`, are resolved against the `xml:base`
+//! of `<feed>`, `<entry>`, `<link>` and `<content>`, then against
+//! [`ParseOptions::base_url`].
+//!
+//! The link of the feed or of a post is its `alternate` HTML link, falling back to any other `alternate`
+//! link, then to a `related` or `via` one and finally to its `id` if that is a URL.
+//! `self`, `replies`, `enclosure` and other links are never picked, `enclosure` links are
+//! the enclosures of the post and the HTML `replies` link its comments.
+//!
+//! Entries without authors have the authors of the feed.
 
-use chrono::{DateTime, Utc};
+use std::{borrow::Cow, fmt::Write, iter};
+
+use chrono::Utc;
 use log::warn;
-use quick_xml::DeError;
+use quick_xml::{
+  escape::escape,
+  events::{BytesStart, Event},
+  DeError, Reader,
+};
 use serde_derive::{Deserialize, Serialize};
+use url::Url;
 
-use crate::blog::{Blog, Post};
+use crate::{
+  blog::{Blog, Post},
+  text::html_to_text,
+};
 
 use super::{
-  limit_description,
+  authors, content, description,
+  error::FeedFormat,
+  markup,
+  media::{attachments, enclosure, ItunesImage, MediaContent, MediaGroup, Thumbnail},
+  non_empty, parse_w3c_date,
   traits::{BlogPost, WebFeed},
-  ParserError,
+  ParseOptions, ParserError,
 };
 
+/// The elements that are text constructs, `xhtml` ones are escaped by [`escape_xhtml`].
+const TEXT_CONSTRUCTS: [&str; 5] = ["title", "subtitle", "summary", "content", "rights"];
+
+/// The prefix of the registered `rel` values when written as IRIs.
+const RELATION_IRI: &str = "http://www.iana.org/assignments/relation/";
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 #[serde(rename_all = "camelCase")]
 #[serde(rename = "feed")]
 pub struct AtomFeed {
-  pub title: String,
+  #[serde(rename = "@base")]
+  pub xml_base: Option<String>,
+  #[serde(rename = "@lang")]
+  pub xml_lang: Option<String>,
+  pub title: Text,
+  pub subtitle: Option<Text>,
+  #[serde(rename = "link", default)]
+  pub links: Vec<Link>,
+  pub icon: Option<String>,
+  pub logo: Option<String>,
+  #[serde(rename = "author", default)]
+  pub authors: Vec<Person>,
   #[serde(rename = "entry", default)]
   pub entries: Vec<AtomPost>,
 }
@@ -41,37 +96,202 @@ pub struct AtomFeed {
 #[serde(rename_all = "camelCase")]
 #[serde(rename = "entry")]
 pub struct AtomPost {
-  pub title: String,
-  #[serde(rename = "link")]
+  #[serde(rename = "@base")]
+  pub xml_base: Option<String>,
+  pub id: Option<String>,
+  pub title: Text,
+  #[serde(rename = "link", default)]
   pub links: Vec<Link>,
-  pub summary: Option<String>,
+  /// `feedburner:origLink`, the link that a `feedproxy` link redirects to.
+  #[serde(rename = "feedburner-origLink")]
+  pub orig_link: Option<String>,
+  pub summary: Option<Text>,
+  pub content: Option<Text>,
   pub description: Option<String>,
   pub published: Option<String>,
-  pub updated: String,
+  pub updated: Option<String>,
+  #[serde(rename = "dc-date")]
+  pub dc_date: Option<String>,
+  #[serde(rename = "category", default)]
+  pub categories: Vec<Category>,
+  #[serde(rename = "author", default)]
+  pub authors: Vec<Person>,
+  #[serde(rename = "dc-creator", default)]
+  pub dc_creators: Vec<String>,
+  #[serde(rename = "media-content", default)]
+  pub media_contents: Vec<MediaContent>,
+  #[serde(rename = "media-thumbnail", default)]
+  pub media_thumbnails: Vec<Thumbnail>,
+  #[serde(rename = "media-group", default)]
+  pub media_groups: Vec<MediaGroup>,
+  #[serde(rename = "itunes-duration")]
+  pub itunes_duration: Option<String>,
+  #[serde(rename = "itunes-image")]
+  pub itunes_image: Option<ItunesImage>,
+}
+
+/// The `<category>` of an entry, only its `term` is kept.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Category {
+  #[serde(rename = "@term")]
+  pub term: String,
+}
+
+/// An `<author>`, only its `name` is kept.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Person {
+  #[serde(default)]
+  pub name: String,
+}
+
+/// A text construct such as `<title>` or `<summary>`, or the `<content>` of an entry.
+///
+/// Its `type` is `text` (the default), `html` or `xhtml`, whose markup is escaped into
+/// `html` before deserializing. `<content>` can also have a media type.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Text {
+  #[serde(rename = "@base")]
+  pub xml_base: Option<String>,
+  #[serde(rename = "@type")]
+  pub kind: Option<String>,
+  #[serde(rename = "$text", default)]
+  pub value: String,
+}
+
+impl Text {
+  /// Whether the value is HTML rather than plain text.
+  pub fn is_html(&self) -> bool {
+    self.kind.as_deref().is_some_and(|kind| {
+      matches!(
+        kind.trim().to_lowercase().as_str(),
+        "html" | "xhtml" | "text/html" | "application/xhtml+xml"
+      )
+    })
+  }
+
+  /// Whether the value is text or HTML, as opposed to other media such as images.
+  pub fn is_textual(&self) -> bool {
+    self.is_html()
+      || self.kind.as_deref().map_or(true, |kind| {
+        let kind = kind.trim().to_lowercase();
+        kind == "text" || kind.starts_with("text/")
+      })
+  }
+
+  /// The value as plain text.
+  pub fn to_text(&self) -> String {
+    if self.is_html() {
+      html_to_text(&self.value)
+    } else {
+      self.value.trim().to_owned()
+    }
+  }
+
+  /// The value as HTML, if it is textual.
+  ///
+  /// Plain text is kept as is, as many feeds escape markup without declaring it.
+  fn as_html(&self) -> Option<&str> {
+    self.is_textual().then_some(self.value.as_str())
+  }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 pub struct Link {
   // See https://github.com/tafia/quick-xml/issues/534
   #[serde(rename = "@href")]
-  href: String,
+  href: Option<String>,
+  #[serde(rename = "@rel")]
+  rel: Option<String>,
+  #[serde(rename = "@type")]
+  kind: Option<String>,
+  #[serde(rename = "@length")]
+  length: Option<String>,
+  #[serde(rename = "@base")]
+  xml_base: Option<String>,
+}
+
+impl Link {
+  /// The `href` resolved against the `xml:base` of the link, `None` if it has none.
+  fn resolve(&self, options: &ParseOptions) -> Option<String> {
+    let href = self.href.as_deref()?.trim();
+    (!href.is_empty()).then(|| {
+      options
+        .with_xml_base(self.xml_base.as_deref())
+        .resolve(href)
+    })
+  }
+
+  fn relation(&self) -> String {
+    let rel = self
+      .rel
+      .as_deref()
+      .map_or_else(|| "alternate".to_owned(), |rel| rel.trim().to_lowercase());
+
+    rel
+      .strip_prefix(RELATION_IRI)
+      .map_or_else(|| rel.clone(), ToOwned::to_owned)
+  }
+
+  /// Whether the link points to an HTML page, which is assumed if it has no type.
+  fn is_html(&self) -> bool {
+    self.kind.as_deref().map_or(true, |kind| {
+      matches!(
+        kind.trim().to_lowercase().as_str(),
+        "text/html" | "application/xhtml+xml"
+      )
+    })
+  }
+
+  /// How suitable the link is as the link of a post, lower is better. `None` if it
+  /// points to something other than the post, such as its comments or the feed.
+  fn rank(&self) -> Option<u8> {
+    match self.relation().as_str() {
+      "alternate" if self.is_html() => Some(0),
+      "alternate" => Some(1),
+      "related" | "via" => Some(2),
+      _ => None,
+    }
+  }
 }
 
 impl WebFeed for Result<AtomFeed, DeError> {
-  fn into_blog(self) -> Result<Blog, ParserError> {
-    let feed = self?;
-    let title = feed.title;
+  fn into_blog(self, options: &ParseOptions) -> Result<Blog, ParserError> {
+    let feed = self.map_err(|e| ParserError::deserialize(FeedFormat::Atom, e))?;
+    let options = options.with_xml_base(feed.xml_base.as_deref());
+    let title = feed.title.to_text();
+    let link = best_link(&feed.links, &options);
+    let description = feed
+      .subtitle
+      .as_ref()
+      .map(Text::to_text)
+      .filter(|description| !description.is_empty());
+    let icon =
+      non_empty(feed.icon.as_deref().or(feed.logo.as_deref())).map(|icon| options.resolve(&icon));
+    let language = non_empty(feed.xml_lang.as_deref());
+    let feed_authors = authors(
+      feed
+        .authors
+        .iter()
+        .map(|author| author.name.as_str())
+        .collect(),
+    );
 
     let posts: Vec<Post> = feed
       .entries
       .iter()
       // TODO Turn this into a method
-      .filter_map(|x| match x.clone().into_post() {
+      .filter_map(|x| match x.clone().into_post(&options) {
+        Ok(post) if post.authors.is_empty() => Some(Post {
+          authors: feed_authors.clone(),
+          ..post
+        }),
         Ok(post) => Some(post),
         Err(e) => {
           warn!(
             "\"{}\"'s post titled \"{}\" errored with '{}'",
-            title, x.title, e
+            title,
+            x.title.to_text(),
+            e
           );
           None
         }
@@ -79,46 +299,177 @@ impl WebFeed for Result<AtomFeed, DeError> {
       .collect::<Vec<_>>();
 
     if posts.is_empty() {
-      return Err(ParserError::Parse(format!("Empty feed: {title}")));
+      return Err(ParserError::parse(
+        FeedFormat::Atom,
+        format!("Empty feed: {title}"),
+      ));
     }
 
     let last_build_date = posts
       .iter()
       .map(|x| x.pub_date)
       .max()
-      .ok_or_else(|| ParserError::Parse("Date error.".to_owned()))?;
+      .ok_or_else(|| ParserError::parse(FeedFormat::Atom, "Date error."))?;
 
     Ok(Blog {
       title,
       most_recent_pub_date: last_build_date,
       posts,
+      link,
+      description,
+      icon,
+      language,
+      feed_url: None,
     })
   }
 }
 
+/// The resolved link with the best [rank](Link::rank), if any. Links without an `href`
+/// are ignored.
+fn best_link(links: &[Link], options: &ParseOptions) -> Option<String> {
+  links
+    .iter()
+    .filter_map(|link| Some((link.rank()?, link.resolve(options)?)))
+    .min_by_key(|(rank, _)| *rank)
+    .map(|(_, link)| link)
+}
+
+impl AtomPost {
+  /// The `feedburner:origLink`, or the best ranked link, or the `id` if it is a web URL.
+  fn link(&self, id: Option<&str>, options: &ParseOptions) -> Result<String, ParserError> {
+    self
+      .orig_link
+      .as_deref()
+      .filter(|link| !link.trim().is_empty())
+      .map(|link| options.resolve(link))
+      .or_else(|| best_link(&self.links, options))
+      .or_else(|| {
+        id.filter(|id| Url::parse(id).is_ok_and(|url| matches!(url.scheme(), "http" | "https")))
+          .map(ToOwned::to_owned)
+      })
+      .ok_or_else(|| ParserError::parse(FeedFormat::Atom, "No link in post"))
+  }
+
+  /// The cleaned `content`, or the `summary` if it has markup, with resolved links.
+  fn html_content(&self, options: &ParseOptions) -> Option<String> {
+    self
+      .content
+      .as_ref()
+      .or_else(|| {
+        self
+          .summary
+          .as_ref()
+          .filter(|summary| markup(summary.as_html()).is_some())
+      })
+      .and_then(|text| {
+        content(
+          text.as_html(),
+          &options.with_xml_base(text.xml_base.as_deref()),
+        )
+      })
+  }
+
+  /// The HTML `replies` link.
+  fn comments(&self, options: &ParseOptions) -> Option<String> {
+    self
+      .links
+      .iter()
+      .filter(|link| link.relation() == "replies" && link.is_html())
+      .find_map(|link| link.resolve(options))
+  }
+}
+
 impl BlogPost for AtomPost {
-  fn into_post(self) -> Result<Post, ParserError> {
-    let title = self.title;
-    // Use the first link for now
-    let link = self.links[0].href.clone();
-    let description = self
-      .summary
-      .or(self.description)
-      .map(|desc| limit_description(&desc, 200));
-
-    // Use publish date if exists otherwise fallback to updated
-    let pub_date = self.published.unwrap_or(self.updated);
+  fn into_post(self, options: &ParseOptions) -> Result<Post, ParserError> {
+    let options = options.with_xml_base(self.xml_base.as_deref());
+    let title = self.title.to_text();
+    let id = self
+      .id
+      .as_deref()
+      .map(|id| id.trim().to_owned())
+      .filter(|id| !id.is_empty());
+
+    let link = self.link(id.as_deref(), &options)?;
+    let comments = self.comments(&options);
+    let content = self.html_content(&options);
+
+    let groups = iter::once(MediaGroup {
+      contents: self.media_contents,
+      thumbnails: self.media_thumbnails,
+      description: None,
+    })
+    .chain(self.media_groups)
+    .collect::<Vec<_>>();
+
+    let description = description(
+      &[
+        self.summary.as_ref().and_then(Text::as_html),
+        self.content.as_ref().and_then(Text::as_html),
+        self.description.as_deref(),
+        groups.iter().find_map(|group| group.description.as_deref()),
+      ],
+      &options,
+    );
+
+    let (enclosures, thumbnail) = attachments(
+      self
+        .links
+        .iter()
+        .filter(|link| link.relation() == "enclosure")
+        .filter_map(|link| {
+          enclosure(
+            &link.resolve(&options)?,
+            link.kind.as_deref(),
+            link.length.as_deref(),
+          )
+        })
+        .collect(),
+      &groups,
+      self.itunes_duration.as_deref(),
+      self.itunes_image.as_ref(),
+      &options,
+    );
+
+    let authors = authors(
+      self
+        .authors
+        .iter()
+        .map(|author| author.name.as_str())
+        .chain(self.dc_creators.iter().map(String::as_str))
+        .collect(),
+    );
+
+    // Use publish date if exists otherwise fallback to updated, then to `dc:date`
+    let pub_date = self
+      .published
+      .or(self.updated)
+      .or(self.dc_date)
+      .unwrap_or_default();
+    let categories = self
+      .categories
+      .into_iter()
+      .map(|category| category.term)
+      .filter(|term| !term.trim().is_empty())
+      .collect();
 
     if pub_date.is_empty() {
       return Err(ParserError::empty_date_error());
     }
 
-    match DateTime::parse_from_rfc3339(&pub_date) {
+    match parse_w3c_date(&pub_date) {
       Ok(last_build_date) => Ok(Post {
         title,
         link,
         description,
+        content,
         pub_date: last_build_date.with_timezone(&Utc),
+        categories,
+        authors,
+        comments,
+        id,
+        feeds: Vec::new(),
+        enclosures,
+        thumbnail,
       }),
       Err(e) => Err(ParserError::generic_date_error(format!(
         "Error parsing date '{pub_date}' ({e})"
@@ -126,3 +477,100 @@ impl BlogPost for AtomPost {
     }
   }
 }
+
+/// Rewrites the `type="xhtml"` [text constructs](TEXT_CONSTRUCTS) of `xml` into
+/// `type="html"` ones holding their markup as escaped text, without the wrapping `<div>`,
+/// so that they can be deserialized into a [`Text`].
+///
+/// Returns `xml` as is if it has none, or is not well-formed.
+pub(super) fn escape_xhtml(xml: &str) -> Cow<'_, str> {
+  let mut reader = Reader::from_str(xml);
+  let mut escaped = String::new();
+  let mut copied = 0;
+
+  loop {
+    let start = reader.buffer_position();
+    match reader.read_event() {
+      Ok(Event::Start(tag)) if is_xhtml_construct(&tag) => {
+        let Ok(inner) = reader.read_text(tag.name()) else {
+          return Cow::Borrowed(xml);
+        };
+        let Ok(name) = std::str::from_utf8(tag.name().into_inner()) else {
+          return Cow::Borrowed(xml);
+        };
+
+        escaped.push_str(&xml[copied..start]);
+        let _ = write!(
+          escaped,
+          "<{name}{} type=\"html\">{}</{name}>",
+          attributes_except_type(&tag),
+          escape(strip_div(&inner))
+        );
+        copied = reader.buffer_position();
+      }
+      Ok(Event::Eof) => break,
+      Err(_) => return Cow::Borrowed(xml),
+      Ok(_) => {}
+    }
+  }
+
+  if copied == 0 {
+    return Cow::Borrowed(xml);
+  }
+  escaped.push_str(&xml[copied..]);
+  Cow::Owned(escaped)
+}
+
+fn is_xhtml_construct(tag: &BytesStart<'_>) -> bool {
+  let is_text_construct = std::str::from_utf8(tag.local_name().into_inner())
+    .is_ok_and(|name| TEXT_CONSTRUCTS.contains(&name));
+
+  is_text_construct
+    && tag.attributes().flatten().any(|attribute| {
+      attribute.key.local_name().as_ref() == b"type"
+        && std::str::from_utf8(&attribute.value).is_ok_and(|kind| kind.trim() == "xhtml")
+    })
+}
+
+/// The attributes of `tag` other than `type`, double-quoted whatever quotes they were
+/// written with.
+fn attributes_except_type(tag: &BytesStart<'_>) -> String {
+  tag
+    .attributes()
+    .flatten()
+    .filter(|attribute| attribute.key.local_name().as_ref() != b"type")
+    .fold(String::new(), |mut attributes, attribute| {
+      let value = attribute.unescape_value().map_or_else(
+        |_| String::from_utf8_lossy(&attribute.value).replace('"', "&quot;"),
+        |value| escape(&value).into_owned(),
+      );
+      let _ = write!(
+        attributes,
+        " {}=\"{value}\"",
+        String::from_utf8_lossy(attribute.key.as_ref()),
+      );
+      attributes
+    })
+}
+
+/// `xhtml` without the `<div>` that wraps the contents of a text construct.
+fn strip_div(xhtml: &str) -> &str {
+  let xhtml = xhtml.trim();
+  let Some(tag) = xhtml.strip_prefix('<') else {
+    return xhtml;
+  };
+
+  let name_end = tag
+    .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+    .unwrap_or(tag.len());
+  let name = &tag[..name_end];
+  if name.rsplit(':').next() != Some("div") {
+    return xhtml;
+  }
+
+  let closing = format!("</{name}>");
+  match (tag.find('>'), xhtml.strip_suffix(closing.as_str())) {
+    (Some(end), Some(_)) => xhtml[end + 2..xhtml.len() - closing.len()].trim(),
+    _ => xhtml,
+  }
+}
@@ -4,12 +4,37 @@ use quick_xml::DeError;
 
 /// Represents possible issues that may arise when trying to parse web feeds.
 /// If this occurs then a web feed is considered invalid.
-#[derive(Debug, Eq, PartialEq, Hash)]
+#[derive(Debug)]
 pub enum ParserError {
-  /// Generic parsing error.
-  Parse(String),
+  /// The document is not a valid feed of the given format.
+  Parse {
+    /// The format the document was parsed as.
+    format: FeedFormat,
+    /// The byte offset of the malformed XML, if the document is not well-formed.
+    position: Option<usize>,
+    message: String,
+    /// The deserializer error behind it, if any.
+    source: Option<Box<DeError>>,
+  },
   /// Date format error.
   Date(DateError),
+  /// The document is neither a valid RSS nor a valid Atom feed.
+  ///
+  /// Its [`source`](std::error::Error::source) is the RSS error, both are shown when it is
+  /// displayed.
+  UnknownFormat {
+    /// Why it is not an RSS feed.
+    rss: Box<Self>,
+    /// Why it is not an Atom feed.
+    atom: Box<Self>,
+  },
+}
+
+/// The web feed formats.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum FeedFormat {
+  Rss,
+  Atom,
 }
 
 /// Represents different types of Date errors.
@@ -21,6 +46,45 @@ pub enum DateError {
 }
 
 impl ParserError {
+  /// Less verbose way of instantiating a [`ParserError::Parse`] without a position.
+  pub fn parse(format: FeedFormat, message: impl Into<String>) -> Self {
+    Self::Parse {
+      format,
+      position: None,
+      message: message.into(),
+      source: None,
+    }
+  }
+
+  /// A [`ParserError::Parse`] for a document that could not be deserialized as `format`.
+  pub fn deserialize(format: FeedFormat, error: DeError) -> Self {
+    Self::Parse {
+      format,
+      position: None,
+      message: error.to_string(),
+      source: Some(Box::new(error)),
+    }
+  }
+
+  /// Sets the position of a [`ParserError::Parse`] that does not have one yet.
+  #[must_use]
+  pub fn at(self, at: Option<usize>) -> Self {
+    match self {
+      Self::Parse {
+        format,
+        position: None,
+        message,
+        source,
+      } => Self::Parse {
+        format,
+        position: at,
+        message,
+        source,
+      },
+      e => e,
+    }
+  }
+
   /// Less verbose way of instantiating a [`DateError::Generic`].
   pub const fn generic_date_error(msg: String) -> Self {
     Self::Date(DateError::Generic(msg))
@@ -37,17 +101,45 @@ impl ParserError {
   }
 }
 
-impl From<DeError> for ParserError {
-  fn from(e: DeError) -> Self {
-    Self::Parse(e.to_string())
+impl Display for ParserError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Parse {
+        format,
+        position: Some(position),
+        message,
+        ..
+      } => write!(f, "Parse error ({format}, at byte {position}): {message}"),
+      Self::Parse {
+        format,
+        position: None,
+        message,
+        ..
+      } => write!(f, "Parse error ({format}): {message}"),
+      Self::Date(e) => write!(f, "{e}"),
+      Self::UnknownFormat { rss, atom } => write!(f, "{rss}\n{atom}"),
+    }
   }
 }
 
-impl Display for ParserError {
+impl std::error::Error for ParserError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Parse {
+        source: Some(e), ..
+      } => Some(e.as_ref()),
+      Self::Parse { source: None, .. } => None,
+      Self::Date(e) => Some(e),
+      Self::UnknownFormat { rss, .. } => Some(rss.as_ref()),
+    }
+  }
+}
+
+impl Display for FeedFormat {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
-      Self::Parse(e) => write!(f, "Parse error: {e}"),
-      Self::Date(e) => write!(f, "{e}"),
+      Self::Rss => write!(f, "RSS"),
+      Self::Atom => write!(f, "Atom"),
     }
   }
 }
@@ -61,3 +153,5 @@ impl Display for DateError {
     }
   }
 }
+
+impl std::error::Error for DateError {}
@@ -0,0 +1,210 @@
+//! [Media RSS](https://www.rssboard.org/media-rss) and
+//! [iTunes](https://podcasters.apple.com/support/823-podcast-requirements) elements, shared
+//! by RSS items and Atom entries.
+//!
+//! ```xml
+//! <media:group>?
+//!   <media:content url="" type="" fileSize="" duration="" medium=""/>*
+//!   <media:thumbnail url=""/>*
+//!   <media:description></media:description>?
+//! </media:group>
+//! <media:content url="" type="" fileSize="" duration="" medium="">*
+//!   <media:thumbnail url=""/>*
+//! </media:content>
+//! <media:thumbnail url=""/>*
+//! <itunes:duration>[[HH:]MM:]SS</itunes:duration>?
+//! <itunes:image href=""/>?
+//! ```
+//!
+//! Images are used as the thumbnail of the post rather than as enclosures.
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::blog::Enclosure;
+
+use super::ParseOptions;
+
+/// A `<media:group>`, or the Media RSS elements of an item or entry.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct MediaGroup {
+  #[serde(rename = "media-content", default)]
+  pub contents: Vec<MediaContent>,
+  #[serde(rename = "media-thumbnail", default)]
+  pub thumbnails: Vec<Thumbnail>,
+  #[serde(rename = "media-description")]
+  pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct MediaContent {
+  #[serde(rename = "@url")]
+  pub url: Option<String>,
+  #[serde(rename = "@type")]
+  pub kind: Option<String>,
+  #[serde(rename = "@fileSize")]
+  pub file_size: Option<String>,
+  #[serde(rename = "@duration")]
+  pub duration: Option<String>,
+  #[serde(rename = "@medium")]
+  pub medium: Option<String>,
+  #[serde(rename = "media-thumbnail", default)]
+  pub thumbnails: Vec<Thumbnail>,
+}
+
+impl MediaContent {
+  fn is_image(&self) -> bool {
+    self
+      .medium
+      .as_deref()
+      .is_some_and(|medium| medium.trim().eq_ignore_ascii_case("image"))
+      || self
+        .kind
+        .as_deref()
+        .is_some_and(|kind| kind.trim().to_lowercase().starts_with("image/"))
+  }
+
+  /// Whether this is an embeddable video player rather than a file.
+  fn is_player(&self) -> bool {
+    self.kind.as_deref().is_some_and(|kind| {
+      kind
+        .trim()
+        .eq_ignore_ascii_case("application/x-shockwave-flash")
+    })
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Thumbnail {
+  #[serde(rename = "@url")]
+  pub url: Option<String>,
+}
+
+/// An `<itunes:image>`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ItunesImage {
+  #[serde(rename = "@href")]
+  pub href: Option<String>,
+}
+
+/// An enclosure from the attributes of an element, `None` if it has no URL.
+pub(super) fn enclosure(
+  url: &str,
+  mime_type: Option<&str>,
+  length: Option<&str>,
+) -> Option<Enclosure> {
+  let url = url.trim();
+  (!url.is_empty()).then(|| Enclosure {
+    url: url.to_owned(),
+    mime_type: mime_type
+      .map(str::trim)
+      .filter(|kind| !kind.is_empty())
+      .map(ToOwned::to_owned),
+    length: length
+      .and_then(|length| length.trim().parse().ok())
+      .filter(|length| *length > 0),
+    duration: None,
+  })
+}
+
+/// The enclosures and the thumbnail of a post, from its `enclosures` and the media
+/// elements of its `groups`, with their links resolved.
+///
+/// Enclosures with the same URL are merged, and the `itunes:duration` is the duration of
+/// the first one.
+pub(super) fn attachments(
+  enclosures: Vec<Enclosure>,
+  groups: &[MediaGroup],
+  itunes_duration: Option<&str>,
+  itunes_image: Option<&ItunesImage>,
+  options: &ParseOptions,
+) -> (Vec<Enclosure>, Option<String>) {
+  let mut media = Vec::new();
+  let mut thumbnails = Vec::new();
+  let mut images = Vec::new();
+
+  for group in groups {
+    for content in &group.contents {
+      thumbnails.extend(
+        content
+          .thumbnails
+          .iter()
+          .filter_map(|thumbnail| thumbnail.url.as_deref()),
+      );
+
+      let Some(url) = content.url.as_deref() else {
+        continue;
+      };
+      if content.is_image() {
+        images.push(url);
+      } else if !content.is_player() {
+        media.extend(
+          enclosure(url, content.kind.as_deref(), content.file_size.as_deref()).map(|enclosure| {
+            Enclosure {
+              duration: content.duration.as_deref().and_then(parse_duration),
+              ..enclosure
+            }
+          }),
+        );
+      }
+    }
+    thumbnails.extend(
+      group
+        .thumbnails
+        .iter()
+        .filter_map(|thumbnail| thumbnail.url.as_deref()),
+    );
+  }
+
+  let mut merged: Vec<Enclosure> = Vec::new();
+  for enclosure in enclosures.into_iter().chain(media) {
+    let url = options.resolve(&enclosure.url);
+    match merged.iter_mut().find(|merged| merged.url == url) {
+      Some(merged) => {
+        merged.mime_type = merged.mime_type.take().or(enclosure.mime_type);
+        merged.length = merged.length.or(enclosure.length);
+        merged.duration = merged.duration.or(enclosure.duration);
+      }
+      None => merged.push(Enclosure { url, ..enclosure }),
+    }
+  }
+
+  if let Some(first) = merged.first_mut() {
+    first.duration = first
+      .duration
+      .or_else(|| itunes_duration.and_then(parse_duration));
+  }
+
+  let thumbnail = thumbnails
+    .into_iter()
+    .chain(images)
+    .chain(itunes_image.and_then(|image| image.href.as_deref()))
+    .map(str::trim)
+    .find(|url| !url.is_empty())
+    .map(|url| options.resolve(url));
+
+  (merged, thumbnail)
+}
+
+/// Parses a duration in seconds, as `SS`, `MM:SS` or `HH:MM:SS` with optional fractions
+/// of a second.
+fn parse_duration(duration: &str) -> Option<u64> {
+  let duration = duration.trim();
+  let whole = duration
+    .split_once('.')
+    .map_or(duration, |(whole, _)| whole);
+  let parts = whole.split(':').collect::<Vec<_>>();
+  if parts.len() > 3 {
+    return None;
+  }
+
+  parts
+    .into_iter()
+    .try_fold(0_u64, |seconds, part| {
+      part
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|part| seconds.checked_mul(60)?.checked_add(part))
+    })
+    .filter(|seconds| *seconds > 0)
+}
@@ -1,38 +1,184 @@
 //! Parses web feeds according to the RSS and Atom specifications and constructs
 //! [`Blog`]s and [`Post`](crate::blog::Post)s.
 
-use quick_xml::de::from_str;
+use chrono::{DateTime, FixedOffset, NaiveDate, ParseError};
+use quick_xml::{de::from_str, events::Event, Reader};
+use url::Url;
 
-use crate::blog::Blog;
+use crate::{
+  blog::Blog,
+  sanitize::clean_content,
+  text::{html_to_text, truncate},
+};
 
 use self::{atom::AtomFeed, error::ParserError, rss::RssFeed, traits::WebFeed};
 
 pub mod atom;
 pub mod error;
+pub mod media;
+mod namespace;
 pub mod rss;
 mod traits;
 
-/// Turns an XML feed into a `Blog` if possible.
+/// The default maximum length of a post description, in characters.
+pub const DEFAULT_DESCRIPTION_LENGTH: usize = 200;
+
+/// Settings that control how web feeds are turned into [`Blog`]s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+  /// The maximum length of a post description, in characters.
+  pub description_length: usize,
+  /// The URL the feed was downloaded from. Relative links are resolved against it,
+  /// after the `xml:base` of their elements, and left as is if it is `None`.
+  pub base_url: Option<Url>,
+}
+
+impl Default for ParseOptions {
+  fn default() -> Self {
+    Self {
+      description_length: DEFAULT_DESCRIPTION_LENGTH,
+      base_url: None,
+    }
+  }
+}
+
+impl ParseOptions {
+  /// The options of the children of an element with the given `xml:base`.
+  fn with_xml_base(&self, xml_base: Option<&str>) -> Self {
+    let Some(xml_base) = xml_base.map(str::trim).filter(|base| !base.is_empty()) else {
+      return self.clone();
+    };
+
+    let base_url = self.base_url.as_ref().map_or_else(
+      || Url::parse(xml_base).ok(),
+      |base_url| base_url.join(xml_base).ok(),
+    );
+
+    Self {
+      base_url: base_url.or_else(|| self.base_url.clone()),
+      ..self.clone()
+    }
+  }
+
+  /// `link` resolved against the base URL. Absolute links are left untouched.
+  fn resolve(&self, link: &str) -> String {
+    let link = link.trim();
+    if Url::parse(link).is_ok() {
+      return link.to_owned();
+    }
+
+    self
+      .base_url
+      .as_ref()
+      .and_then(|base_url| base_url.join(link).ok())
+      .map_or_else(|| link.to_owned(), String::from)
+  }
+}
+
+/// Turns an XML feed into a `Blog` if possible, using the default [`ParseOptions`].
 ///
 /// First tries to parse it into an [`RssFeed`]. If that fails,
 /// it then tries to parse it into an [`AtomFeed`]. If both fail,
-/// the error is set to `Error1. Error2`.
+/// a [`ParserError::UnknownFormat`] holds both errors.
 pub fn parse_web_feed(xml: &str) -> Result<Blog, ParserError> {
-  from_str::<RssFeed>(xml).into_blog().or_else(|e1| {
-    from_str::<AtomFeed>(xml)
-      .into_blog()
-      .map_err(|e2| ParserError::Parse(format!("{e1}\n{e2}")))
-  })
+  parse_web_feed_with(xml, &ParseOptions::default())
+}
+
+/// Same as [`parse_web_feed`] but with the given [`ParseOptions`].
+pub fn parse_web_feed_with(xml: &str, options: &ParseOptions) -> Result<Blog, ParserError> {
+  from_str::<RssFeed>(&namespace::qualify(xml))
+    .into_blog(options)
+    .or_else(|rss| {
+      from_str::<AtomFeed>(&namespace::qualify(&atom::escape_xhtml(xml)))
+        .into_blog(options)
+        .map_err(|atom| {
+          let position = syntax_error_position(xml);
+          ParserError::UnknownFormat {
+            rss: Box::new(rss.at(position)),
+            atom: Box::new(atom.at(position)),
+          }
+        })
+    })
 }
 
-/// Limits the description to the amount of specified characters
-/// and postpends `...`.
-fn limit_description(desc: &str, limit: usize) -> String {
-  let mut res = desc.chars().take(limit).collect::<String>();
+/// The byte offset at which `xml` stops being well-formed, if it does.
+fn syntax_error_position(xml: &str) -> Option<usize> {
+  let mut reader = Reader::from_str(xml);
 
-  if desc.chars().count() >= limit {
-    res += "...";
+  loop {
+    match reader.read_event() {
+      Ok(Event::Eof) => return None,
+      Ok(_) => {}
+      Err(_) => return Some(reader.buffer_position()),
+    }
+  }
+}
+
+/// Turns the first non-empty HTML fragment of `candidates` into a plain-text
+/// description, shortened to `options.description_length`.
+fn description(candidates: &[Option<&str>], options: &ParseOptions) -> Option<String> {
+  candidates
+    .iter()
+    .flatten()
+    .map(|html| html_to_text(html))
+    .find(|text| !text.is_empty())
+    .map(|text| truncate(&text, options.description_length))
+}
+
+/// `html` if it has markup, which is lost when it is turned into a plain-text description.
+fn markup(html: Option<&str>) -> Option<&str> {
+  html.filter(|html| html.contains('<'))
+}
+
+/// Cleans the HTML of the full content of a post, resolving its relative links.
+fn content(html: Option<&str>, options: &ParseOptions) -> Option<String> {
+  html
+    .map(|html| clean_content(html, options.base_url.as_ref()))
+    .filter(|html| !html.trim().is_empty())
+}
+
+/// `value` trimmed, if it is not empty.
+fn non_empty(value: Option<&str>) -> Option<String> {
+  value
+    .map(str::trim)
+    .filter(|value| !value.is_empty())
+    .map(ToOwned::to_owned)
+}
+
+/// The distinct non-empty names of `names`, in order.
+fn authors(names: Vec<&str>) -> Vec<String> {
+  let mut authors: Vec<String> = Vec::new();
+
+  for name in names
+    .into_iter()
+    .map(str::trim)
+    .filter(|name| !name.is_empty())
+  {
+    if !authors
+      .iter()
+      .any(|author| author.eq_ignore_ascii_case(name))
+    {
+      authors.push(name.to_owned());
+    }
   }
 
-  res
+  authors
+}
+
+/// Parses a [W3C date](https://www.w3.org/TR/NOTE-datetime), as used by Atom and Dublin
+/// Core: RFC 3339, or the same without seconds or without a time (midnight UTC).
+fn parse_w3c_date(date: &str) -> Result<DateTime<FixedOffset>, ParseError> {
+  let date = date.trim();
+
+  DateTime::parse_from_rfc3339(date).or_else(|e| {
+    DateTime::parse_from_str(date, "%Y-%m-%dT%H:%M%#z")
+      .ok()
+      .or_else(|| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+          .ok()
+          .and_then(|day| day.and_hms_opt(0, 0, 0))
+          .map(|midnight| midnight.and_utc().fixed_offset())
+      })
+      .ok_or(e)
+  })
 }
@@ -0,0 +1,135 @@
+//! Makes the elements of extension namespaces distinguishable when deserializing.
+//!
+//! [`quick_xml`]'s deserializer only sees the local name of elements, so `<itunes:title>`
+//! would be read as a second `<title>`. [`qualify`] renames every element that is not in
+//! the namespace of the root element to `{prefix}-{local name}`, where the prefix is the
+//! conventional one of the [namespaces](NAMESPACES) that are known, whatever prefix the
+//! feed binds them to, and the prefix written in the feed otherwise:
+//!
+//! ```xml
+//! <rss xmlns:c="http://purl.org/rss/1.0/modules/content/">
+//!   <channel><item><c:encoded/></item></channel>
+//! </rss>
+//! ```
+//!
+//! becomes `<content-encoded/>`.
+
+use std::borrow::Cow;
+
+use quick_xml::{
+  events::Event,
+  name::{QName, ResolveResult},
+  reader::NsReader,
+};
+
+/// The known namespaces and their conventional prefix.
+const NAMESPACES: [(&str, &str); 8] = [
+  ("http://www.w3.org/2005/Atom", "atom"),
+  ("http://purl.org/rss/1.0/modules/content/", "content"),
+  ("http://purl.org/dc/elements/1.1/", "dc"),
+  ("http://rssnamespace.org/feedburner/ext/1.0", "feedburner"),
+  ("http://www.itunes.com/dtds/podcast-1.0.dtd", "itunes"),
+  ("http://search.yahoo.com/mrss/", "media"),
+  ("http://wellformedweb.org/CommentAPI/", "wfw"),
+  ("http://purl.org/rss/1.0/modules/slash/", "slash"),
+];
+
+/// Renames the elements of `xml` that are not in the namespace of its root element, see
+/// the [module documentation](self).
+///
+/// Returns `xml` as is if there are none, or if it is not well-formed.
+pub(super) fn qualify(xml: &str) -> Cow<'_, str> {
+  let mut reader = NsReader::from_str(xml);
+  let mut qualified = String::new();
+  let mut copied = 0;
+  let mut root = None;
+
+  loop {
+    let start = reader.buffer_position();
+    let (namespace, event) = match reader.read_resolved_event() {
+      Ok((_, Event::Eof)) => break,
+      Ok((namespace, event)) => (namespace_of(namespace), event),
+      Err(_) => return Cow::Borrowed(xml),
+    };
+
+    let name = match &event {
+      Event::Start(tag) | Event::Empty(tag) => {
+        let root = root.get_or_insert_with(|| namespace.clone());
+        (namespace != *root).then(|| qualified_name(tag.name(), &namespace))
+      }
+      Event::End(tag) => root
+        .as_ref()
+        .filter(|root| namespace != **root)
+        .map(|_| qualified_name(tag.name(), &namespace)),
+      _ => None,
+    };
+    let Some(name) = name else {
+      continue;
+    };
+
+    qualified.push_str(&xml[copied..start]);
+    match &event {
+      Event::Start(tag) => {
+        qualified.push('<');
+        qualified.push_str(&name);
+        qualified.push_str(&String::from_utf8_lossy(tag.attributes_raw()));
+        qualified.push('>');
+      }
+      Event::Empty(tag) => {
+        qualified.push('<');
+        qualified.push_str(&name);
+        qualified.push_str(&String::from_utf8_lossy(tag.attributes_raw()));
+        qualified.push_str("/>");
+      }
+      _ => {
+        qualified.push_str("</");
+        qualified.push_str(&name);
+        qualified.push('>');
+      }
+    }
+    copied = reader.buffer_position();
+  }
+
+  if copied == 0 {
+    return Cow::Borrowed(xml);
+  }
+  qualified.push_str(&xml[copied..]);
+  Cow::Owned(qualified)
+}
+
+/// The namespace an element is in, `Ok` with its URI if it is bound and `Err` with its
+/// prefix if that is not declared.
+type ElementNamespace = Result<Option<Vec<u8>>, Vec<u8>>;
+
+fn namespace_of(namespace: ResolveResult<'_>) -> ElementNamespace {
+  match namespace {
+    ResolveResult::Unbound => Ok(None),
+    ResolveResult::Bound(namespace) => Ok(Some(namespace.into_inner().to_vec())),
+    ResolveResult::Unknown(prefix) => Err(prefix),
+  }
+}
+
+/// `{prefix}-{local name}`, with the conventional prefix of `namespace` if it is known.
+fn qualified_name(name: QName<'_>, namespace: &ElementNamespace) -> String {
+  let known = namespace
+    .as_ref()
+    .ok()
+    .and_then(Option::as_ref)
+    .and_then(|uri| {
+      let uri = uri.strip_suffix(b"/").unwrap_or(uri);
+      NAMESPACES
+        .iter()
+        .find(|(known, _)| known.trim_end_matches('/').as_bytes() == uri)
+        .map(|(_, prefix)| prefix.as_bytes())
+    });
+  let written = name.prefix();
+  let prefix = known
+    .or_else(|| written.as_ref().map(AsRef::as_ref))
+    .unwrap_or(b"ns");
+
+  format!(
+    "{}-{}",
+    String::from_utf8_lossy(prefix),
+    String::from_utf8_lossy(name.local_name().as_ref())
+  )
+}
@@ -1,20 +1,45 @@
 //! [Specification](https://www.rssboard.org/rss-specification)
 //!
 //! ```xml
-//! <rss>
-//!   <channel>
+//! <rss xml:lang="">
+//!   <channel xml:lang="">
 //!     <title></title>
+//!     <link></link>?
+//!     <description></description>?
+//!     <language></language>?
+//!     <image><url></url></image>?
+//!     <itunes:image href=""/>?
 //!     <lastBuildDate>RFC 2822</lastBuildDate>
 //!     <pubDate>RFC 2822</pubDate>
+//!     <dc:date>W3C date</dc:date>?
 //!     <item>
 //!       <title></title>
 //!       <link></link>
+//!       <feedburner:origLink></feedburner:origLink>?
+//!       <guid isPermaLink="true"></guid>?
 //!       <pubDate>RFC 2822</pubDate>
 //!       <description></description>?
+//!       <content:encoded></content:encoded>?
+//!       <category></category>*
+//!       <author>email (name)</author>?
+//!       <dc:creator></dc:creator>*
+//!       <dc:date>W3C date</dc:date>?
+//!       <comments></comments>?
+//!       <enclosure url="" length="" type=""/>*
+//!       <!-- Media RSS and iTunes elements, see `media` -->
 //!     </item>
 //!   </channel>
 //! </rss>
 //! ```
+//!
+//! Relative links are resolved against the `xml:base` of `<rss>`, `<channel>` and
+//! `<item>`, then against [`ParseOptions::base_url`].
+//!
+//! Elements of other namespaces are renamed by [`qualify`](super::namespace::qualify), so
+//! `content:encoded` is read as `content-encoded` whatever the prefix its namespace is
+//! bound to.
+
+use std::iter;
 
 use chrono::{DateTime, FixedOffset, Utc};
 use quick_xml::DeError;
@@ -22,28 +47,49 @@ use regex::Regex;
 use serde_derive::{Deserialize, Serialize};
 
 use crate::{
-  blog::{Blog, Post},
+  blog::{Blog, Enclosure, Post},
   warn,
 };
 
 use super::{
-  limit_description,
+  authors, content, description,
+  error::FeedFormat,
+  markup,
+  media::{attachments, enclosure, ItunesImage, MediaContent, MediaGroup, Thumbnail},
+  non_empty, parse_w3c_date,
   traits::{BlogPost, WebFeed},
-  ParserError,
+  ParseOptions, ParserError,
 };
+use crate::text::{html_to_text, truncate};
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 #[serde(rename = "rss")]
 pub struct RssFeed {
+  #[serde(rename = "@base")]
+  pub xml_base: Option<String>,
+  #[serde(rename = "@lang")]
+  pub xml_lang: Option<String>,
   pub channel: Channel,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Channel {
+  #[serde(rename = "@base")]
+  pub xml_base: Option<String>,
+  #[serde(rename = "@lang")]
+  pub xml_lang: Option<String>,
   pub title: String,
+  pub link: Option<String>,
+  pub description: Option<String>,
+  pub language: Option<String>,
+  pub image: Option<Image>,
+  #[serde(rename = "itunes-image")]
+  pub itunes_image: Option<ItunesImage>,
   pub last_build_date: Option<String>,
   pub pub_date: Option<String>,
+  #[serde(rename = "dc-date")]
+  pub dc_date: Option<String>,
   #[serde(rename = "item", default)]
   pub items: Vec<RssPost>,
 }
@@ -52,30 +98,148 @@ pub struct Channel {
 #[serde(rename_all = "camelCase")]
 #[serde(rename = "item")]
 pub struct RssPost {
+  #[serde(rename = "@base")]
+  pub xml_base: Option<String>,
   // Link and title can be omitted, according to spec, provided that there is a description
   // https://www.rssboard.org/rss-specification#hrelementsOfLtitemgt
   pub title: Option<String>,
   pub link: Option<String>,
+  /// `feedburner:origLink`, the link that a `feedproxy` link redirects to.
+  #[serde(rename = "feedburner-origLink")]
+  pub orig_link: Option<String>,
   pub description: Option<String>,
+  /// `content:encoded`, see <https://web.resource.org/rss/1.0/modules/content/>.
+  #[serde(rename = "content-encoded")]
+  pub content_encoded: Option<String>,
   pub pub_date: Option<String>,
+  #[serde(rename = "category", default)]
+  pub categories: Vec<Category>,
+  pub guid: Option<Guid>,
+  pub author: Option<String>,
+  #[serde(rename = "dc-creator", default)]
+  pub dc_creators: Vec<String>,
+  #[serde(rename = "dc-date")]
+  pub dc_date: Option<String>,
+  pub comments: Option<String>,
+  #[serde(rename = "enclosure", default)]
+  pub enclosures: Vec<RssEnclosure>,
+  #[serde(rename = "media-content", default)]
+  pub media_contents: Vec<MediaContent>,
+  #[serde(rename = "media-thumbnail", default)]
+  pub media_thumbnails: Vec<Thumbnail>,
+  #[serde(rename = "media-group", default)]
+  pub media_groups: Vec<MediaGroup>,
+  #[serde(rename = "itunes-duration")]
+  pub itunes_duration: Option<String>,
+  #[serde(rename = "itunes-image")]
+  pub itunes_image: Option<ItunesImage>,
+}
+
+/// The `<guid>` of an item, which is also its link unless `isPermaLink` is `false`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Guid {
+  #[serde(rename = "$text", default)]
+  pub value: String,
+  #[serde(rename = "@isPermaLink")]
+  pub is_perma_link: Option<String>,
+}
+
+impl Guid {
+  /// Whether the guid is the link of the item.
+  pub fn is_perma_link(&self) -> bool {
+    self
+      .is_perma_link
+      .as_deref()
+      .map_or(true, |value| !value.trim().eq_ignore_ascii_case("false"))
+  }
+}
+
+/// The `<enclosure>` of an item.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct RssEnclosure {
+  #[serde(rename = "@url")]
+  pub url: Option<String>,
+  #[serde(rename = "@length")]
+  pub length: Option<String>,
+  #[serde(rename = "@type")]
+  pub kind: Option<String>,
+}
+
+impl RssEnclosure {
+  /// The enclosure, `None` if it has no URL.
+  fn to_enclosure(&self) -> Option<Enclosure> {
+    enclosure(
+      self.url.as_deref()?,
+      self.kind.as_deref(),
+      self.length.as_deref(),
+    )
+  }
+}
+
+/// The `<image>` of a channel, only its `url` is kept.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Image {
+  pub url: Option<String>,
+}
+
+/// The `<category>` of an item, its `domain` is ignored.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Category {
+  #[serde(rename = "$text", default)]
+  pub value: String,
 }
 
 impl WebFeed for Result<RssFeed, DeError> {
-  fn into_blog(self) -> Result<Blog, ParserError> {
-    let feed = self?;
+  fn into_blog(self, options: &ParseOptions) -> Result<Blog, ParserError> {
+    let feed = self.map_err(|e| ParserError::deserialize(FeedFormat::Rss, e))?;
+    let options = options
+      .with_xml_base(feed.xml_base.as_deref())
+      .with_xml_base(feed.channel.xml_base.as_deref());
     let title = feed.channel.title;
-    let site_last_build_date = feed.channel.pub_date;
+    let link = non_empty(feed.channel.link.as_deref()).map(|link| options.resolve(&link));
+    let description = feed
+      .channel
+      .description
+      .as_deref()
+      .map(html_to_text)
+      .filter(|description| !description.is_empty());
+    let icon = non_empty(
+      feed
+        .channel
+        .image
+        .as_ref()
+        .and_then(|image| image.url.as_deref())
+        .or_else(|| {
+          feed
+            .channel
+            .itunes_image
+            .as_ref()
+            .and_then(|image| image.href.as_deref())
+        }),
+    )
+    .map(|icon| options.resolve(&icon));
+    let language = non_empty(
+      feed
+        .channel
+        .language
+        .as_deref()
+        .or(feed.channel.xml_lang.as_deref())
+        .or(feed.xml_lang.as_deref()),
+    );
+    let site_last_build_date = feed.channel.pub_date.or(feed.channel.dc_date);
     let items = feed.channel.items;
-    let last_post_build_date = items.first().and_then(|x| x.clone().pub_date);
+    let last_post_build_date = items
+      .first()
+      .and_then(|x| x.pub_date.clone().or_else(|| x.dc_date.clone()));
 
     let last_build_date = site_last_build_date
       .or(last_post_build_date)
-      .ok_or_else(|| ParserError::Parse("Date not found.".to_owned()))?;
+      .ok_or_else(|| ParserError::parse(FeedFormat::Rss, "Date not found."))?;
 
     let posts: Vec<Post> = items
       .iter()
       // TODO Turn this into a method
-      .filter_map(|x| match x.clone().into_post() {
+      .filter_map(|x| match x.clone().into_post(&options) {
         Ok(post) => Some(post),
         Err(e) => {
           warn!(
@@ -97,25 +261,76 @@ impl WebFeed for Result<RssFeed, DeError> {
       title,
       most_recent_pub_date: last_build_date.with_timezone(&Utc),
       posts,
+      link,
+      description,
+      icon,
+      language,
+      feed_url: None,
     })
   }
 }
 
 impl BlogPost for RssPost {
-  fn into_post(self) -> Result<Post, ParserError> {
-    let Some(link) = self.link else {
-      return Err(ParserError::Parse("No link in post".to_string()));
+  fn into_post(self, options: &ParseOptions) -> Result<Post, ParserError> {
+    let options = options.with_xml_base(self.xml_base.as_deref());
+    let id = self
+      .guid
+      .as_ref()
+      .map(|guid| guid.value.trim().to_owned())
+      .filter(|id| !id.is_empty());
+    let permalink = self
+      .guid
+      .as_ref()
+      .is_some_and(Guid::is_perma_link)
+      .then(|| id.clone())
+      .flatten();
+    let orig_link = self.orig_link.filter(|link| !link.trim().is_empty());
+    let Some(link) = orig_link.or(self.link).or(permalink) else {
+      return Err(ParserError::parse(FeedFormat::Rss, "No link in post"));
     };
+    let link = options.resolve(&link);
+
+    let groups = iter::once(MediaGroup {
+      contents: self.media_contents,
+      thumbnails: self.media_thumbnails,
+      description: None,
+    })
+    .chain(self.media_groups)
+    .collect::<Vec<_>>();
 
-    let (title, description) = match (
-      self.title,
-      self.description.map(|desc| limit_description(&desc, 200)),
-    ) {
-      (Some(link), description) => (link, description),
+    let description = description(
+      &[
+        self.description.as_deref(),
+        self.content_encoded.as_deref(),
+        groups.iter().find_map(|group| group.description.as_deref()),
+      ],
+      &options,
+    );
+    let content = content(
+      self
+        .content_encoded
+        .as_deref()
+        .or_else(|| markup(self.description.as_deref())),
+      &options,
+    );
+    let (enclosures, thumbnail) = attachments(
+      self
+        .enclosures
+        .iter()
+        .filter_map(RssEnclosure::to_enclosure)
+        .collect(),
+      &groups,
+      self.itunes_duration.as_deref(),
+      self.itunes_image.as_ref(),
+      &options,
+    );
+
+    let (title, description) = match (self.title, description) {
+      (Some(title), description) => (title, description),
       (None, None) => (link.clone(), None),
       (None, Some(description)) => {
-        if description.len() > 50 {
-          (limit_description(&description, 50), Some(description))
+        if description.chars().count() > 50 {
+          (truncate(&description, 50), Some(description))
         } else {
           (description, None)
         }
@@ -124,7 +339,8 @@ impl BlogPost for RssPost {
 
     let pub_date = self
       .pub_date
-      .ok_or_else(|| ParserError::Parse("Date not found.".to_owned()))?;
+      .or(self.dc_date)
+      .ok_or_else(|| ParserError::parse(FeedFormat::Rss, "Date not found."))?;
 
     let last_build_date = parse_date_helper(&pub_date)?;
 
@@ -132,19 +348,56 @@ impl BlogPost for RssPost {
       title,
       link,
       description,
+      content,
       pub_date: last_build_date.with_timezone(&Utc),
+      categories: self
+        .categories
+        .into_iter()
+        .map(|category| category.value.trim().to_owned())
+        .filter(|category| !category.is_empty())
+        .collect(),
+      authors: authors(
+        self
+          .author
+          .as_deref()
+          .map(author_name)
+          .into_iter()
+          .chain(self.dc_creators.iter().map(String::as_str))
+          .collect(),
+      ),
+      comments: self
+        .comments
+        .filter(|comments| !comments.trim().is_empty())
+        .map(|comments| options.resolve(&comments)),
+      id,
+      feeds: Vec::new(),
+      enclosures,
+      thumbnail,
     })
   }
 }
 
+/// The name of an RSS `author`, which is an email address optionally followed by the name
+/// in parentheses.
+fn author_name(author: &str) -> &str {
+  author
+    .trim()
+    .strip_suffix(')')
+    .and_then(|author| author.split_once('('))
+    .map_or(author, |(_, name)| name)
+}
+
 /// Helper method that first tries to parse a date using [`DateTime::parse_from_rfc2822`]
-/// and if that fails, it tries with [`parse_from_rfc822`].
+/// and if that fails, it tries with [`parse_from_rfc822`] and then as a W3C date, which is
+/// the format of `dc:date`.
 fn parse_date_helper(date: &str) -> Result<DateTime<FixedOffset>, ParserError> {
   if date.is_empty() {
     return Err(ParserError::empty_date_error());
   }
 
-  DateTime::parse_from_rfc2822(date).or_else(|_| parse_from_rfc822(date))
+  DateTime::parse_from_rfc2822(date)
+    .or_else(|_| parse_from_rfc822(date))
+    .or_else(|e| parse_w3c_date(date).map_err(|_e| e))
 }
 
 /// Tries to parse [`RFC822`](https://www.w3.org/Protocols/rfc822/#z28). This is a very much *not*
@@ -1,5 +1,14 @@
-use reqwest::Client;
+use rss2email_lib::fetch::{Fetcher, ReplayFetcher};
 
-pub fn create_client() -> Client {
-  Client::new()
+/// The responses the tests are run against.
+pub const RECORDINGS: &str = "test-data/recordings";
+
+/// Serves the responses saved under [`RECORDINGS`].
+pub fn fetcher() -> Box<dyn Fetcher> {
+  Box::new(ReplayFetcher::new(RECORDINGS))
+}
+
+/// Requests `url` from [`fetcher`], failing if it is not a web feed.
+pub async fn get_feed(url: &str) -> Result<String, rss2email_lib::Error> {
+  fetcher().fetch(url).await?.into_feed(url)
 }
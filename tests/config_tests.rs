@@ -0,0 +1,252 @@
+use rss2email_lib::{
+  config::{is_email_address, split_addresses, Config, ConfigFile, InvalidVar},
+  parse_feeds,
+};
+
+#[test]
+fn test_parse_feeds() {
+  let feeds =
+    "https://a.org/feed # a comment\n\n# https://b.org/feed\nhttps://c.org/rss;https://d.org/atom";
+
+  assert_eq!(
+    parse_feeds(feeds),
+    vec![
+      "https://a.org/feed",
+      "https://c.org/rss",
+      "https://d.org/atom"
+    ]
+  );
+}
+
+#[test]
+fn test_split_addresses() {
+  assert_eq!(
+    split_addresses(" a@example.org,b@example.org ,,"),
+    vec!["a@example.org", "b@example.org"]
+  );
+}
+
+#[test]
+fn test_subject_post_count() {
+  let config = Config {
+    subject: Some("rss2email - $POST_COUNT new posts".to_owned()),
+    ..Config::default()
+  };
+
+  assert_eq!(
+    config.subject(3).as_deref(),
+    Some("rss2email - 3 new posts")
+  );
+  assert_eq!(Config::default().subject(3), None);
+}
+
+#[test]
+fn test_feeds_from_file() {
+  let config = Config {
+    feeds_file: "test-data/feeds.txt".into(),
+    ..Config::default()
+  };
+
+  assert!(config.feeds().is_err());
+
+  let config = Config {
+    feeds: Some(parse_feeds("https://a.org/feed;https://b.org/feed")),
+    ..config
+  };
+
+  assert_eq!(
+    config.feeds().expect("FEEDS is set"),
+    vec!["https://a.org/feed", "https://b.org/feed"]
+  );
+}
+
+#[test]
+fn test_is_email_address() {
+  assert!(is_email_address("a@example.org"));
+  assert!(is_email_address("Rss2Email <a@example.org>"));
+  assert!(!is_email_address("a@example"));
+  assert!(!is_email_address("@example.org"));
+  assert!(!is_email_address("a b@example.org"));
+  assert!(!is_email_address("example.org"));
+}
+
+#[test]
+fn test_validate_reports_every_error() {
+  let config = Config {
+    feeds: Some(parse_feeds("https://a.org/feed")),
+    recipient_addresses: vec!["a@example.org".to_owned(), "nope".to_owned()],
+    email_provider: "carrier-pigeon".to_owned(),
+    theme: Some("sepia".to_owned()),
+    html_template: Some("test-data/templates/missing.html".into()),
+    invalid_vars: vec![InvalidVar {
+      name: "DAYS",
+      value: "abc".to_owned(),
+      expected: "a whole number of days",
+    }],
+    ..Config::default()
+  };
+
+  let validation = config.validate();
+
+  assert_eq!(
+    validation.errors,
+    vec![
+      "DAYS is set to 'abc', expected a whole number of days",
+      "Unknown theme 'sepia', expected one of light, dark or compact",
+      "HTML_TEMPLATE is set to test-data/templates/missing.html, which is not a file",
+      "EMAIL_ADDRESS must be set",
+      "RECIPIENT_ADDRESSES contains 'nope', which is not a valid email address",
+      "SUBJECT must be set",
+      "EMAIL is set to 'carrier-pigeon', expected one of SENDGRID, RESEND, MAIL_COMMAND",
+    ]
+  );
+  assert!(!validation.is_valid());
+}
+
+#[test]
+fn test_validate_warnings() {
+  let config = Config {
+    days: 0,
+    feeds: Some(parse_feeds("https://a.org/feed;ftp://b.org/feed")),
+    sender_address: Some("rss2email@example.org".to_owned()),
+    recipient_addresses: vec!["a@example.org".to_owned()],
+    subject: Some("rss2email".to_owned()),
+    email_provider: "mail_command".to_owned(),
+    ..Config::default()
+  };
+
+  let validation = config.validate();
+
+  assert!(validation.is_valid(), "{validation}");
+  assert_eq!(
+    validation.warnings,
+    vec![
+      "DAYS is 0, no posts will be included",
+      "'ftp://b.org/feed' is not an http(s) URL",
+    ]
+  );
+}
+
+#[test]
+fn test_validate_invalid_template() {
+  let config = Config {
+    feeds: Some(parse_feeds("https://a.org/feed")),
+    text_template: Some("test-data/templates/invalid.txt".into()),
+    ..Config::default()
+  };
+
+  let validation = config.validate_templates();
+
+  assert_eq!(validation.errors.len(), 1);
+  assert!(validation.errors[0].starts_with("Invalid template test-data/templates/invalid.txt"));
+}
+
+const CONFIG_FILE: &str = r#"
+[defaults]
+sender_address = "rss2email@example.org"
+subject = "rss2email"
+
+[categories]
+news = ["https://a.org/news", "https://b.org/news"]
+blogs = ["https://c.org/blog", "https://a.org/news"]
+
+[profiles.daily]
+categories = ["news"]
+days = 1
+recipient_addresses = ["me@example.org"]
+
+[profiles.weekly]
+categories = ["blogs"]
+feeds = ["https://d.org/feed"]
+recipient_addresses = ["me@example.org", "team@example.org"]
+subject = "This week"
+theme = "compact"
+"#;
+
+#[test]
+fn test_config_file_profiles() {
+  let file = ConfigFile::parse(CONFIG_FILE).expect("Valid config file");
+  let base = Config::default();
+
+  let daily = file.profile(&base, "daily").expect("daily is defined");
+  assert_eq!(daily.profile.as_deref(), Some("daily"));
+  assert_eq!(daily.days, 1);
+  assert_eq!(
+    daily.feeds,
+    Some(parse_feeds("https://a.org/news;https://b.org/news"))
+  );
+  assert_eq!(daily.recipient_addresses, vec!["me@example.org"]);
+  assert_eq!(daily.subject.as_deref(), Some("rss2email"));
+  assert_eq!(
+    daily.sender_address.as_deref(),
+    Some("rss2email@example.org")
+  );
+
+  let weekly = file.profile(&base, "weekly").expect("weekly is defined");
+  assert_eq!(weekly.days, base.days);
+  assert_eq!(
+    weekly.feeds,
+    Some(parse_feeds(
+      "https://c.org/blog;https://a.org/news;https://d.org/feed"
+    ))
+  );
+  assert_eq!(weekly.subject.as_deref(), Some("This week"));
+  assert_eq!(weekly.theme.as_deref(), Some("compact"));
+
+  let configs = file.configs(&base).expect("Every profile is valid");
+  assert_eq!(configs, vec![daily, weekly]);
+}
+
+#[test]
+fn test_config_file_without_profiles() {
+  let file = ConfigFile::parse(
+    r#"
+    [defaults]
+    days = 3
+
+    [categories]
+    news = ["https://a.org/news"]
+    blogs = ["https://c.org/blog", "https://a.org/news"]
+    "#,
+  )
+  .expect("Valid config file");
+
+  let base = Config {
+    invalid_vars: vec![InvalidVar {
+      name: "DAYS",
+      value: "abc".to_owned(),
+      expected: "a whole number of days",
+    }],
+    ..Config::default()
+  };
+
+  let configs = file.configs(&base).expect("The defaults are valid");
+
+  assert_eq!(configs.len(), 1);
+  assert_eq!(configs[0].profile, None);
+  assert_eq!(configs[0].days, 3);
+  assert!(configs[0].invalid_vars.is_empty());
+  assert_eq!(
+    configs[0].feeds,
+    Some(parse_feeds("https://c.org/blog;https://a.org/news"))
+  );
+}
+
+#[test]
+fn test_config_file_errors() {
+  let file = ConfigFile::parse(CONFIG_FILE).expect("Valid config file");
+
+  assert!(file.profile(&Config::default(), "monthly").is_err());
+
+  let file = ConfigFile::parse(
+    r#"
+    [profiles.daily]
+    categories = ["sports"]
+    "#,
+  )
+  .expect("Valid config file");
+
+  assert!(file.configs(&Config::default()).is_err());
+
+  assert!(ConfigFile::parse("[defaults]\nrecipients = [\"me@example.org\"]").is_err());
+}
@@ -1,26 +1,83 @@
-use std::env;
+use std::{env, sync::Mutex};
 
-use rss2email_lib::email::email_provider::{EmailProvider, EmailProviders};
+use rss2email_lib::email::{
+  email_provider::{EmailProvider, EmailProviders},
+  message::EmailMessage,
+};
+
+/// Held by the tests that change `API_KEY`, which is shared by the whole test binary.
+static ENV_LOCK: Mutex<()> = Mutex::new(());
 
 #[test]
 fn load_sendgrid() {
+  let _env = ENV_LOCK
+    .lock()
+    .unwrap_or_else(std::sync::PoisonError::into_inner);
   env::remove_var("API_KEY");
 
+  let message = EmailMessage::new("<p>email</p>", "email");
   let sendgrid =
     EmailProviders::try_from("SENDGRID".to_owned()).expect("The Sendgrid provider is defined");
 
   assert!(
     sendgrid
-      .send_email("address", vec!["person"], "subject", "email")
+      .send_email("address", vec!["person"], "subject", &message)
       .is_err(),
     "Mandatory API_KEY should cause an Err()"
   );
   env::set_var("API_KEY", "ASD");
   assert!(
     sendgrid
-      .send_email("address", vec!["person"], "subject", "email")
+      .send_email("address", vec!["person"], "subject", &message)
       .is_err(),
     "Failed to load proper Email Provider SendGrid"
   );
   env::remove_var("API_KEY");
 }
+
+#[test]
+fn multipart_body_contains_both_parts() {
+  let message = EmailMessage::new("<p>email</p>", "email");
+  let boundary = message.boundary();
+  let body = message.multipart_body(&boundary);
+
+  let text_idx = body
+    .find("Content-Type: text/plain; charset=utf-8")
+    .expect("Plain-text part");
+  let html_idx = body
+    .find("Content-Type: text/html; charset=utf-8")
+    .expect("HTML part");
+
+  assert!(text_idx < html_idx, "Plain-text part should come first");
+  assert!(body.contains("\r\n\r\nemail\r\n"));
+  assert!(body.contains("<p>email</p>"));
+  assert!(body.ends_with(&format!("--{boundary}--\r\n")));
+}
+
+#[tokio::test]
+async fn send_email_async_without_api_key() {
+  let message = EmailMessage::new("<p>email</p>", "email");
+
+  // The key is read when the provider is created, so the lock is not needed while sending.
+  let providers = {
+    let _env = ENV_LOCK
+      .lock()
+      .unwrap_or_else(std::sync::PoisonError::into_inner);
+    env::remove_var("API_KEY");
+    ["SENDGRID", "RESEND"].map(|name| {
+      let provider = EmailProviders::try_from(name.to_owned()).expect("The provider is defined");
+      (name, provider)
+    })
+  };
+
+  for (name, provider) in providers {
+    let result = provider
+      .send_email_async("address", vec!["person"], "subject", &message)
+      .await;
+
+    assert!(
+      result.is_err(),
+      "{name} should fail without a valid API_KEY"
+    );
+  }
+}
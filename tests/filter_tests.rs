@@ -0,0 +1,153 @@
+use rss2email_lib::{
+  config::{Config, ConfigFile},
+  filter::{Condition, FilterRule, Pattern, PostFilter},
+  pipeline::Filter,
+  Blog, Post,
+};
+
+const CONFIG_FILE: &str = r#"
+  [[defaults.filters]]
+  name = "no ads"
+  exclude = [{ title = "Sponsored" }, { link = "/[?&]ref=ad/" }]
+
+  [[profiles.news.filters]]
+  feeds = ["https://news.example"]
+  include = [
+    { any = [{ title = "rust" }, { category = "rust" }] },
+    { all = [{ description = "go" }, { not = { title = "/(?i)google/" } }] },
+  ]
+
+  [profiles.blogs]
+  days = 3
+"#;
+
+fn post(title: &str, categories: &[&str], description: Option<&str>) -> Post {
+  Post {
+    title: title.to_owned(),
+    link: format!("https://example.org/{}", title.replace(' ', "-")),
+    description: description.map(ToOwned::to_owned),
+    categories: categories.iter().map(ToString::to_string).collect(),
+    ..Post::default()
+  }
+}
+
+fn blog() -> Blog {
+  Blog {
+    title: "Blog".to_owned(),
+    posts: vec![
+      post("Rust 2.0", &[], None),
+      post("Weekly update", &["Rust"], None),
+      post("Sponsored: Rust hosting", &[], None),
+      post("Google Go", &[], Some("All about go")),
+      post("Go generics", &[], Some("More go")),
+      post("Python news", &["python"], Some("Nothing about it")),
+    ],
+    ..Blog::default()
+  }
+}
+
+fn titles(blog: Option<Blog>) -> Vec<String> {
+  blog
+    .expect("Filters keep the blog")
+    .posts
+    .into_iter()
+    .map(|post| post.title)
+    .collect()
+}
+
+#[test]
+fn test_filters_from_config_file() {
+  let file = ConfigFile::parse(CONFIG_FILE).expect("Valid config file");
+  let base = Config::default();
+
+  let news = file.profile(&base, "news").expect("Valid profile");
+  assert_eq!(news.filters.len(), 2);
+  assert_eq!(news.filters[0].name.as_deref(), Some("no ads"));
+  assert_eq!(news.filters[1].feeds, ["https://news.example"]);
+
+  let blogs = file.profile(&base, "blogs").expect("Valid profile");
+  assert_eq!(blogs.filters.len(), 1);
+}
+
+#[test]
+fn test_post_filter() {
+  let file = ConfigFile::parse(CONFIG_FILE).expect("Valid config file");
+  let config = file
+    .profile(&Config::default(), "news")
+    .expect("Valid profile");
+  let filter = PostFilter::new(config.filters);
+
+  assert_eq!(
+    titles(filter.apply("https://news.example", blog())),
+    ["Rust 2.0", "Weekly update", "Go generics"]
+  );
+  assert_eq!(
+    filter.dropped(),
+    [("no ads".to_owned(), 1), ("#2".to_owned(), 2)]
+  );
+
+  // The second rule only applies to the news feed.
+  assert_eq!(
+    titles(filter.apply("https://blog.example", blog())).len(),
+    5
+  );
+  assert_eq!(
+    filter.dropped(),
+    [("no ads".to_owned(), 2), ("#2".to_owned(), 2)]
+  );
+
+  filter.finish();
+  assert_eq!(
+    filter.dropped(),
+    [("no ads".to_owned(), 0), ("#2".to_owned(), 0)]
+  );
+}
+
+#[test]
+fn test_patterns() {
+  let keyword = Pattern::try_from("RUST".to_owned()).expect("Valid keyword");
+  assert!(keyword.matches("Why rust?"));
+  assert!(!keyword.matches("Why Go?"));
+
+  let regex = Pattern::try_from(r"/^\[\w+\]/".to_owned()).expect("Valid regex");
+  assert!(regex.matches("[ad] Buy this"));
+  assert!(!regex.matches("Buy this [ad]"));
+
+  assert!(Pattern::try_from("/(unclosed/".to_owned()).is_err());
+  assert!(Pattern::try_from(" ".to_owned()).is_err());
+  assert!(ConfigFile::parse("[[defaults.filters]]\nexclude = [{ title = \"/(/\" }]").is_err());
+  assert!(ConfigFile::parse("[[defaults.filters]]\nexclude = [{ guid = \"me\" }]").is_err());
+}
+
+#[test]
+fn test_conditions() {
+  let post = post("Rust 2.0", &["release"], None);
+  let title =
+    |pattern: &str| Condition::Title(Pattern::try_from(pattern.to_owned()).expect("Valid"));
+
+  assert!(Condition::Any(vec![title("go"), title("rust")]).matches(&post));
+  assert!(!Condition::All(vec![title("go"), title("rust")]).matches(&post));
+  assert!(Condition::Not(Box::new(title("go"))).matches(&post));
+  assert!(
+    !Condition::Description(Pattern::try_from("rust".to_owned()).expect("Valid")).matches(&post)
+  );
+
+  let rule = FilterRule {
+    exclude: vec![Condition::Category(
+      Pattern::try_from("Release".to_owned()).expect("Valid"),
+    )],
+    ..FilterRule::default()
+  };
+  assert!(rule.applies_to("https://any.example"));
+  assert!(!rule.keeps(&post));
+
+  let post = Post {
+    authors: vec!["Jane Doe".to_owned(), "John Smith".to_owned()],
+    ..post
+  };
+  let author =
+    |pattern: &str| Condition::Author(Pattern::try_from(pattern.to_owned()).expect("Valid"));
+  assert!(author("smith").matches(&post));
+  assert!(author("/^Jane/").matches(&post));
+  assert!(!author("roe").matches(&post));
+}
@@ -1,16 +1,16 @@
-use rss2email_lib::{get_page_async, Error};
+use rss2email_lib::{
+  fetch::{Fetcher, RecordingFetcher, ReplayFetcher},
+  pipeline::Pipeline,
+  Error,
+};
 use tokio::test;
 
-use crate::common::create_client;
+use crate::common::{get_feed, RECORDINGS};
 mod common;
 
 #[test]
 async fn test_download_plain_xml() {
-  let payload = get_page_async(
-    "https://antoniosbarotsis.github.io/index.xml",
-    &create_client(),
-  )
-  .await;
+  let payload = get_feed("https://example.org/index.xml").await;
   let content = payload.expect("Downloaded content");
   assert!(content.starts_with("<?xml"));
   assert!(content.contains("<rss"));
@@ -19,31 +19,36 @@ async fn test_download_plain_xml() {
 
 #[test]
 async fn test_download_xml_for_rss() {
-  let payload = get_page_async("https://github.blog/feed", &create_client()).await;
+  let payload = get_feed("https://example.org/feed").await;
   let content = payload.expect("Downloaded content");
   assert!(content.starts_with("<?xml"));
 }
 
 #[test]
 async fn test_download_invalid_page() {
-  let payload = get_page_async(
-    "https://antoniosbarotsis.github.io/ordex.pkxml",
-    &create_client(),
-  )
-  .await;
-  assert!(payload.is_err());
+  let payload = get_feed("https://example.org/missing.xml").await;
+  let error = payload.expect_err("Should error");
+  assert!(
+    matches!(error, Error::HttpStatus { status: 404, .. }),
+    "Unexpected error {error:?}"
+  );
+  assert_eq!(error.url(), Some("https://example.org/missing.xml"));
 }
 
 #[test]
 async fn test_download_with_text() {
   // Text should not be confused with xml: here, we received html for a markdown
-  let url = "https://raw.githubusercontent.com/AntoniosBarotsis/Rss2Email/cc5b2bee846f9dab8f5787dfcb9a01d963321630/README.md";
-  let payload = get_page_async(url, &create_client()).await;
+  let url = "https://example.org/README.md";
+  let payload = get_feed(url).await;
   assert!(payload.is_err());
   let error = payload.expect_err("Should error");
-  if let Error::Generic(message) = error {
-    assert!(message.contains("Invalid content"));
-    assert!(message.contains(url));
+  if let Error::UnsupportedContentType {
+    url: ref error_url,
+    content_type: Some(_),
+  } = error
+  {
+    assert_eq!(error_url, url);
+    assert!(error.to_string().contains("Invalid content"));
   } else {
     panic!("Unexpected error {error:?}");
   }
@@ -51,14 +56,17 @@ async fn test_download_with_text() {
 
 #[test]
 async fn test_download_with_an_image() {
-  // Using an URL to a specific sha of this github repo to make sure the target remains
-  let url = "https://github.com/AntoniosBarotsis/Rss2Email/raw/cc5b2bee846f9dab8f5787dfcb9a01d963321630/assets/res.jpg";
-  let payload = get_page_async(url, &create_client()).await;
+  let url = "https://example.org/image.jpg";
+  let payload = get_feed(url).await;
   assert!(payload.is_err());
   let error = payload.expect_err("Should error");
-  if let Error::Generic(message) = error {
-    assert!(message.contains("Invalid content"));
-    assert!(message.contains(url));
+  if let Error::UnsupportedContentType {
+    url: ref error_url,
+    content_type: Some(_),
+  } = error
+  {
+    assert_eq!(error_url, url);
+    assert!(error.to_string().contains("Invalid content"));
   } else {
     panic!("Unexpected error {error:?}");
   }
@@ -67,12 +75,98 @@ async fn test_download_with_an_image() {
 #[test]
 async fn test_download_multiple_pages() {
   // Sanity test to check that the process is not a one-shot operation
-  let urls = vec![
-    "https://blog.rust-lang.org/feed.xml",
-    "https://github.blog/feed",
-  ];
+  let urls = vec!["https://example.org/atom.xml", "https://example.org/feed"];
   for url in urls {
-    let result = get_page_async(url, &create_client()).await;
+    let result = get_feed(url).await;
     assert!(result.is_ok(), "Error for {url}: {result:?}");
   }
 }
+
+#[test]
+async fn test_record_and_replay() {
+  let dir = std::env::temp_dir().join(format!("rss2email-recordings-{}", std::process::id()));
+  let url = "https://example.org/feed";
+  let image_url = "https://example.org/image.jpg";
+
+  let recorder = RecordingFetcher::new(ReplayFetcher::new(RECORDINGS), &dir);
+  let response = recorder.fetch(url).await.expect("Recorded response");
+  assert_eq!(response.status, 200);
+  assert_eq!(
+    response.header("Content-Type"),
+    Some("application/rss+xml; charset=UTF-8")
+  );
+  // Binary bodies are kept byte for byte.
+  let image = recorder.fetch(image_url).await.expect("Recorded response");
+  assert!(std::str::from_utf8(&image.body).is_err());
+
+  let replayer = ReplayFetcher::new(&dir);
+  let mut urls = replayer.urls().expect("Readable recordings");
+  urls.sort();
+  assert_eq!(urls, [url, image_url]);
+  let replayed_response = replayer.fetch(url).await.expect("Replayed response");
+  assert_eq!(replayed_response, response);
+  let replayed_image = replayer.fetch(image_url).await.expect("Replayed response");
+  assert_eq!(replayed_image, image);
+
+  // URLs that only differ by punctuation are not saved over each other.
+  let missing = replayer.fetch("https://example.org/feed/").await;
+  assert!(missing.is_err());
+
+  std::fs::remove_dir_all(dir).expect("Removable temp dir");
+}
+
+#[test]
+async fn test_pipeline_with_recordings() {
+  let urls = ReplayFetcher::new(RECORDINGS)
+    .urls()
+    .expect("Readable recordings");
+
+  let digest = Pipeline::new(urls.clone())
+    .with_fetcher(ReplayFetcher::new(RECORDINGS))
+    .fetch()
+    .await
+    .expect("The feeds are listed");
+
+  assert_eq!(digest.blogs.len(), 3);
+  assert_eq!(digest.failed_feeds.len(), urls.len() - 3);
+}
+
+#[test]
+async fn test_parse_error_context() {
+  let error = rss2email_lib::xml::parse_web_feed("<html></html>")
+    .map_err(Error::from)
+    .expect_err("Should error")
+    .with_url("https://example.org/feed.xml");
+
+  // Parse errors keep the error of the deserializer: error -> both formats -> RSS -> XML.
+  let source = std::error::Error::source(&error).expect("Parse errors have a source");
+  let rss = source.source().expect("Unknown formats have a source");
+  assert!(rss.to_string().starts_with("Parse error (RSS)"));
+  assert!(rss.source().is_some());
+
+  assert_eq!(error.url(), Some("https://example.org/feed.xml"));
+  // Attaching a URL never replaces the one that is already there.
+  assert_eq!(
+    error.with_url("https://example.org").url(),
+    Some("https://example.org/feed.xml")
+  );
+
+  let error = Error::from(rss2email_lib::xml::error::ParserError::empty_date_error());
+  assert!(
+    matches!(error, Error::Date { .. }),
+    "Unexpected error {error:?}"
+  );
+  let source = std::error::Error::source(&error).expect("Date errors have a source");
+  assert_eq!(source.to_string(), "Date was empty");
+
+  let error = rss2email_lib::xml::parse_web_feed(
+    "<rss><channel><title>Blog</title><item><title>Post</title>\
+     <link>https://example.org/post</link><pubDate>Yesterday</pubDate></item></channel></rss>",
+  )
+  .map_err(Error::from)
+  .expect_err("Should error");
+  assert!(
+    matches!(error, Error::Date { .. }),
+    "Unexpected error {error:?}"
+  );
+}
@@ -0,0 +1,439 @@
+use rss2email_lib::{
+  config::{Config, ConfigFile},
+  link::{canonical_link, matches_param, LinkPattern, LinkRewriter, RewriteRule, TRACKING_PARAMS},
+  parse_feeds,
+  pipeline::Filter,
+  Blog, Digest, Post,
+};
+
+const CONFIG_FILE: &str = r#"
+  [defaults]
+  strip_params = ["default", "ref"]
+
+  [[defaults.rewrites]]
+  from = "reddit.com"
+  to = "old.reddit.com"
+
+  [profiles.video]
+  unwrap_redirects = false
+
+  [[profiles.video.rewrites]]
+  from = "/^https://(?:www\\.)?youtube\\.com/(.*)$/"
+  to = "https://invidious.example/$1"
+"#;
+
+fn post(title: &str, link: &str, id: Option<&str>, feed: &str) -> Post {
+  Post {
+    title: title.to_owned(),
+    link: link.to_owned(),
+    id: id.map(ToOwned::to_owned),
+    feeds: vec![feed.to_owned()],
+    ..Post::default()
+  }
+}
+
+fn blog(title: &str, posts: Vec<Post>) -> Blog {
+  Blog {
+    title: title.to_owned(),
+    posts,
+    ..Blog::default()
+  }
+}
+
+#[test]
+fn test_canonical_link() {
+  assert_eq!(
+    canonical_link(" http://WWW.Example.org/post/?utm_source=rss&id=3&fbclid=x#comments "),
+    "https://example.org/post?id=3"
+  );
+  assert_eq!(
+    canonical_link("https://example.org:443/post/"),
+    "https://example.org/post"
+  );
+  assert_eq!(
+    canonical_link("https://example.org/?UTM_Medium=feed"),
+    "https://example.org/"
+  );
+  assert_eq!(canonical_link(" /relative/post "), "/relative/post");
+  assert_eq!(
+    canonical_link("mailto:me@example.org"),
+    "mailto:me@example.org"
+  );
+}
+
+#[test]
+fn test_matches_param() {
+  assert!(matches_param("utm_campaign", &TRACKING_PARAMS));
+  assert!(matches_param("GCLID", &TRACKING_PARAMS));
+  assert!(!matches_param("utm", &TRACKING_PARAMS));
+  assert!(!matches_param("page", &TRACKING_PARAMS));
+  assert!(matches_param("ref", &["ref*"]));
+}
+
+#[test]
+fn test_parse_feeds_duplicates() {
+  // Only exact duplicates are removed, the server may treat the others differently.
+  assert_eq!(
+    parse_feeds("https://a.org/feed\nhttp://www.a.org/feed/\nhttps://a.org/feed"),
+    ["https://a.org/feed", "http://www.a.org/feed/"]
+  );
+}
+
+#[test]
+fn test_dedupe() {
+  let mut digest = Digest {
+    blogs: vec![
+      blog(
+        "Planet",
+        vec![
+          post(
+            "Syndicated",
+            "http://www.a.org/post/?utm_source=planet",
+            None,
+            "https://planet.org/feed",
+          ),
+          post(
+            "Only here",
+            "https://planet.org/news",
+            None,
+            "https://planet.org/feed",
+          ),
+        ],
+      ),
+      blog(
+        "A",
+        vec![post(
+          "Original",
+          "https://a.org/post",
+          Some("1"),
+          "https://a.org/feed",
+        )],
+      ),
+      blog(
+        "Mirror",
+        vec![
+          post(
+            "Mirrored",
+            "https://mirror.org/b-post",
+            Some("tag:b.org,2024:1"),
+            "https://feeds.mirror.org/rss",
+          ),
+          post(
+            "Numeric id",
+            "https://mirror.org/other",
+            Some("1"),
+            "https://feeds.mirror.org/rss",
+          ),
+        ],
+      ),
+      blog(
+        "B",
+        vec![post(
+          "B post",
+          "https://b.org/post",
+          Some("tag:b.org,2024:1"),
+          "https://b.org/feed",
+        )],
+      ),
+    ],
+    ..Digest::default()
+  };
+
+  digest.dedupe();
+
+  let titles = digest
+    .blogs
+    .iter()
+    .map(|blog| {
+      (
+        blog.title.as_str(),
+        blog
+          .posts
+          .iter()
+          .map(|post| post.title.as_str())
+          .collect::<Vec<_>>(),
+      )
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(
+    titles,
+    [
+      ("Planet", vec!["Only here"]),
+      ("A", vec!["Original"]),
+      ("Mirror", vec!["Numeric id"]),
+      ("B", vec!["B post"]),
+    ]
+  );
+
+  assert_eq!(
+    digest.blogs[1].posts[0].feeds,
+    ["https://planet.org/feed", "https://a.org/feed"]
+  );
+  assert_eq!(
+    digest.blogs[3].posts[0].feeds,
+    ["https://feeds.mirror.org/rss", "https://b.org/feed"]
+  );
+}
+
+#[test]
+fn test_dedupe_through_other_posts() {
+  let date = |hours| {
+    chrono::DateTime::UNIX_EPOCH + chrono::Duration::try_hours(hours).expect("Valid duration")
+  };
+  let dated = |post: Post, hours| Post {
+    pub_date: date(hours),
+    ..post
+  };
+
+  // The first two only have different keys in common with the third one.
+  let mut digest = Digest {
+    blogs: vec![
+      blog(
+        "Planet",
+        vec![
+          dated(
+            post(
+              "By link",
+              "https://a.org/post",
+              None,
+              "https://planet.org/feed",
+            ),
+            3,
+          ),
+          dated(
+            post(
+              "Other",
+              "https://planet.org/news",
+              None,
+              "https://planet.org/feed",
+            ),
+            2,
+          ),
+        ],
+      ),
+      blog(
+        "Mirror",
+        vec![dated(
+          post(
+            "By id",
+            "https://mirror.org/a-post",
+            Some("tag:a.org,2024:1"),
+            "https://mirror.org/feed",
+          ),
+          1,
+        )],
+      ),
+      blog(
+        "A",
+        vec![dated(
+          post(
+            "Original",
+            "https://a.org/post",
+            Some("tag:a.org,2024:1"),
+            "https://a.org/feed",
+          ),
+          1,
+        )],
+      ),
+    ],
+    ..Digest::default()
+  };
+  digest.blogs[0].most_recent_pub_date = date(3);
+
+  digest.dedupe();
+
+  assert_eq!(digest.blogs.len(), 2);
+  assert_eq!(digest.blogs[0].posts.len(), 1);
+  assert_eq!(digest.blogs[0].most_recent_pub_date, date(2));
+  assert_eq!(
+    digest.blogs[1].posts[0].feeds,
+    [
+      "https://planet.org/feed",
+      "https://mirror.org/feed",
+      "https://a.org/feed"
+    ]
+  );
+}
+
+#[test]
+fn test_dedupe_within_a_feed() {
+  let feed = "https://a.org/feed";
+  let mut digest = Digest {
+    blogs: vec![
+      blog(
+        "A",
+        vec![
+          post("v1", "https://a.org/changelog#v1", None, feed),
+          post("v2", "https://a.org/changelog#v2", None, feed),
+          post("Home", "https://a.org/", None, feed),
+          post("Also home", "https://a.org/", None, feed),
+          post("Twice", "https://a.org/twice", Some("7"), feed),
+          post("Twice again", "https://a.org/twice", Some("7"), feed),
+        ],
+      ),
+      blog(
+        "Planet",
+        vec![post(
+          "v1 again",
+          "https://a.org/changelog#v1",
+          None,
+          "https://planet.org/feed",
+        )],
+      ),
+    ],
+    ..Digest::default()
+  };
+
+  digest.dedupe();
+
+  // Only the posts with the same id are merged within a feed, and the planet's copy is
+  // merged with one of the changelog posts.
+  let titles = digest.blogs[0]
+    .posts
+    .iter()
+    .map(|post| post.title.as_str())
+    .collect::<Vec<_>>();
+  assert_eq!(titles, ["v1", "v2", "Home", "Also home", "Twice"]);
+  assert_eq!(digest.blogs.len(), 1);
+  assert_eq!(
+    digest.blogs[0].posts[0].feeds,
+    [feed, "https://planet.org/feed"]
+  );
+}
+
+#[test]
+fn test_link_rewriter() {
+  let rewriter = LinkRewriter::new();
+
+  assert_eq!(
+    rewriter.rewrite("https://example.org/post?utm_source=rss&id=a%20b&fbclid=x#top"),
+    "https://example.org/post?id=a%20b#top"
+  );
+  assert_eq!(
+    rewriter.rewrite(
+      "https://l.facebook.com/l.php?u=https%3A%2F%2Fexample.org%2Fpost%3Futm_medium%3Dsocial&h=x"
+    ),
+    "https://example.org/post"
+  );
+  assert_eq!(
+    rewriter.rewrite("https://www.google.com/url?q=https://out.reddit.com/t3_x?url=https%253A%252F%252Fexample.org"),
+    "https://example.org/"
+  );
+  // Links that are left alone keep their original form.
+  assert_eq!(
+    rewriter.rewrite("HTTPS://Example.org"),
+    "HTTPS://Example.org"
+  );
+  assert_eq!(
+    rewriter.rewrite("https://l.facebook.com/l.php?u=javascript:alert(1)"),
+    "https://l.facebook.com/l.php?u=javascript:alert(1)"
+  );
+
+  let rewriter = LinkRewriter::new()
+    .with_unwrap_redirects(false)
+    .with_strip_params(vec!["ref".to_owned()]);
+  assert_eq!(
+    rewriter.rewrite("https://l.facebook.com/l.php?u=https%3A%2F%2Fexample.org&ref=x&utm_source=y"),
+    "https://l.facebook.com/l.php?u=https%3A%2F%2Fexample.org&utm_source=y"
+  );
+}
+
+#[test]
+fn test_rewrite_rules() {
+  let host = RewriteRule {
+    from: LinkPattern::try_from("Reddit.com".to_owned()).expect("Valid host"),
+    to: "old.reddit.com".to_owned(),
+  };
+  assert_eq!(
+    host.rewrite("https://www.reddit.com/r/rust/comments/x"),
+    Some("https://old.reddit.com/r/rust/comments/x".to_owned())
+  );
+  assert_eq!(host.rewrite("https://np.reddit.com/r/rust"), None);
+
+  let regex = RewriteRule {
+    from: LinkPattern::try_from(r"/^https://youtu\.be/(\w+)$/".to_owned()).expect("Valid regex"),
+    to: "https://invidious.example/watch?v=$1".to_owned(),
+  };
+  assert_eq!(
+    regex.rewrite("https://youtu.be/abc123"),
+    Some("https://invidious.example/watch?v=abc123".to_owned())
+  );
+  assert_eq!(regex.rewrite("https://youtube.com/watch?v=abc123"), None);
+
+  for to in [
+    "https://old.reddit.com",
+    "old.reddit.com:8080",
+    "old.reddit.com/r",
+    "",
+  ] {
+    let rule = RewriteRule {
+      from: LinkPattern::try_from("reddit.com".to_owned()).expect("Valid host"),
+      to: to.to_owned(),
+    };
+    assert!(rule.check().is_err(), "'{to}' is not a host");
+
+    let config = Config {
+      rewrites: vec![rule],
+      ..Config::default()
+    };
+    assert!(!config.validate_feeds().is_valid(), "'{to}' is reported");
+  }
+  assert!(regex.check().is_ok());
+  assert!(ConfigFile::parse(
+    r#"
+      [[defaults.rewrites]]
+      from = "reddit.com"
+      to = "https://old.reddit.com"
+    "#
+  )
+  .is_err());
+
+  assert!(LinkPattern::try_from("/(/".to_owned()).is_err());
+  assert!(LinkPattern::try_from("reddit.com/r".to_owned()).is_err());
+  assert!(LinkPattern::try_from(" ".to_owned()).is_err());
+}
+
+#[test]
+fn test_rewrites_from_config_file() {
+  let file = ConfigFile::parse(CONFIG_FILE).expect("Valid config file");
+  let config = file
+    .profile(&Config::default(), "video")
+    .expect("Valid profile");
+  assert_eq!(config.rewrites.len(), 2);
+  assert!(!config.unwrap_redirects);
+
+  let blog = Blog {
+    posts: vec![
+      post(
+        "Reddit",
+        "https://www.reddit.com/r/rust?ref=feed&utm_source=rss",
+        None,
+        "https://a.org",
+      ),
+      post(
+        "Video",
+        "https://www.youtube.com/watch?v=abc123&gclid=x",
+        None,
+        "https://a.org",
+      ),
+    ],
+    ..Blog::default()
+  };
+  let links = config
+    .link_rewriter()
+    .apply("https://a.org", blog)
+    .expect("Links are rewritten")
+    .posts
+    .into_iter()
+    .map(|post| post.link)
+    .collect::<Vec<_>>();
+  assert_eq!(
+    links,
+    [
+      "https://old.reddit.com/r/rust",
+      "https://invidious.example/watch?v=abc123"
+    ]
+  );
+
+  assert!(ConfigFile::parse("[[defaults.rewrites]]\nfrom = \"/(/\"\nto = \"x\"").is_err());
+}
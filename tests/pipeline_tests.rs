@@ -0,0 +1,517 @@
+use std::{
+  collections::{BTreeMap, HashMap},
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+  },
+};
+
+use chrono::{Duration, Utc};
+use futures::{
+  future::{self, BoxFuture},
+  StreamExt,
+};
+use rss2email_lib::{
+  email::message::EmailMessage,
+  fetch::Response,
+  link::{resolved_link_key, LinkResolver, LinkRewriter},
+  pipeline::{Fetcher, Filter, Mailer, Parser, Pipeline, RecentPosts, Renderer, WebFeedParser},
+  state::{MemoryStore, StateStore},
+  Blog, Digest, Error, Post,
+};
+
+/// Serves feeds from memory.
+#[derive(Debug, Default)]
+struct StaticFetcher {
+  pages: HashMap<String, String>,
+  redirects: HashMap<String, String>,
+  requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl StaticFetcher {
+  fn with(mut self, url: &str, content: &str) -> Self {
+    let _previous = self.pages.insert(url.to_owned(), content.to_owned());
+    self
+  }
+
+  fn with_redirect(mut self, from: &str, to: &str) -> Self {
+    let _previous = self.redirects.insert(from.to_owned(), to.to_owned());
+    self
+  }
+}
+
+impl Fetcher for StaticFetcher {
+  fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response, Error>> {
+    self
+      .requests
+      .lock()
+      .expect("Not poisoned")
+      .push(url.to_owned());
+    let redirected_to = self.redirects.get(url).cloned();
+    let served_from = redirected_to.as_deref().unwrap_or(url);
+    let response = self.pages.get(served_from).map_or_else(
+      || Response {
+        status: 404,
+        headers: BTreeMap::from([("content-type".to_owned(), "text/html".to_owned())]),
+        body: format!("{url} not found").into_bytes(),
+        redirected_to: redirected_to.clone(),
+      },
+      |page| Response {
+        status: 200,
+        headers: BTreeMap::from([("content-type".to_owned(), "application/xml".to_owned())]),
+        body: page.clone().into_bytes(),
+        redirected_to: redirected_to.clone(),
+      },
+    );
+    Box::pin(async move { Ok(response) })
+  }
+}
+
+/// Never answers.
+#[derive(Debug)]
+struct SilentFetcher;
+
+impl Fetcher for SilentFetcher {
+  fn fetch<'a>(&'a self, _url: &'a str) -> BoxFuture<'a, Result<Response, Error>> {
+    Box::pin(future::pending())
+  }
+}
+
+/// Parses feeds in a `title|post|post...` format, where posts are `title,days ago`.
+#[derive(Debug)]
+struct PipeParser;
+
+impl Parser for PipeParser {
+  fn parse(&self, _feed: &str, content: &str) -> Result<Blog, Error> {
+    let mut parts = content.split('|');
+    let title = parts
+      .next()
+      .filter(|title| !title.is_empty())
+      .ok_or_else(|| Error::Generic("Missing title".to_owned()))?;
+
+    let posts = parts
+      .map(|post| {
+        let (title, days) = post
+          .split_once(',')
+          .ok_or_else(|| Error::Generic(format!("Invalid post {post}")))?;
+        let age = days
+          .parse::<i64>()
+          .ok()
+          .and_then(Duration::try_days)
+          .ok_or_else(|| Error::Generic(format!("Invalid age {days}")))?;
+
+        Ok(Post {
+          title: title.to_owned(),
+          link: format!("https://example.org/{title}"),
+          description: None,
+          pub_date: Utc::now() - age,
+          ..Post::default()
+        })
+      })
+      .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(Blog {
+      title: title.to_owned(),
+      most_recent_pub_date: posts
+        .iter()
+        .map(|post| post.pub_date)
+        .max()
+        .unwrap_or_default(),
+      posts,
+      ..Blog::default()
+    })
+  }
+}
+
+/// Drops the posts whose title contains a word.
+#[derive(Debug)]
+struct Exclude(&'static str);
+
+impl Filter for Exclude {
+  fn apply(&self, _feed: &str, mut blog: Blog) -> Option<Blog> {
+    blog.posts.retain(|post| !post.title.contains(self.0));
+    Some(blog)
+  }
+}
+
+/// Counts how many runs went through it.
+#[derive(Debug, Clone, Default)]
+struct Runs(Arc<AtomicUsize>);
+
+impl Filter for Runs {
+  fn apply(&self, _feed: &str, blog: Blog) -> Option<Blog> {
+    Some(blog)
+  }
+
+  fn finish(&self) {
+    let _previous = self.0.fetch_add(1, Ordering::Relaxed);
+  }
+}
+
+/// Lists the post titles.
+#[derive(Debug)]
+struct TitleRenderer;
+
+impl Renderer for TitleRenderer {
+  fn render(&self, digest: &Digest) -> Result<EmailMessage, Error> {
+    let titles = digest
+      .blogs
+      .iter()
+      .flat_map(|blog| &blog.posts)
+      .map(|post| post.title.as_str())
+      .collect::<Vec<_>>()
+      .join(",");
+
+    Ok(EmailMessage::new(format!("<p>{titles}</p>"), titles))
+  }
+}
+
+/// Keeps every message it is asked to send.
+#[derive(Debug, Clone, Default)]
+struct Outbox {
+  sent: Arc<Mutex<Vec<String>>>,
+}
+
+impl Mailer for Outbox {
+  fn send<'a>(
+    &'a self,
+    digest: &'a Digest,
+    message: &'a EmailMessage,
+  ) -> BoxFuture<'a, Result<(), Error>> {
+    self.sent.lock().expect("Not poisoned").push(format!(
+      "{} posts: {}",
+      digest.post_count(),
+      message.text
+    ));
+    Box::pin(async { Ok(()) })
+  }
+}
+
+fn pipeline() -> Pipeline {
+  let fetcher = StaticFetcher::default()
+    .with("https://a.example", "A|old,30|recent,1|draft,2")
+    .with("https://b.example", "B|older,40")
+    .with("https://broken.example", "|not a feed");
+
+  Pipeline::new(vec![
+    "https://a.example".to_owned(),
+    "https://b.example".to_owned(),
+    "https://broken.example".to_owned(),
+    "https://missing.example".to_owned(),
+  ])
+  .with_fetcher(fetcher)
+  .with_parser(PipeParser)
+  .with_filter(RecentPosts::new(7))
+  .with_filter(Exclude("draft"))
+  .with_renderer(TitleRenderer)
+}
+
+#[test]
+fn test_pipeline_fetch() {
+  let digest = pipeline().fetch_blocking().expect("The feeds are listed");
+
+  assert_eq!(digest.blogs.len(), 1);
+  assert_eq!(digest.blogs[0].title, "A");
+  assert_eq!(digest.post_count(), 1);
+  assert_eq!(digest.blogs[0].posts[0].title, "recent");
+  assert_eq!(
+    digest.blogs[0].feed_url.as_deref(),
+    Some("https://a.example")
+  );
+
+  let mut failed = digest
+    .failed_feeds
+    .iter()
+    .map(|feed| feed.url.as_str())
+    .collect::<Vec<_>>();
+  failed.sort_unstable();
+  assert_eq!(
+    failed,
+    ["https://broken.example", "https://missing.example"]
+  );
+}
+
+#[test]
+fn test_pipeline_dedupe() {
+  let pipeline = || {
+    let fetcher = StaticFetcher::default()
+      .with("https://a.example", "A|shared,1|own,1")
+      .with("https://b.example", "B|shared,2");
+
+    Pipeline::new(vec![
+      "https://a.example".to_owned(),
+      "https://b.example".to_owned(),
+    ])
+    .with_fetcher(fetcher)
+    .with_parser(PipeParser)
+  };
+
+  let digest = pipeline().fetch_blocking().expect("The feeds are listed");
+  assert_eq!(digest.post_count(), 2);
+  let shared = digest
+    .blogs
+    .iter()
+    .flat_map(|blog| &blog.posts)
+    .find(|post| post.title == "shared")
+    .expect("One copy is kept");
+  let mut feeds = shared.feeds.clone();
+  feeds.sort_unstable();
+  assert_eq!(feeds, ["https://a.example", "https://b.example"]);
+
+  let digest = pipeline()
+    .with_dedupe(false)
+    .fetch_blocking()
+    .expect("The feeds are listed");
+  assert_eq!(digest.post_count(), 3);
+}
+
+#[test]
+fn test_pipeline_run() {
+  let outbox = Outbox::default();
+
+  let result = pipeline().run_blocking();
+  assert!(result.is_err(), "Running without a mailer should fail");
+
+  let digest = pipeline()
+    .with_mailer(outbox.clone())
+    .run_blocking()
+    .expect("The pipeline runs");
+  assert_eq!(digest.post_count(), 1);
+  assert_eq!(
+    *outbox.sent.lock().expect("Not poisoned"),
+    ["1 posts: recent"]
+  );
+}
+
+#[tokio::test]
+async fn test_pipeline_fetch_async() {
+  let digest = pipeline().fetch().await.expect("The feeds are listed");
+  assert_eq!(digest.post_count(), 1);
+
+  // Also works from within a runtime.
+  let digest = pipeline().fetch_blocking().expect("The feeds are listed");
+  assert_eq!(digest.post_count(), 1);
+}
+
+#[tokio::test]
+async fn test_pipeline_stream() {
+  let pipeline = pipeline();
+  let mut results = pipeline
+    .stream()
+    .expect("The feeds are listed")
+    .collect::<Vec<_>>()
+    .await;
+  results.sort_by_key(|result| match result {
+    Ok(blog) => blog.title.clone(),
+    Err(failed) => failed.url.clone(),
+  });
+
+  // B is dropped by the filters, the other feeds failed.
+  assert_eq!(results.len(), 3);
+  assert!(matches!(&results[0], Ok(blog) if blog.title == "A"));
+  assert!(results[1..].iter().all(Result::is_err));
+
+  // The stream can outlive the pipeline.
+  let results = tokio::spawn(
+    pipeline
+      .into_stream()
+      .expect("The feeds are listed")
+      .count(),
+  )
+  .await
+  .expect("The task finished");
+  assert_eq!(results, 3);
+}
+
+#[test]
+fn test_pipeline_fallback_parser() {
+  let fetcher = StaticFetcher::default().with("https://a.example", "A|recent,1");
+
+  let pipeline = Pipeline::new(vec!["https://a.example".to_owned()]).with_fetcher(fetcher);
+  let digest = pipeline.fetch_blocking().expect("The feeds are listed");
+  assert_eq!(digest.failed_feeds.len(), 1);
+
+  let digest = pipeline
+    .with_fallback_parser(PipeParser)
+    .fetch_blocking()
+    .expect("The feeds are listed");
+  assert_eq!(digest.failed_feeds.len(), 0);
+  assert_eq!(digest.post_count(), 1);
+}
+
+#[tokio::test]
+async fn test_pipeline_finishes_filters() {
+  let runs = Runs::default();
+  let pipeline = pipeline().with_filter(runs.clone());
+
+  let _digest = pipeline.fetch().await.expect("The feeds are listed");
+  let _digest = pipeline.fetch().await.expect("The feeds are listed");
+  assert_eq!(runs.0.load(Ordering::Relaxed), 2);
+
+  let _count = pipeline
+    .into_stream()
+    .expect("The feeds are listed")
+    .count()
+    .await;
+  assert_eq!(runs.0.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn test_web_feed_parser_resolves_links() {
+  let content =
+    std::fs::read_to_string("test-data/atom-feeds/relative-links.xml").expect("Readable fixture");
+  let blog = WebFeedParser::default()
+    .parse("https://example.org/feeds/atom.xml", &content)
+    .expect("Parsed content");
+
+  assert_eq!(
+    blog.posts[0].link,
+    "https://example.org/blog/posts/first.html"
+  );
+}
+
+#[test]
+fn test_pipeline_resolves_links_after_redirects() {
+  let content =
+    std::fs::read_to_string("test-data/atom-feeds/relative-links.xml").expect("Readable fixture");
+  let fetcher = StaticFetcher::default()
+    .with("https://example.org/feeds/atom.xml", &content)
+    .with_redirect(
+      "https://old.example/atom.xml",
+      "https://example.org/feeds/atom.xml",
+    );
+
+  let digest = Pipeline::new(vec!["https://old.example/atom.xml".to_owned()])
+    .with_fetcher(fetcher)
+    .fetch_blocking()
+    .expect("The feeds are listed");
+
+  let blog = &digest.blogs[0];
+  assert_eq!(
+    blog.posts[0].link,
+    "https://example.org/blog/posts/first.html"
+  );
+  assert_eq!(
+    blog.feed_url.as_deref(),
+    Some("https://old.example/atom.xml")
+  );
+}
+
+/// An RSS feed whose posts link to `links`.
+fn rss(links: &[&str]) -> String {
+  let items = links
+    .iter()
+    .map(|link| {
+      format!(
+        "<item><title>{link}</title><link>{link}</link>\
+         <pubDate>Tue, 10 Jun 2003 04:00:00 GMT</pubDate></item>"
+      )
+    })
+    .collect::<Vec<_>>()
+    .concat();
+
+  format!("<rss version=\"2.0\"><channel><title>Feed</title>{items}</channel></rss>")
+}
+
+#[tokio::test]
+async fn test_pipeline_resolves_shortened_links() {
+  let fetcher = StaticFetcher::default()
+    .with(
+      "https://a.example/feed.xml",
+      &rss(&[
+        "https://t.co/abc",
+        "http://feeds.feedburner.com/~r/blog/~3/xyz/",
+        "https://t.co/unknown",
+        "https://example.org/kept",
+      ]),
+    )
+    .with("https://b.example/feed.xml", &rss(&["https://t.co/abc"]))
+    .with_redirect(
+      "https://t.co/abc",
+      "https://example.org/post?utm_source=twitter",
+    )
+    .with_redirect(
+      "http://feeds.feedburner.com/~r/blog/~3/xyz/",
+      "https://blog.example/xyz",
+    );
+  let requests = Arc::clone(&fetcher.requests);
+
+  let digest = Pipeline::new(vec![
+    "https://a.example/feed.xml".to_owned(),
+    "https://b.example/feed.xml".to_owned(),
+  ])
+  .with_fetcher(fetcher)
+  .with_concurrency(1)
+  .with_dedupe(false)
+  .with_filter(LinkResolver::new())
+  .with_filter(LinkRewriter::new())
+  .fetch()
+  .await
+  .expect("The feeds are listed");
+
+  let links = digest
+    .blogs
+    .iter()
+    .flat_map(|blog| blog.posts.iter().map(|post| post.link.as_str()))
+    .collect::<Vec<_>>();
+  // The resolved links are cleaned up, and links that do not redirect are left as is.
+  assert_eq!(
+    links,
+    [
+      "https://example.org/post",
+      "https://blog.example/xyz",
+      "https://t.co/unknown",
+      "https://example.org/kept",
+      "https://example.org/post",
+    ]
+  );
+
+  // Each link is only requested once.
+  let requests = requests.lock().expect("Not poisoned").clone();
+  assert_eq!(
+    requests
+      .iter()
+      .filter(|url| *url == "https://t.co/abc")
+      .count(),
+    1
+  );
+  assert!(!requests.iter().any(|url| url == "https://example.org/kept"));
+}
+
+#[tokio::test]
+async fn test_link_resolver_cache_and_timeout() {
+  let store = Arc::new(MemoryStore::new());
+  let fetcher =
+    StaticFetcher::default().with_redirect("https://t.co/abc", "https://example.org/post");
+
+  let resolver = LinkResolver::new().with_cache(store.clone());
+  assert_eq!(
+    resolver
+      .resolve("https://t.co/abc", &fetcher)
+      .await
+      .as_deref(),
+    Some("https://example.org/post")
+  );
+  assert!(store
+    .get(&resolved_link_key("https://t.co/abc"))
+    .expect("Valid key")
+    .is_some());
+
+  // Cached links are not requested again, by any resolver sharing the store.
+  let resolver = LinkResolver::new()
+    .with_cache(store)
+    .with_timeout(std::time::Duration::from_millis(10));
+  assert_eq!(
+    resolver
+      .resolve("https://t.co/abc", &SilentFetcher)
+      .await
+      .as_deref(),
+    Some("https://example.org/post")
+  );
+
+  // Links that take too long to resolve are left as is.
+  assert_eq!(
+    resolver.resolve("https://t.co/slow", &SilentFetcher).await,
+    None
+  );
+}
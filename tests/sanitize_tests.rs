@@ -0,0 +1,59 @@
+use rss2email_lib::sanitize::{clean_content, clean_html, safe_url};
+use url::Url;
+
+#[test]
+fn test_safe_url() {
+  assert_eq!(safe_url("https://example.org"), Some("https://example.org"));
+  assert_eq!(safe_url(" HTTP://example.org "), Some("HTTP://example.org"));
+  assert_eq!(
+    safe_url("mailto:me@example.org"),
+    Some("mailto:me@example.org")
+  );
+  assert_eq!(safe_url("javascript:alert(1)"), None);
+  assert_eq!(safe_url("data:text/html;base64,PHNjcmlwdD4="), None);
+  assert_eq!(safe_url("/relative/link"), None);
+}
+
+#[test]
+fn test_clean_html() {
+  assert_eq!(
+    clean_html(r#"<p style="color: red">Hi <a href="javascript:alert(1)">there</a></p>"#),
+    r#"<p>Hi <a rel="noopener noreferrer">there</a></p>"#
+  );
+  assert_eq!(
+    clean_html(r#"<a href="https://example.org" onclick="alert(1)">link</a>"#),
+    r#"<a href="https://example.org" rel="noopener noreferrer">link</a>"#
+  );
+  assert_eq!(
+    clean_html("<iframe src=\"x\"></iframe>Text<b>bold"),
+    "Text<b>bold</b>"
+  );
+}
+
+#[test]
+fn test_clean_content_with_base() {
+  let base = Url::parse("https://example.org/blog/post.html").expect("Valid URL");
+  assert_eq!(
+    clean_content(
+      r#"<a href="/about">About</a> <a href="next.html">Next</a> <a href="https://other.example">Other</a>"#,
+      Some(&base)
+    ),
+    r#"<a href="https://example.org/about" rel="noopener noreferrer">About</a> <a href="https://example.org/blog/next.html" rel="noopener noreferrer">Next</a> <a href="https://other.example" rel="noopener noreferrer">Other</a>"#
+  );
+}
+
+#[test]
+fn test_clean_content() {
+  let html = r#"<h2 id="x">Title</h2><img src="/a.png" alt="A" onerror="alert(1)"><img src="data:image/png;base64,AA=="><table><tr><td colspan="2" style="x">Cell</td></tr></table><iframe src="x"></iframe>"#;
+
+  assert_eq!(
+    clean_content(html, None),
+    r#"<h2>Title</h2><img src="/a.png" alt="A"><img><table><tbody><tr><td colspan="2">Cell</td></tr></tbody></table>"#
+  );
+
+  let base = Url::parse("https://example.org/blog/post.html").expect("Valid URL");
+  assert!(
+    clean_content(html, Some(&base)).contains(r#"<img src="https://example.org/a.png" alt="A">"#)
+  );
+  assert_eq!(clean_html("<h2>Title</h2>"), "Title");
+}
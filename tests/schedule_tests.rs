@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use rss2email_lib::schedule::Schedule;
+
+fn utc(date: &str) -> DateTime<Utc> {
+  DateTime::parse_from_rfc3339(date)
+    .expect("Valid date")
+    .with_timezone(&Utc)
+}
+
+#[test]
+fn test_five_field_schedule_in_timezone() {
+  let schedule = Schedule::new("0 7 * * MON-FRI", "Europe/Athens").expect("Valid schedule");
+
+  // Friday 2024-03-08 at 07:00 in Athens has passed, so the next run is on Monday.
+  let next = schedule
+    .next_after(utc("2024-03-08T06:00:00Z"))
+    .expect("The schedule fires again");
+
+  assert_eq!(next.to_rfc3339(), "2024-03-11T07:00:00+02:00");
+  assert_eq!(next.with_timezone(&Utc), utc("2024-03-11T05:00:00Z"));
+}
+
+#[test]
+fn test_five_field_numeric_weekdays() {
+  let schedule = Schedule::new("0 7 * * 1-5", "UTC").expect("Valid schedule");
+
+  // Saturday 2024-03-09, the next run is on Monday and not on Sunday.
+  let next = schedule
+    .next_after(utc("2024-03-09T08:00:00Z"))
+    .expect("The schedule fires again");
+  assert_eq!(next.with_timezone(&Utc), utc("2024-03-11T07:00:00Z"));
+
+  // Thursday 2024-03-07, the next run is on Friday.
+  let next = schedule
+    .next_after(utc("2024-03-07T08:00:00Z"))
+    .expect("The schedule fires again");
+  assert_eq!(next.with_timezone(&Utc), utc("2024-03-08T07:00:00Z"));
+
+  // Both 0 and 7 are Sunday.
+  for sunday in ["0", "7", "0,6", "5-7"] {
+    let schedule = Schedule::new(&format!("0 7 * * {sunday}"), "UTC").expect("Valid schedule");
+    let next = schedule
+      .next_after(utc("2024-03-09T08:00:00Z"))
+      .expect("The schedule fires again");
+    assert_eq!(next.with_timezone(&Utc), utc("2024-03-10T07:00:00Z"));
+  }
+
+  // Every other day from Sunday: Sunday, Tuesday, Thursday and Saturday.
+  let schedule = Schedule::new("0 7 * * */2", "UTC").expect("Valid schedule");
+  let next = schedule
+    .next_after(utc("2024-03-10T08:00:00Z"))
+    .expect("The schedule fires again");
+  assert_eq!(next.with_timezone(&Utc), utc("2024-03-12T07:00:00Z"));
+}
+
+#[test]
+fn test_schedule_follows_daylight_saving_time() {
+  let schedule = Schedule::new("0 7 * * *", "Europe/Athens").expect("Valid schedule");
+
+  // Clocks in Athens move forward on 2024-03-31.
+  let next = schedule
+    .next_after(utc("2024-03-30T06:00:00Z"))
+    .expect("The schedule fires again");
+
+  assert_eq!(next.with_timezone(&Utc), utc("2024-03-31T04:00:00Z"));
+}
+
+#[test]
+fn test_six_field_schedule() {
+  let schedule = Schedule::new("30 0 12 * * *", "UTC").expect("Valid schedule");
+
+  let next = schedule
+    .next_after(utc("2024-03-08T12:00:00Z"))
+    .expect("The schedule fires again");
+
+  assert_eq!(next.with_timezone(&Utc), utc("2024-03-08T12:00:30Z"));
+}
+
+#[test]
+fn test_invalid_schedule() {
+  assert!(Schedule::new("0 7 * * FOO", "UTC").is_err());
+  assert!(Schedule::new("0 7 * *", "UTC").is_err());
+  assert!(Schedule::new("0 7 * * 8", "UTC").is_err());
+  assert!(Schedule::new("0 7 * * 5-1", "UTC").is_err());
+  assert!(Schedule::new("0 7 * * *", "Mars/Base").is_err());
+}
@@ -0,0 +1,286 @@
+use std::{
+  io::{BufRead, BufReader, Write},
+  net::TcpListener,
+  path::PathBuf,
+  sync::Arc,
+  thread::{self, JoinHandle},
+};
+
+use chrono::Utc;
+use rss2email_lib::{
+  config::Config,
+  fetch::{cache_key, Fetcher, HttpFetcher},
+  state::{self, FsStore, MemoryStore, StateStore},
+  summary::{Delivery, RunSummary},
+};
+
+/// A directory under the system temp dir that is unique to `name` and this process.
+fn temp_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("rss2email-{name}-{}", std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  dir
+}
+
+fn check_store(store: &dyn StateStore) {
+  assert_eq!(store.get("runs/daily/last.json").expect("Valid key"), None);
+
+  store
+    .put("runs/daily/last.json", b"first")
+    .expect("Valid key");
+  store
+    .put("runs/daily/last.json", b"second")
+    .expect("Valid key");
+  assert_eq!(
+    store
+      .get("runs/daily/last.json")
+      .expect("Valid key")
+      .as_deref(),
+    Some(&b"second"[..])
+  );
+
+  store.delete("runs/daily/last.json").expect("Valid key");
+  assert_eq!(store.get("runs/daily/last.json").expect("Valid key"), None);
+  // Deleting a missing value is not an error.
+  store.delete("runs/daily/last.json").expect("Valid key");
+
+  for key in [
+    "",
+    "/absolute",
+    "runs/../escape",
+    "runs//empty",
+    "./runs",
+    "runs\\windows",
+  ] {
+    assert!(store.get(key).is_err(), "{key} should be rejected");
+    assert!(
+      store.put(key, b"value").is_err(),
+      "{key} should be rejected"
+    );
+  }
+}
+
+#[test]
+fn test_memory_store() {
+  check_store(&MemoryStore::new());
+}
+
+#[test]
+fn test_fs_store() {
+  let dir = temp_dir("fs-store");
+  let store = FsStore::new(&dir);
+  check_store(&store);
+
+  store
+    .put("nested/key", b"value")
+    .expect("Writable temp dir");
+  assert_eq!(
+    std::fs::read(dir.join("nested/key")).expect("The value was written"),
+    b"value"
+  );
+
+  std::fs::remove_dir_all(dir).expect("Removable temp dir");
+}
+
+#[test]
+fn test_fs_store_concurrent_writes() {
+  let dir = temp_dir("fs-store-concurrent");
+  let store = Arc::new(FsStore::new(&dir));
+
+  let writers = (0..8)
+    .map(|i| {
+      let store = Arc::clone(&store);
+      thread::spawn(move || {
+        for _ in 0..20 {
+          store
+            .put("runs/daily/last.json", format!("value {i}").as_bytes())
+            .expect("Concurrent writes succeed");
+        }
+      })
+    })
+    .collect::<Vec<_>>();
+  for writer in writers {
+    writer.join().expect("The writer finished");
+  }
+
+  let value = store
+    .get("runs/daily/last.json")
+    .expect("Valid key")
+    .expect("A value was written");
+  assert!(value.starts_with(b"value "));
+  assert_eq!(
+    std::fs::read_dir(dir.join("runs/daily"))
+      .expect("Readable directory")
+      .count(),
+    1,
+    "No temporary file is left behind"
+  );
+
+  std::fs::remove_dir_all(dir).expect("Removable temp dir");
+}
+
+/// Answers each request made to the returned URL with the next of `responses`, and
+/// returns the head of every request that was received.
+fn serve(responses: Vec<&'static str>) -> (String, JoinHandle<Vec<String>>) {
+  let listener = TcpListener::bind("127.0.0.1:0").expect("Free port");
+  let url = format!(
+    "http://{}/feed.xml",
+    listener.local_addr().expect("Bound address")
+  );
+
+  let server = thread::spawn(move || {
+    responses
+      .into_iter()
+      .map(|response| {
+        let (mut stream, _) = listener.accept().expect("A request");
+        let mut request = String::new();
+        let mut reader = BufReader::new(stream.try_clone().expect("Cloneable stream"));
+        while reader.read_line(&mut request).expect("Readable request") > 2 {}
+        stream
+          .write_all(response.as_bytes())
+          .expect("Writable stream");
+        request.to_lowercase()
+      })
+      .collect()
+  });
+
+  (url, server)
+}
+
+#[tokio::test]
+async fn test_http_cache() {
+  let (url, server) = serve(vec![
+    "HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\nETag: \"v1\"\r\nContent-Length: 6\r\nConnection: close\r\n\r\n<rss/>",
+    "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n",
+  ]);
+  let store = Arc::new(MemoryStore::new());
+  let fetcher = HttpFetcher::default().with_cache(store.clone());
+
+  let first = fetcher.fetch(&url).await.expect("Downloaded feed");
+  assert_eq!(first.status, 200);
+  assert!(store.get(&cache_key(&url)).expect("Valid key").is_some());
+
+  let second = fetcher.fetch(&url).await.expect("Revalidated feed");
+  assert_eq!(second, first);
+  assert_eq!(second.into_feed(&url).expect("Cached feed"), "<rss/>");
+
+  let requests = server.join().expect("The server finished");
+  assert!(!requests[0].contains("if-none-match"));
+  assert!(requests[1].contains("if-none-match: \"v1\""));
+}
+
+#[test]
+fn test_record_run() {
+  let store = MemoryStore::new();
+  let summary = RunSummary {
+    profile: Some("daily".to_owned()),
+    posts: 3,
+    delivery: Delivery::Sent,
+    ..RunSummary::default()
+  };
+
+  assert_eq!(
+    state::last_run(&store, Some("daily")).expect("Valid state"),
+    None
+  );
+  let started_at = Utc::now();
+  state::record_run(&store, &summary, started_at).expect("Valid state");
+
+  let last_run = state::last_run(&store, Some("daily"))
+    .expect("Valid state")
+    .expect("The run was recorded");
+  assert_eq!(last_run.summary, summary);
+  assert_eq!(last_run.started_at, Some(started_at));
+  assert_eq!(state::last_run(&store, None).expect("Valid state"), None);
+
+  // A failed run does not replace the last successful one.
+  let failed = RunSummary::failed(Some("daily".to_owned()), "Provider is down");
+  state::record_run(&store, &failed, Utc::now()).expect("Valid state");
+
+  let last_run = state::last_run(&store, Some("daily"))
+    .expect("Valid state")
+    .expect("The run was recorded");
+  assert_eq!(last_run.summary, failed);
+  let last_success = state::last_successful_run(&store, Some("daily"))
+    .expect("Valid state")
+    .expect("The run was recorded");
+  assert_eq!(last_success.summary, summary);
+}
+
+#[test]
+fn test_state_backend() {
+  let mut config = Config::default();
+  assert!(config.state_store().expect("Valid backend").is_none());
+  assert!(config.validate_state().is_valid());
+
+  config.state_backend = "FS".to_owned();
+  config.state_dir = temp_dir("state-backend");
+  let store = config
+    .state_store()
+    .expect("Valid backend")
+    .expect("A backend is set");
+  store.put("key", b"value").expect("Writable temp dir");
+  assert!(config.state_dir.join("key").is_file());
+  std::fs::remove_dir_all(&config.state_dir).expect("Removable temp dir");
+
+  config.state_backend = "redis".to_owned();
+  let validation = config.validate_state();
+  assert_eq!(
+    validation.errors,
+    ["STATE_BACKEND is set to 'redis', expected one of none, fs, s3, memory"]
+  );
+
+  config.state_backend = "s3".to_owned();
+  assert!(!config.validate_state().is_valid());
+}
+
+/// The S3 store is created, used and dropped inside a runtime, as in `Pipeline::fetch`.
+#[cfg(feature = "s3")]
+#[tokio::test]
+async fn test_s3_store_in_runtime() {
+  use rss2email_lib::state::S3Store;
+
+  let (url, server) = serve(vec![
+    "HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nvalue",
+    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+    "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n",
+  ]);
+  let endpoint = url.trim_end_matches("/feed.xml");
+  let store = S3Store::new(endpoint, "us-east-1", "bucket", "prefix", None).expect("Valid bucket");
+
+  assert_eq!(
+    store.get("present").expect("Readable object"),
+    Some(b"value".to_vec())
+  );
+  assert_eq!(store.get("missing").expect("Readable object"), None);
+  store.delete("present").expect("Deleted object");
+  drop(store);
+
+  let requests = server.join().expect("The server finished");
+  assert!(requests[0].starts_with("get /bucket/prefix/present "));
+  assert!(requests[2].starts_with("delete /bucket/prefix/present "));
+}
+
+/// Runs against the bucket configured with `S3_ENDPOINT`, `S3_BUCKET` and the `AWS_*`
+/// credentials, for example a local `MinIO`:
+///
+/// ```sh
+/// docker run -p 9000:9000 minio/minio server /data
+/// S3_ENDPOINT=http://localhost:9000 S3_BUCKET=rss2email AWS_ACCESS_KEY_ID=minioadmin \
+///   AWS_SECRET_ACCESS_KEY=minioadmin cargo test --features s3 -- --ignored
+/// ```
+#[cfg(feature = "s3")]
+#[test]
+#[ignore = "needs an S3-compatible server"]
+fn test_s3_store() {
+  let config = Config {
+    state_backend: "s3".to_owned(),
+    s3_prefix: format!("rss2email-test-{}", std::process::id()),
+    ..Config::from_env()
+  };
+
+  let store = config
+    .state_store()
+    .expect("Valid backend")
+    .expect("A backend is set");
+  check_store(store.as_ref());
+}
@@ -0,0 +1,73 @@
+use chrono::Utc;
+use rss2email_lib::{
+  summary::{Delivery, RunSummary},
+  Blog, Digest, FailedFeed, Post,
+};
+use serde_json::json;
+
+fn digest() -> Digest {
+  let post = Post {
+    title: "Post".to_owned(),
+    link: "https://example.org/post".to_owned(),
+    description: None,
+    pub_date: Utc::now(),
+    ..Post::default()
+  };
+
+  Digest {
+    blogs: vec![Blog {
+      title: "Blog".to_owned(),
+      most_recent_pub_date: post.pub_date,
+      posts: vec![post.clone(), post],
+      ..Blog::default()
+    }],
+    failed_feeds: vec![FailedFeed {
+      url: "https://example.org/broken".to_owned(),
+      error: "404".to_owned(),
+    }],
+  }
+}
+
+#[test]
+fn test_summary_counts() {
+  let summary = RunSummary::new(Some("daily".to_owned()), 3, &digest());
+
+  assert_eq!(summary.feeds_fetched, 2);
+  assert_eq!(summary.feeds_failed.len(), 1);
+  assert_eq!(summary.blogs, 1);
+  assert_eq!(summary.posts, 2);
+  assert_eq!(summary.delivery, Delivery::NotAttempted);
+  assert!(!summary.is_failure());
+}
+
+#[test]
+fn test_summary_json() {
+  let summary = RunSummary {
+    delivery: Delivery::Failed {
+      error: "Unauthorized".to_owned(),
+    },
+    ..RunSummary::new(None, 3, &digest())
+  };
+
+  assert!(summary.is_failure());
+  assert_eq!(
+    serde_json::to_value(&summary).expect("Summaries serialize"),
+    json!({
+      "profile": null,
+      "feeds_fetched": 2,
+      "feeds_failed": [{ "url": "https://example.org/broken", "error": "404" }],
+      "blogs": 1,
+      "posts": 2,
+      "delivery": { "status": "failed", "error": "Unauthorized" },
+    })
+  );
+
+  assert_eq!(
+    serde_json::to_value(RunSummary::failed(
+      Some("weekly".to_owned()),
+      "SUBJECT must be set"
+    ))
+    .expect("Summaries serialize")["delivery"],
+    json!({ "status": "failed", "error": "SUBJECT must be set" })
+  );
+}
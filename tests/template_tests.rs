@@ -0,0 +1,283 @@
+use chrono::{DateTime, Utc};
+use rss2email_lib::{
+  template::{Templates, Theme},
+  Blog, Enclosure, FailedFeed, Post,
+};
+
+fn post_date(value: &str) -> DateTime<Utc> {
+  value
+    .parse::<DateTime<Utc>>()
+    .unwrap_or_else(|_| panic!("Invalid date {value}"))
+}
+
+fn blogs() -> Vec<Blog> {
+  vec![Blog {
+    title: "Example Feed".into(),
+    most_recent_pub_date: post_date("2003-12-13T18:30:02+00:00"),
+    posts: vec![Post {
+      title: "Atom-Powered Robots Run Amok".into(),
+      link: "http://example.org/2003/12/13/atom03".into(),
+      description: Some("Some text.".into()),
+      pub_date: post_date("2003-12-13T18:30:02+00:00"),
+      ..Post::default()
+    }],
+    ..Blog::default()
+  }]
+}
+
+#[test]
+fn test_default_templates() {
+  let message = Templates::default()
+    .render(&blogs(), &[])
+    .expect("Rendered templates");
+
+  assert!(message.html.starts_with("<!DOCTYPE html>"));
+  assert!(message.html.contains(">Rss2Email - "));
+  assert!(message.html.contains(">Example Feed</h2>"));
+  assert!(message.html.contains(">Atom-Powered Robots Run Amok</a>"));
+  assert!(!message.html.contains("Failed feeds"));
+
+  assert!(message.text.starts_with("Rss2Email - "));
+  assert!(message
+    .text
+    .contains("Example Feed\n------------\n\n- Atom-Powered Robots Run Amok\n  http://example.org/2003/12/13/atom03\n"));
+}
+
+#[test]
+fn test_default_templates_without_posts() {
+  let failed_feeds = vec![FailedFeed {
+    url: "https://example.org/feed".into(),
+    error: "Parse error".into(),
+  }];
+  let message = Templates::default()
+    .render(&[], &failed_feeds)
+    .expect("Rendered templates");
+
+  assert!(message.html.contains("No new posts were found."));
+  assert!(message.text.contains("No new posts were found."));
+  assert!(message.html.contains(">https://example.org/feed</li>"));
+  assert!(message.text.contains("- https://example.org/feed"));
+}
+
+#[test]
+fn test_custom_html_template() {
+  let templates = Templates::default()
+    .with_html_file("test-data/templates/custom.html")
+    .expect("Loaded template");
+
+  let html = templates
+    .render_html(&blogs(), &[])
+    .expect("Rendered template");
+
+  assert!(html.contains(">1 posts on "));
+  assert!(html.contains(">Example Feed: Atom-Powered Robots Run Amok (13 Dec 2003)</p>"));
+}
+
+#[test]
+fn test_missing_template_file() {
+  assert!(Templates::default()
+    .with_text_file("test-data/templates/missing.txt")
+    .is_err());
+}
+
+#[test]
+fn test_css_is_inlined() {
+  let light = Templates::default()
+    .render_html(&blogs(), &[])
+    .expect("Rendered template");
+  let dark = Templates::default()
+    .with_theme(Theme::Dark)
+    .render_html(&blogs(), &[])
+    .expect("Rendered template");
+
+  assert!(!light.contains("<style"));
+  assert!(light.contains(r#"<a href="http://example.org/2003/12/13/atom03" style="#));
+  assert!(light.contains("background-color: #f4f4f5"));
+  assert!(dark.contains("background-color: #18181b"));
+}
+
+#[test]
+fn test_custom_css() {
+  let html = Templates::default()
+    .with_css_file("test-data/templates/custom.css")
+    .expect("Loaded stylesheet")
+    .render_html(&blogs(), &[])
+    .expect("Rendered template");
+
+  assert!(html.contains("color: #ff0000"));
+}
+
+#[test]
+fn test_unknown_theme() {
+  assert_eq!(Theme::try_from(" Dark ".to_owned()), Ok(Theme::Dark));
+  assert!(Theme::try_from("sepia".to_owned()).is_err());
+}
+
+#[test]
+fn test_feed_content_is_sanitized() {
+  let blogs = vec![Blog {
+    title: "<b>Evil</b> Feed".into(),
+    most_recent_pub_date: post_date("2003-12-13T18:30:02+00:00"),
+    posts: vec![Post {
+      title: "<script>alert(1)</script>".into(),
+      link: "javascript:alert(1)".into(),
+      description: Some(
+        r#"<p onclick="alert(1)">Some <em>text</em><script>alert(1)</script><img src="x"></p>"#
+          .into(),
+      ),
+      pub_date: post_date("2003-12-13T18:30:02+00:00"),
+      ..Post::default()
+    }],
+    ..Blog::default()
+  }];
+
+  let html = Templates::default()
+    .render_html(&blogs, &[])
+    .expect("Rendered template");
+
+  assert!(!html.contains("<script>"));
+  assert!(!html.contains("<b>Evil</b>"));
+  assert!(html.contains(">&lt;script&gt;alert(1)&lt;/script&gt;\n"));
+  assert!(!html.contains("javascript:"));
+  assert!(!html.contains("<img"));
+  assert!(!html.contains("<p onclick"));
+  assert!(html.contains("Some &lt;em&gt;text&lt;/em&gt;"));
+}
+
+#[test]
+fn test_full_text() {
+  let mut blogs = blogs();
+  blogs[0].posts[0].content = Some(
+    r#"<p>The <em>full</em> article.</p><p onclick="alert(1)">Second paragraph.</p><script>alert(1)</script>"#
+      .into(),
+  );
+
+  let message = Templates::default()
+    .render(&blogs, &[])
+    .expect("Rendered templates");
+  assert!(message.html.contains("Some text."));
+  assert!(!message.html.contains("full</em>"));
+
+  let message = Templates::default()
+    .with_full_text(true)
+    .render(&blogs, &[])
+    .expect("Rendered templates");
+  assert!(!message.html.contains("Some text."));
+  assert!(message.html.contains("The <em"));
+  assert!(message.html.contains(">full</em> article.</p>"));
+  assert!(!message.html.contains("onclick"));
+  assert!(!message.html.contains("<script>"));
+  assert!(message.text.contains(
+    "  http://example.org/2003/12/13/atom03\n\n  The full article.\n\n  Second paragraph.\n"
+  ));
+
+  // Posts without content fall back to their description.
+  blogs[0].posts[0].content = None;
+  let message = Templates::default()
+    .with_full_text(true)
+    .render(&blogs, &[])
+    .expect("Rendered templates");
+  assert!(message.html.contains("Some text."));
+  assert!(message.text.contains("\n  Some text.\n"));
+}
+
+#[test]
+fn test_enclosures_and_thumbnails() {
+  let mut blogs = blogs();
+  blogs[0].posts[0].thumbnail = Some("https://example.org/cover.jpg".into());
+  blogs[0].posts[0].enclosures = vec![
+    Enclosure {
+      url: "https://example.org/episode.mp3".into(),
+      mime_type: Some("audio/mpeg".into()),
+      duration: Some(3723),
+      ..Enclosure::default()
+    },
+    Enclosure {
+      url: "https://example.org/trailer.mp4".into(),
+      duration: Some(95),
+      ..Enclosure::default()
+    },
+    Enclosure {
+      url: "javascript:alert(1)".into(),
+      ..Enclosure::default()
+    },
+  ];
+
+  let message = Templates::default()
+    .render(&blogs, &[])
+    .expect("Rendered templates");
+  assert!(message
+    .html
+    .contains(r#"src="https://example.org/cover.jpg""#));
+  assert!(message
+    .html
+    .contains(r#"<a href="https://example.org/episode.mp3" style="#));
+  assert!(message.html.contains(">Download</a> (1:02:03)</div>"));
+  assert!(message.html.contains(">Download</a> (1:35)</div>"));
+  assert!(!message.html.contains("javascript:"));
+  assert!(message.text.contains(
+    "  Download: https://example.org/episode.mp3 (1:02:03)\n  Download: https://example.org/trailer.mp4 (1:35)\n"
+  ));
+
+  blogs[0].posts[0].thumbnail = Some("javascript:alert(1)".into());
+  let html = Templates::default()
+    .render_html(&blogs, &[])
+    .expect("Rendered template");
+  assert!(!html.contains("<img"));
+}
+
+#[test]
+fn test_authors_and_comments() {
+  let mut blogs = blogs();
+  blogs[0].posts[0].authors = vec!["Jane Doe".into(), "John Smith".into()];
+  blogs[0].posts[0].comments = Some("https://example.org/post#comments".into());
+
+  let message = Templates::default()
+    .render(&blogs, &[])
+    .expect("Rendered templates");
+  assert!(message.html.contains("by Jane Doe, John Smith · <a"));
+  assert!(message
+    .html
+    .contains(r#"<a href="https://example.org/post#comments" style="#));
+  assert!(message.text.contains("  by Jane Doe, John Smith\n"));
+  assert!(message
+    .text
+    .contains("  Comments: https://example.org/post#comments\n"));
+
+  blogs[0].posts[0].comments = Some("javascript:alert(1)".into());
+  let html = Templates::default()
+    .render_html(&blogs, &[])
+    .expect("Rendered template");
+  assert!(!html.contains("javascript:"));
+  assert!(!html.contains(">Comments</a>"));
+}
+
+#[test]
+fn test_blog_metadata() {
+  let mut blogs = blogs();
+  blogs[0].link = Some("http://example.org/".into());
+  blogs[0].description = Some("A blog about robots.".into());
+  blogs[0].icon = Some("http://example.org/favicon.ico".into());
+  blogs[0].language = Some("en".into());
+
+  let message = Templates::default()
+    .render(&blogs, &[])
+    .expect("Rendered templates");
+  assert!(message.html.contains(r#"lang="en""#));
+  assert!(message
+    .html
+    .contains(r#"src="http://example.org/favicon.ico""#));
+  assert!(message.html.contains(">Example Feed</a></h2>"));
+  assert!(message.html.contains(">A blog about robots.</p>"));
+  assert!(message.text.contains(
+    "Example Feed\n------------\nA blog about robots.\nhttp://example.org/\n\n- Atom-Powered Robots Run Amok\n"
+  ));
+
+  blogs[0].link = Some("javascript:alert(1)".into());
+  blogs[0].icon = Some("javascript:alert(1)".into());
+  let html = Templates::default()
+    .render_html(&blogs, &[])
+    .expect("Rendered template");
+  assert!(!html.contains("javascript:"));
+  assert!(html.contains(">Example Feed</h2>"));
+}
@@ -0,0 +1,55 @@
+use rss2email_lib::text::{html_to_paragraphs, html_to_text, truncate};
+
+#[test]
+fn test_html_to_text() {
+  assert_eq!(
+    html_to_text("<p>First&nbsp;paragraph</p><p>Second <b>bold</b> &amp; <i>italic</i></p>"),
+    "First paragraph Second bold & italic"
+  );
+  assert_eq!(
+    html_to_text("Before<script>var a = '<p>';</script><style>p { x: y }</style> after"),
+    "Before after"
+  );
+  assert_eq!(
+    html_to_text("<a href=\"x?a=1&b=2\" title='a > b'>link</a><!-- comment --> 1 < 2"),
+    "link 1 < 2"
+  );
+  assert_eq!(
+    html_to_text("Cut in the <a href=\"http://exa"),
+    "Cut in the"
+  );
+  assert_eq!(html_to_text("   \n "), "");
+}
+
+#[test]
+fn test_html_to_paragraphs() {
+  assert_eq!(
+    html_to_paragraphs(
+      "<h2>Title</h2>\n<p>First\n  paragraph with a <a href=\"x\">link</a>.</p><ul><li>One</li><li>Two</li></ul>Tail"
+    ),
+    "Title\n\nFirst paragraph with a link.\n\nOne\n\nTwo\n\nTail"
+  );
+  assert_eq!(html_to_paragraphs("<p> </p><br>"), "");
+}
+
+#[test]
+fn test_truncate() {
+  assert_eq!(truncate("Short enough.", 20), "Short enough.");
+  assert_eq!(truncate("Lorem ipsum dolor sit amet", 14), "Lorem ipsum...");
+  assert_eq!(truncate("Lorem ipsum dolor sit amet", 11), "Lorem ipsum...");
+  assert_eq!(
+    truncate("First sentence. Second sentence is long", 30),
+    "First sentence."
+  );
+  assert_eq!(
+    truncate("Hi. Then a long second sentence", 30),
+    "Hi. Then a long second..."
+  );
+  assert_eq!(
+    truncate("e.g. numbers like 3.14 are fine", 25),
+    "e.g. numbers like 3.14..."
+  );
+  assert_eq!(truncate("Supercalifragilistic", 5), "Super...");
+  assert_eq!(truncate("Supercalifragilistic", 0), "");
+  assert_eq!(truncate("Ünïcödé wörds äre fïne", 12), "Ünïcödé...");
+}
@@ -0,0 +1,248 @@
+use std::{
+  io::{BufRead, BufReader, Write},
+  net::TcpListener,
+  thread,
+};
+
+use chrono::{DateTime, Duration, Utc};
+use rss2email_lib::{
+  config::Config,
+  download_digest,
+  pipeline::{Filter, RecentPosts},
+  schedule::parse_timezone,
+  state::{self, FsStore},
+  summary::{Delivery, RunSummary},
+  window::{parse_duration, FuturePosts, Period, Window},
+  Blog, Post,
+};
+
+fn time(value: &str) -> DateTime<Utc> {
+  value
+    .parse::<DateTime<Utc>>()
+    .unwrap_or_else(|_| panic!("Invalid date {value}"))
+}
+
+fn hours(hours: i64) -> Duration {
+  Duration::try_hours(hours).expect("Valid duration")
+}
+
+/// A blog with a post for each of the given ages, titled after them.
+fn blog(ages: &[Duration]) -> Blog {
+  let now = Utc::now();
+  let posts = ages
+    .iter()
+    .map(|age| Post {
+      title: age.num_hours().to_string(),
+      pub_date: now - *age,
+      ..Post::default()
+    })
+    .collect::<Vec<_>>();
+
+  Blog {
+    most_recent_pub_date: posts.iter().map(|post| post.pub_date).max().unwrap_or(now),
+    posts,
+    ..Blog::default()
+  }
+}
+
+fn titles(blog: Option<Blog>) -> Vec<String> {
+  blog
+    .map(|blog| blog.posts.into_iter().map(|post| post.title).collect())
+    .unwrap_or_default()
+}
+
+#[test]
+fn test_parse_duration() {
+  assert_eq!(parse_duration("36h").expect("Valid"), hours(36));
+  assert_eq!(parse_duration("2d12h").expect("Valid"), hours(60));
+  assert_eq!(parse_duration(" 1W ").expect("Valid"), hours(168));
+  assert_eq!(
+    parse_duration("1h30m").expect("Valid"),
+    Duration::try_minutes(90).expect("Valid duration")
+  );
+  assert_eq!(parse_duration("0").expect("Valid"), Duration::zero());
+
+  for invalid in ["", "12", "h", "1y", "1.5h", "-1h"] {
+    assert!(
+      parse_duration(invalid).is_err(),
+      "{invalid} should be invalid"
+    );
+  }
+}
+
+#[test]
+fn test_calendar_windows() {
+  let berlin = parse_timezone("Europe/Berlin").expect("Valid timezone");
+  // A Wednesday, at 00:30 in Berlin.
+  let now = time("2024-05-14T22:30:00Z");
+
+  let window = Window::parse("yesterday", berlin).expect("Valid window");
+  assert_eq!(
+    window,
+    Window::Calendar {
+      period: Period::Yesterday,
+      timezone: berlin
+    }
+  );
+  assert_eq!(
+    window.bounds(now),
+    (
+      time("2024-05-13T22:00:00Z"),
+      Some(time("2024-05-14T22:00:00Z"))
+    )
+  );
+
+  let today = Window::parse("today", berlin).expect("Valid window");
+  assert_eq!(today.bounds(now), (time("2024-05-14T22:00:00Z"), None));
+
+  let last_week = Window::parse("last-week", berlin).expect("Valid window");
+  assert_eq!(
+    last_week.bounds(now),
+    (
+      time("2024-05-05T22:00:00Z"),
+      Some(time("2024-05-12T22:00:00Z"))
+    )
+  );
+
+  let this_week = Window::parse("this-week", berlin).expect("Valid window");
+  assert_eq!(this_week.bounds(now), (time("2024-05-12T22:00:00Z"), None));
+
+  assert_eq!(
+    Window::parse("36h", berlin).expect("Valid window"),
+    Window::Last(hours(36))
+  );
+  assert!(Window::parse("tomorrow", berlin).is_err());
+}
+
+#[test]
+fn test_recent_posts() {
+  let posts = || blog(&[hours(-3), hours(0), hours(23), hours(30), hours(50)]);
+
+  // DAYS=1 is exactly 24 hours, and posts from the future are left out.
+  let filtered = RecentPosts::new(1).apply("https://a.example", posts());
+  let filtered = filtered.expect("Posts are left");
+  assert!(filtered.most_recent_pub_date <= Utc::now());
+  assert_eq!(titles(Some(filtered)), ["0", "23"]);
+
+  // Or clamped to now.
+  let filtered = RecentPosts::new(1)
+    .with_future_posts(FuturePosts::Clamp)
+    .apply("https://a.example", posts());
+  assert_eq!(titles(filtered.clone()), ["-3", "0", "23"]);
+  assert!(filtered.expect("Posts are left").posts[0].pub_date <= Utc::now());
+
+  let filtered = RecentPosts::within(Window::Last(hours(36))).apply("https://a.example", posts());
+  assert_eq!(titles(filtered), ["0", "23", "30"]);
+
+  let filtered = RecentPosts::new(1)
+    .with_future_tolerance(hours(4))
+    .apply("https://a.example", posts());
+  assert_eq!(titles(filtered), ["-3", "0", "23"]);
+
+  let since = Window::Since(Utc::now() - hours(40));
+  assert_eq!(
+    titles(RecentPosts::within(since).apply("https://a.example", posts())),
+    ["0", "23", "30"]
+  );
+
+  assert!(RecentPosts::new(1)
+    .apply("https://a.example", blog(&[hours(48)]))
+    .is_none());
+}
+
+#[test]
+fn test_window_config() {
+  let dir = std::env::temp_dir().join(format!("rss2email-window-{}", std::process::id()));
+  let mut config = Config {
+    profile: Some("daily".to_owned()),
+    days: 2,
+    feeds: Some(vec!["https://a.example".to_owned()]),
+    window: Some("since-last-run".to_owned()),
+    state_backend: "fs".to_owned(),
+    state_dir: dir.clone(),
+    ..Config::default()
+  };
+  assert!(config
+    .validate_feeds()
+    .warnings
+    .iter()
+    .all(|w| !w.contains("WINDOW")));
+
+  // Until a run is recorded, DAYS is used.
+  assert_eq!(config.window().expect("Valid window"), Window::days(2));
+
+  let started_at = time("2024-05-14T07:00:00Z");
+  let summary = RunSummary {
+    profile: Some("daily".to_owned()),
+    delivery: Delivery::Sent,
+    ..RunSummary::default()
+  };
+  state::record_run(&FsStore::new(&dir), &summary, started_at).expect("Writable temp dir");
+  assert_eq!(
+    config.window().expect("Valid window"),
+    Window::Since(started_at)
+  );
+  std::fs::remove_dir_all(&dir).expect("Removable temp dir");
+
+  config.state_backend = "none".to_owned();
+  assert!(config
+    .validate_feeds()
+    .warnings
+    .iter()
+    .any(|w| w.contains("STATE_BACKEND is none")));
+
+  config.window = Some("2 days".to_owned());
+  config.future_tolerance = Some("soon".to_owned());
+  config.future_posts = Some("ignore".to_owned());
+  assert_eq!(config.validate_feeds().errors.len(), 3);
+  assert!(config.recent_posts().is_err());
+}
+
+/// `download_digest` keeps the posts of the last `days` days, whatever the configured
+/// window.
+#[test]
+fn test_download_digest_ignores_window() {
+  let listener = TcpListener::bind("127.0.0.1:0").expect("Free port");
+  let url = format!(
+    "http://{}/feed.xml",
+    listener.local_addr().expect("Bound address")
+  );
+  let feed = format!(
+    "<rss version=\"2.0\"><channel><title>Blog</title><link>https://blog.example</link>\
+     <item><title>Three days old</title><link>https://blog.example/post</link>\
+     <pubDate>{}</pubDate></item></channel></rss>",
+    (Utc::now() - hours(72)).to_rfc2822()
+  );
+  let server = thread::spawn(move || {
+    let (mut stream, _) = listener.accept().expect("A request");
+    let mut reader = BufReader::new(stream.try_clone().expect("Cloneable stream"));
+    let mut line = String::new();
+    while reader.read_line(&mut line).expect("Readable request") > 2 {
+      line.clear();
+    }
+    write!(
+      stream,
+      "HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\nContent-Length: {}\r\n\
+       Connection: close\r\n\r\n{feed}",
+      feed.len()
+    )
+    .expect("Writable stream");
+  });
+
+  std::env::set_var("FEEDS", &url);
+  std::env::set_var("WINDOW", "1d");
+  std::env::set_var("STATE_BACKEND", "none");
+  let digest = download_digest(7);
+  for name in ["FEEDS", "WINDOW", "STATE_BACKEND"] {
+    std::env::remove_var(name);
+  }
+  server.join().expect("The server finished");
+
+  assert!(digest.failed_feeds.is_empty());
+  let titles = digest
+    .blogs
+    .iter()
+    .flat_map(|blog| blog.posts.iter().map(|post| post.title.as_str()))
+    .collect::<Vec<_>>();
+  assert_eq!(titles, ["Three days old"]);
+}
@@ -1,10 +1,14 @@
 use chrono::{DateTime, Utc};
 use rss2email_lib::{
-  xml::{error::ParserError, parse_web_feed},
-  Blog, Post,
+  xml::{
+    error::{FeedFormat, ParserError},
+    parse_web_feed, parse_web_feed_with, ParseOptions,
+  },
+  Blog, Enclosure, Post,
 };
 
 use pretty_assertions::assert_eq;
+use url::Url;
 
 fn read_file(dir_name: &str, file_name: &str) -> String {
   use std::fs;
@@ -45,7 +49,12 @@ fn test_parse_brief_single_entry_atom() {
         link: "http://example.org/2003/12/13/atom03".into(),
         description: Some("Some text.".into()),
         pub_date: post_date("2003-12-13T18:30:02+00:00"),
+        authors: vec!["John Doe".into()],
+        id: Some("urn:uuid:1225c695-cfb8-4ebb-aaaa-80da344efa6a".into()),
+        ..Post::default()
       }],
+      link: Some("http://example.org/".into()),
+      ..Blog::default()
     }
   );
 }
@@ -55,7 +64,7 @@ fn test_parse_complex_single_entry_atom() {
   let content = read_atom("complex-single-entry.xml");
   let blog = parse_web_feed(&content).expect("Parsed content");
 
-  // The chosen link is the .../2005/04/02/atom because it is the first in the list
+  // The chosen link is the .../2005/04/02/atom because it is the alternate HTML one
   assert_eq!(
     blog,
     Blog {
@@ -64,9 +73,22 @@ fn test_parse_complex_single_entry_atom() {
       posts: vec![Post {
         title: "Atom draft-07 snapshot".into(),
         link: "http://example.org/2005/04/02/atom".into(),
-        description: None,
+        description: Some("[Update: The Atom draft is finished.]".into()),
+        content: Some("<p><i>[Update: The Atom draft is finished.]</i></p>".into()),
+        enclosures: vec![Enclosure {
+          url: "http://example.org/audio/ph34r_my_podcast.mp3".into(),
+          mime_type: Some("audio/mpeg".into()),
+          length: Some(1337),
+          duration: None,
+        }],
         pub_date: post_date("2003-12-13T08:29:29-04:00"),
+        authors: vec!["Mark Pilgrim".into()],
+        id: Some("tag:example.org,2003:3.2397".into()),
+        ..Post::default()
       }],
+      link: Some("http://example.org/".into()),
+      description: Some("A lot of effort went into making this effortless".into()),
+      ..Blog::default()
     }
   );
 }
@@ -78,13 +100,42 @@ fn test_parse_atom_without_entry() {
   assert!(result.is_err());
 
   let is_empty_feed_error = match result.expect_err("Should error") {
-    ParserError::Parse(p) => p.contains("Empty feed"),
-    ParserError::Date(_) => false,
+    ParserError::UnknownFormat { atom, .. } => matches!(
+      *atom,
+      ParserError::Parse { format: FeedFormat::Atom, position: None, ref message, .. }
+        if message.contains("Empty feed")
+    ),
+    _ => false,
   };
 
   assert!(is_empty_feed_error);
 }
 
+#[test]
+fn test_parse_error_position() {
+  let result = parse_web_feed("<rss><channel><title>Blog</titl></channel></rss>");
+
+  let Err(ParserError::UnknownFormat { rss, atom }) = result else {
+    panic!("Should error, got {result:?}");
+  };
+  assert!(matches!(
+    *rss,
+    ParserError::Parse {
+      format: FeedFormat::Rss,
+      position: Some(_),
+      ..
+    }
+  ));
+  assert!(matches!(
+    *atom,
+    ParserError::Parse {
+      format: FeedFormat::Atom,
+      position: Some(_),
+      ..
+    }
+  ));
+}
+
 #[test]
 fn test_parse_atom_with_many_entries() {
   let content = read_atom("multi-entries.xml");
@@ -103,14 +154,18 @@ fn test_parse_atom_with_many_entries() {
           link: "http://awesome.com/link1.html".into(),
           description: Some("First content".into()),
           pub_date: first_date,
+          ..Post::default()
         },
         Post {
           title: "Second title".into(),
           link: "http://com.net/why-not.html".into(),
           description: None,
           pub_date: second_date,
+          ..Post::default()
         }
       ],
+      link: Some("http://example.org/".into()),
+      ..Blog::default()
     }
   );
 }
@@ -129,12 +184,17 @@ fn test_parse_atom_text_with_html_tags() {
       posts: vec![Post {
         title: "<b>Star</b> City".into(),
         link: "http://link.com".into(),
-        description: Some(
-          "How did it work? <i>Details</i> <a href=\"http://liftoff.msfc.nasa.gov\">here</a>"
+        description: Some("How did it work? Details here".into()),
+        // The links of the description are kept in the content.
+        content: Some(
+          r#"How did it work? <i>Details</i> <a href="http://liftoff.msfc.nasa.gov" rel="noopener noreferrer">here</a>"#
             .into()
         ),
         pub_date: date,
+        ..Post::default()
       }],
+      link: Some("http://example.org/".into()),
+      ..Blog::default()
     }
   );
 }
@@ -174,22 +234,42 @@ fn test_parse_rss_2() {
       posts: vec![Post {
         title: "Star City".into(),
         link:"http://liftoff.msfc.nasa.gov/news/2003/news-starcity.asp".into(),
-        description: Some("How do Americans get ready to work with Russians aboard the International Space Station? They take a crash course in culture, language and protocol at Russia's <a href=\"http://howe.iki.rssi.ru/GCTC/gc...".into()),
+        description: Some("How do Americans get ready to work with Russians aboard the International Space Station? They take a crash course in culture, language and protocol at Russia's Star City.".into()),
+        content: Some(r#"How do Americans get ready to work with Russians aboard the International Space Station? They take a crash course in culture, language and protocol at Russia's <a href="http://howe.iki.rssi.ru/GCTC/gctc_e.htm" rel="noopener noreferrer">Star City</a>."#.into()),
         pub_date: post_date("2003-06-03T09:39:21+00:00"),
+        id: Some("http://liftoff.msfc.nasa.gov/2003/06/03.html#item573".into()),
+        ..Post::default()
+      },
+      // Sky watchers post has no link, its guid is a permalink
+       Post {
+        title: "Sky watchers in Europe, Asia, and parts of Alaska...".into(),
+        link: "http://liftoff.msfc.nasa.gov/2003/05/30.html#item572".into(),
+        description: Some("Sky watchers in Europe, Asia, and parts of Alaska and Canada will experience a partial eclipse of the Sun on Saturday, May 31st.".into()),
+        content: Some(r#"Sky watchers in Europe, Asia, and parts of Alaska and Canada will experience a <a href="http://science.nasa.gov/headlines/y2003/30may_solareclipse.htm" rel="noopener noreferrer">partial eclipse of the Sun</a> on Saturday, May 31st."#.into()),
+        pub_date: post_date("2003-05-30T11:06:42+00:00"),
+        id: Some("http://liftoff.msfc.nasa.gov/2003/05/30.html#item572".into()),
+        ..Post::default()
       },
-      // Sky watchers post ignored as not containing link
        Post {
         title: "The Engine That Does More".into(),
         link:"http://liftoff.msfc.nasa.gov/news/2003/news-VASIMR.asp".into(),
-        description: Some("Before man travels to Mars, NASA hopes to design new engines that will let us fly through the Solar System more quickly.  The proposed VASIMR engine would do that.".into()),
+        description: Some("Before man travels to Mars, NASA hopes to design new engines that will let us fly through the Solar System more quickly. The proposed VASIMR engine would do that.".into()),
         pub_date: post_date("2003-05-27T08:37:32+00:00"),
+        id: Some("http://liftoff.msfc.nasa.gov/2003/05/27.html#item571".into()),
+        ..Post::default()
       },
        Post {
         title: "Astronauts' Dirty Laundry".into(),
         link:"http://liftoff.msfc.nasa.gov/news/2003/news-laundry.asp".into(),
-        description: Some("Compared to earlier spacecraft, the International Space Station has many luxuries, but laundry facilities are not one of them.  Instead, astronauts have other options.".into()),
+        description: Some("Compared to earlier spacecraft, the International Space Station has many luxuries, but laundry facilities are not one of them. Instead, astronauts have other options.".into()),
         pub_date: post_date("2003-05-20T08:56:02+00:00"),
+        id: Some("http://liftoff.msfc.nasa.gov/2003/05/20.html#item570".into()),
+        ..Post::default()
       }],
+      link: Some("http://liftoff.msfc.nasa.gov/".into()),
+      description: Some("Liftoff to Space Exploration.".into()),
+      language: Some("en-us".into()),
+      ..Blog::default()
     });
 }
 
@@ -205,12 +285,16 @@ fn test_parse_rss_text_with_html_tags() {
       posts: vec![Post {
         title: "<b>Star</b> City".into(),
         link: "http://liftoff.msfc.nasa.gov/news/2003/news-starcity.asp".into(),
-        description: Some(
-          "How did it work? <i>Details</i> <a href=\"http://liftoff.msfc.nasa.gov\">here</a>"
+        description: Some("How did it work? Details here".into()),
+        // The links of the description are kept in the content.
+        content: Some(
+          r#"How did it work? <i>Details</i> <a href="http://liftoff.msfc.nasa.gov" rel="noopener noreferrer">here</a>"#
             .into()
         ),
         pub_date: post_date("2003-06-03T09:39:21+00:00"),
+        ..Post::default()
       }],
+      ..Blog::default()
     }
   );
 }
@@ -224,7 +308,8 @@ fn test_parse_rss_without_items() {
     Blog {
       title: "NoNews".into(),
       most_recent_pub_date: post_date("2003-06-10T04:00:00+00:00"),
-      posts: vec![]
+      posts: vec![],
+      ..Blog::default()
     }
   );
 }
@@ -246,14 +331,17 @@ fn test_parse_rss_entry_without_link() {
           link: "http://abc.com".into(),
           description: None,
           pub_date: post_date("2003-06-03T09:39:21+00:00"),
+          ..Post::default()
         },
         Post {
           title: "Planet City".into(),
           link: "http://def.com".into(),
           description: Some("def".into()),
           pub_date: post_date("2003-06-03T09:39:21+00:00"),
+          ..Post::default()
         }
       ],
+      ..Blog::default()
     }
   );
 }
@@ -272,7 +360,9 @@ fn test_parse_rss_entry_without_title_and_short_description() {
         link: "http://liftoff.msfc.nasa.gov".into(),
         description: None,
         pub_date: post_date("2003-06-03T09:39:21+00:00"),
+        ..Post::default()
       }],
+      ..Blog::default()
     }
   );
 }
@@ -287,14 +377,414 @@ fn test_parse_rss_entry_without_title_and_long_description() {
       title: "Liftoff News".into(),
       most_recent_pub_date: post_date("2003-06-10T04:00:00+00:00"),
       posts: vec![Post {
-        title: "How do Americans get ready to work with Russians a...".into(),
+        title: "How do Americans get ready to work with Russians...".into(),
         link: "http://liftoff.msfc.nasa.gov".into(),
         description: Some(
           "How do Americans get ready to work with Russians aboard the International Space Station? They take a crash course in culture, language and protocol at Russia's Star City."
             .into()
         ),
         pub_date: post_date("2003-06-03T09:39:21+00:00"),
+        ..Post::default()
       }],
+      ..Blog::default()
     }
   );
 }
+
+#[test]
+fn test_parse_rss_with_description_length() {
+  let content = read_rss("sample-2.xml");
+  let blog = parse_web_feed_with(
+    &content,
+    &ParseOptions {
+      description_length: 100,
+      ..ParseOptions::default()
+    },
+  )
+  .expect("Parsed content");
+
+  let descriptions = blog
+    .posts
+    .iter()
+    .map(|post| post.description.as_deref())
+    .collect::<Vec<_>>();
+
+  assert_eq!(
+    descriptions,
+    vec![
+      Some("How do Americans get ready to work with Russians aboard the International Space Station?"),
+      Some("Sky watchers in Europe, Asia, and parts of Alaska and Canada will experience a partial eclipse of..."),
+      Some("Before man travels to Mars, NASA hopes to design new engines that will let us fly through the Solar..."),
+      Some("Compared to earlier spacecraft, the International Space Station has many luxuries, but laundry..."),
+    ]
+  );
+}
+
+#[test]
+fn test_parse_rss_content_encoded() {
+  let content = read_rss("v2-with-content-encoded.xml");
+  let blog = parse_web_feed(&content).expect("Parsed content");
+
+  assert_eq!(
+    blog.posts[0].description.as_deref(),
+    Some("Full article & more.")
+  );
+  assert_eq!(
+    blog.posts[0].content.as_deref(),
+    Some("<p>Full <b>article</b> &amp; more.</p>")
+  );
+
+  let options = ParseOptions {
+    base_url: Some(Url::parse("http://liftoff.msfc.nasa.gov/rss").expect("Valid URL")),
+    ..ParseOptions::default()
+  };
+  let blog = parse_web_feed_with(&content, &options).expect("Parsed content");
+  assert_eq!(
+    blog.posts[1].content.as_deref(),
+    Some(
+      r#"<h2>Heading</h2><img src="http://liftoff.msfc.nasa.gov/images/launch.jpg" alt="Launch">"#
+    )
+  );
+}
+
+#[test]
+fn test_parse_atom_content() {
+  let content = read_atom("entry-with-content.xml");
+  let blog = parse_web_feed(&content).expect("Parsed content");
+
+  let posts = blog
+    .posts
+    .iter()
+    .map(|post| (post.description.as_deref(), post.content.as_deref()))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    posts,
+    [
+      (
+        Some("A summary."),
+        Some(
+          r#"<p>See <a href="https://cdn.example.net/posts/other.html" rel="noopener noreferrer">this</a>.</p>"#
+        )
+      ),
+      (
+        Some("An inline."),
+        Some(r#"<p>An <img src="https://example.org/blog/image.png" alt="image"> inline.</p>"#)
+      ),
+      (None, None),
+    ]
+  );
+}
+
+#[test]
+fn test_parse_rss_categories() {
+  let content = read_rss("v2-with-categories.xml");
+  let blog = parse_web_feed(&content).expect("Parsed content");
+
+  assert_eq!(blog.posts[0].categories, ["Space", "Training"]);
+}
+
+#[test]
+fn test_parse_atom_categories() {
+  let content = read_atom("entry-with-categories.xml");
+  let blog = parse_web_feed(&content).expect("Parsed content");
+
+  assert_eq!(blog.posts[0].categories, ["rust", "release"]);
+}
+
+#[test]
+fn test_parse_rss_authors() {
+  let content = read_rss("v2-with-authors.xml");
+  let options = ParseOptions {
+    base_url: Some(Url::parse("http://liftoff.msfc.nasa.gov/rss").expect("Valid URL")),
+    ..ParseOptions::default()
+  };
+  let blog = parse_web_feed_with(&content, &options).expect("Parsed content");
+
+  assert_eq!(blog.most_recent_pub_date, post_date("2003-06-10T04:00:00Z"));
+  assert_eq!(blog.posts[0].authors, ["Jane Doe", "John Smith"]);
+  assert_eq!(
+    blog.posts[0].comments.as_deref(),
+    Some("http://liftoff.msfc.nasa.gov/news/2003/news-starcity.asp#comments")
+  );
+  assert_eq!(
+    blog.posts[0].pub_date,
+    post_date("2003-06-03T09:39:21+02:00")
+  );
+  assert_eq!(blog.posts[1].authors, ["editor@example.com"]);
+  assert_eq!(blog.posts[1].comments, None);
+  assert_eq!(blog.posts[1].pub_date, post_date("2003-05-30T00:00:00Z"));
+}
+
+#[test]
+fn test_parse_atom_authors() {
+  let content = read_atom("entry-with-authors.xml");
+  let blog = parse_web_feed(&content).expect("Parsed content");
+
+  assert_eq!(blog.posts[0].authors, ["Jane Doe", "John Smith"]);
+  assert_eq!(
+    blog.posts[0].comments.as_deref(),
+    Some("http://example.org/two.html#comments")
+  );
+  assert_eq!(blog.posts[1].authors, ["The Team"]);
+  assert_eq!(blog.posts[1].comments, None);
+  assert_eq!(
+    blog.posts[1].pub_date,
+    post_date("2022-09-17T21:00:00+02:00")
+  );
+}
+
+#[test]
+fn test_parse_rss_feed_metadata() {
+  let content = read_rss("v2-with-metadata.xml");
+  let options = ParseOptions {
+    base_url: Some(Url::parse("https://liftoff.example/rss").expect("Valid URL")),
+    ..ParseOptions::default()
+  };
+  let blog = parse_web_feed_with(&content, &options).expect("Parsed content");
+
+  assert_eq!(blog.link.as_deref(), Some("https://liftoff.example/"));
+  assert_eq!(
+    blog.description.as_deref(),
+    Some("Liftoff to Space Exploration.")
+  );
+  assert_eq!(
+    blog.icon.as_deref(),
+    Some("https://liftoff.example/images/logo.png")
+  );
+  assert_eq!(blog.language.as_deref(), Some("de"));
+  assert_eq!(blog.feed_url, None);
+}
+
+#[test]
+fn test_parse_atom_feed_metadata() {
+  let content = read_atom("feed-metadata.xml");
+  let blog = parse_web_feed(&content).expect("Parsed content");
+
+  assert_eq!(blog.link.as_deref(), Some("https://example.org/blog/"));
+  assert_eq!(blog.description.as_deref(), Some("Notes du jour"));
+  assert_eq!(
+    blog.icon.as_deref(),
+    Some("https://example.org/favicon.ico")
+  );
+  assert_eq!(blog.language.as_deref(), Some("fr"));
+  assert_eq!(blog.posts[0].link, "https://example.org/blog/bonjour.html");
+}
+
+#[test]
+fn test_parse_rss_feedburner_orig_link() {
+  let content = read_rss("v2-feedburner.xml");
+  let blog = parse_web_feed(&content).expect("Parsed content");
+
+  let links = blog
+    .posts
+    .iter()
+    .map(|post| post.link.as_str())
+    .collect::<Vec<_>>();
+  assert_eq!(
+    links,
+    [
+      "http://liftoff.msfc.nasa.gov/news/2003/news-starcity.asp",
+      "http://feedproxy.google.com/~r/liftoff/~3/a4b5c6/news-VASIMR.asp"
+    ]
+  );
+}
+
+fn links(blog: &Blog) -> Vec<&str> {
+  blog.posts.iter().map(|post| post.link.as_str()).collect()
+}
+
+#[test]
+fn test_parse_atom_relative_links() {
+  let content = read_atom("relative-links.xml");
+  let options = ParseOptions {
+    base_url: Some(Url::parse("https://example.org/feeds/atom.xml").expect("Valid URL")),
+    ..ParseOptions::default()
+  };
+  let blog = parse_web_feed_with(&content, &options).expect("Parsed content");
+
+  assert_eq!(
+    links(&blog),
+    [
+      "https://example.org/blog/posts/first.html",
+      "https://cdn.example.net/mirror/second.html",
+      "https://example.org/blog/third.html?a=1#top",
+      "http://Example.org",
+    ]
+  );
+
+  // Without the feed URL, only the absolute bases are used.
+  let blog = parse_web_feed(&content).expect("Parsed content");
+  assert_eq!(
+    links(&blog),
+    [
+      "posts/first.html",
+      "https://cdn.example.net/mirror/second.html",
+      "../third.html?a=1#top",
+      "http://Example.org",
+    ]
+  );
+}
+
+#[test]
+fn test_parse_rss_relative_links() {
+  let content = read_rss("v2-with-relative-links.xml");
+  let options = ParseOptions {
+    base_url: Some(Url::parse("https://example.org/rss").expect("Valid URL")),
+    ..ParseOptions::default()
+  };
+  let blog = parse_web_feed_with(&content, &options).expect("Parsed content");
+
+  assert_eq!(
+    links(&blog),
+    [
+      "https://liftoff.example/news/2003/news-starcity.asp",
+      "https://liftoff.example/archive/news-VASIMR.asp",
+      "https://liftoff.example/posts/570",
+    ]
+  );
+  assert_eq!(
+    blog.posts[1].content.as_deref(),
+    Some(
+      r#"<a href="https://liftoff.example/archive/news-VASIMR-2.asp" rel="noopener noreferrer">Read more</a>"#
+    )
+  );
+}
+
+#[test]
+fn test_parse_atom_link_relations() {
+  let content = read_atom("link-relations.xml");
+  let blog = parse_web_feed(&content).expect("Parsed content");
+
+  // The last entry only links to the feed and is skipped.
+  assert_eq!(
+    links(&blog),
+    [
+      "http://example.org/first.html",
+      "http://example.org/second.html",
+      "http://example.org/third.html",
+      "http://example.org/fourth.html",
+    ]
+  );
+
+  // Links without an `href` are ignored rather than failing the feed.
+  assert!(blog.posts[3].enclosures.is_empty());
+  assert_eq!(blog.posts[3].comments, None);
+}
+
+#[test]
+fn test_parse_atom_text_constructs() {
+  let content = read_atom("text-constructs.xml");
+  let blog = parse_web_feed(&content).expect("Parsed content");
+
+  assert_eq!(blog.title, "Text & Markup");
+  let posts = blog
+    .posts
+    .iter()
+    .map(|post| (post.title.as_str(), post.description.as_deref()))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    posts,
+    [
+      ("Bold claims", Some("An escaped summary")),
+      ("An inline title", Some("A structured summary")),
+      ("Plain <tags> stay", None),
+      ("Quoted base", Some("Linked summary")),
+    ]
+  );
+
+  // The quotes in the single-quoted `xml:base` of an xhtml summary are kept escaped.
+  assert_eq!(
+    blog.posts[3].content.as_deref(),
+    Some(
+      r#"<a href="https://example.org/%22quoted%22/summary.html" rel="noopener noreferrer">Linked</a> summary"#
+    )
+  );
+}
+
+#[test]
+fn test_parse_rss_podcast() {
+  let content = read_rss("v2-podcast.xml");
+  let options = ParseOptions {
+    base_url: Some(Url::parse("https://liftoff.example/feed.xml").expect("Valid URL")),
+    ..ParseOptions::default()
+  };
+  let blog = parse_web_feed_with(&content, &options).expect("Parsed content");
+
+  // The `itunes:title` and `itunes:summary` do not clash with `title` and `description`.
+  assert_eq!(blog.posts[0].title, "Star City");
+  assert_eq!(
+    blog.posts[0].description.as_deref(),
+    Some("Training with the cosmonauts.")
+  );
+
+  let attachments = blog
+    .posts
+    .iter()
+    .map(|post| (post.enclosures.clone(), post.thumbnail.as_deref()))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    attachments,
+    [
+      (
+        vec![Enclosure {
+          url: "https://liftoff.example/audio/1.mp3".into(),
+          mime_type: Some("audio/mpeg".into()),
+          length: Some(24_986_239),
+          duration: Some(1571),
+        }],
+        Some("https://liftoff.example/episodes/1.jpg")
+      ),
+      (
+        vec![Enclosure {
+          url: "https://liftoff.example/audio/2.m4a".into(),
+          mime_type: Some("audio/x-m4a".into()),
+          length: None,
+          duration: Some(3723),
+        }],
+        Some("https://liftoff.example/images/2.jpg")
+      ),
+      (
+        vec![Enclosure {
+          url: "https://liftoff.example/videos/3.mp4".into(),
+          mime_type: Some("video/mp4".into()),
+          length: None,
+          duration: Some(95),
+        }],
+        Some("https://liftoff.example/videos/3.jpg")
+      ),
+      // Enclosures and images without a URL are skipped rather than failing the feed.
+      (
+        vec![Enclosure {
+          url: "https://liftoff.example/audio/4.mp3".into(),
+          mime_type: Some("audio/mpeg".into()),
+          length: None,
+          duration: None,
+        }],
+        None
+      ),
+    ]
+  );
+  assert_eq!(
+    blog.posts[2].description.as_deref(),
+    Some("A launch, filmed from the pad.")
+  );
+}
+
+#[test]
+fn test_parse_atom_youtube() {
+  let content = read_atom("youtube.xml");
+  let blog = parse_web_feed(&content).expect("Parsed content");
+
+  assert_eq!(
+    blog.posts,
+    [Post {
+      title: "Launch day".into(),
+      link: "https://www.youtube.com/watch?v=abcdefghijk".into(),
+      description: Some("Everything that happened on launch day.".into()),
+      pub_date: post_date("2024-05-01T12:00:00+00:00"),
+      id: Some("yt:video:abcdefghijk".into()),
+      thumbnail: Some("https://i1.ytimg.com/vi/abcdefghijk/hqdefault.jpg".into()),
+      ..Post::default()
+    }]
+  );
+}